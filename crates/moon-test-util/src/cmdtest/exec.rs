@@ -105,7 +105,10 @@ fn run_process(
     toolchain_root: Option<&Path>,
 ) -> CommandOutput {
     let mut command = Command::new(program);
-    command.args(args).current_dir(workdir);
+    command
+        .args(args)
+        .current_dir(workdir)
+        .env("MOON_BUILD_CACHE", "off");
     if let Some(toolchain_root) = toolchain_root {
         command.env("MOON_TOOLCHAIN_ROOT", toolchain_root);
     }
@@ -20,6 +20,7 @@ use anyhow::bail;
 use moonutil::{
     build_options::RunMode,
     cond_expr::OptLevel as BuildProfile,
+    message_format::MessageFormat,
     target::{SurfaceTarget, TargetBackend},
     test_metadata::DiagnosticLevel,
 };
@@ -87,6 +88,10 @@ pub struct BuildFlags {
     #[clap(long, conflicts_with = "no_render")]
     pub output_json: bool,
 
+    /// Output diagnostics in a format for CI tools
+    #[clap(long, value_enum, value_name = "FORMAT", conflicts_with_all = ["no_render", "output_json"])]
+    pub message_format: Option<MessageFormat>,
+
     /// Warn list config
     #[clap(long, allow_hyphen_values = true)]
     pub warn_list: Option<String>,
@@ -126,6 +131,7 @@ impl Default for BuildFlags {
             deny_warn: false,
             no_render: false,
             output_json: false,
+            message_format: None,
             warn_list: None,
             enable_value_tracing: false,
             jobs: None,
@@ -163,17 +169,25 @@ pub enum OutputStyle {
     Fancy,
     /// Machine-readable output in JSON
     Json,
+    /// A report for CI tools, from `--message-format`
+    Message(MessageFormat),
 }
 
 impl OutputStyle {
     /// Whether the output style requires `moonc` to emit JSON diagnostics.
     pub fn needs_moonc_json(&self) -> bool {
-        matches!(self, OutputStyle::Fancy | OutputStyle::Json)
+        matches!(
+            self,
+            OutputStyle::Fancy | OutputStyle::Json | OutputStyle::Message(_)
+        )
     }
 
     /// Whether the output style requires no rendering (i.e., raw diagnostics).
     pub fn needs_no_render(&self) -> bool {
-        matches!(self, OutputStyle::Raw | OutputStyle::Json)
+        matches!(
+            self,
+            OutputStyle::Raw | OutputStyle::Json | OutputStyle::Message(_)
+        )
     }
 }
 
@@ -241,6 +255,9 @@ impl BuildFlags {
     }
 
     pub fn output_style(&self) -> OutputStyle {
+        if let Some(format) = self.message_format {
+            return OutputStyle::Message(format);
+        }
         match (self.no_render, self.output_json) {
             (true, false) => OutputStyle::Raw,
             (false, true) => OutputStyle::Json,
@@ -70,7 +70,9 @@ pub(crate) use generate_test_driver::*;
 pub(crate) use info::*;
 use moonbuild::upgrade::UpgradeSubcommand;
 use mooncake::pkg::{
-    add::AddSubcommand, install::InstallSubcommand, remove::RemoveSubcommand, tree::TreeSubcommand,
+    add::AddSubcommand, install::InstallSubcommand, licenses::LicensesSubcommand,
+    outdated::OutdatedSubcommand, remove::RemoveSubcommand, tree::TreeSubcommand,
+    upgrade_deps::UpgradeDepsSubcommand, vendor::VendorSubcommand,
 };
 use moonutil::cli_support::{
     LoginSubcommand, PackageSubcommand, PublishSubcommand, RegisterSubcommand, UniversalFlags,
@@ -135,6 +137,10 @@ pub(crate) enum MoonBuildSubcommands {
     Remove(RemoveSubcommand),
     Install(InstallSubcommand),
     Tree(TreeSubcommand),
+    Outdated(OutdatedSubcommand),
+    UpgradeDeps(UpgradeDepsSubcommand),
+    Vendor(VendorSubcommand),
+    Licenses(LicensesSubcommand),
     Fetch(FetchSubcommand),
     Work(WorkSubcommand),
 
@@ -64,6 +64,41 @@ pub(crate) struct BenchSubcommand {
     /// Run the benchmarks in a target backend sequentially
     #[clap(long)]
     pub no_parallelize: bool,
+
+    /// Save the results of this run as a named baseline under the target directory
+    #[clap(long, value_name = "NAME", value_parser = parse_baseline_name, conflicts_with = "build_only")]
+    pub save_baseline: Option<String>,
+
+    /// Compare the results of this run with a baseline saved by `--save-baseline`, and exit
+    /// with a non-zero status if any benchmark regressed
+    #[clap(long, value_name = "NAME", value_parser = parse_baseline_name, conflicts_with = "build_only")]
+    pub baseline: Option<String>,
+
+    /// Slowdown of the mean, in percent, above which a significant change against `--baseline`
+    /// counts as a regression
+    #[clap(
+        long,
+        value_name = "PERCENT",
+        default_value_t = 5.0,
+        requires = "baseline"
+    )]
+    pub regression_threshold: f64,
+}
+
+/// Baselines are stored as files named after them, so the name must not
+/// reach outside the baseline directory.
+fn parse_baseline_name(name: &str) -> Result<String, String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "invalid baseline name `{name}`, use letters, digits, `-`, `_` and `.`"
+        ));
+    }
+    Ok(name.to_string())
 }
 
 #[instrument(skip_all)]
@@ -271,7 +271,8 @@ fn run_build_rr_from_resolved(
         })?;
         true
     } else {
-        let cfg = BuildConfig::from_flags(&cmd.build_flags, &cli.unstable_feature, cli.verbose);
+        let cfg = BuildConfig::from_flags(&cmd.build_flags, &cli.unstable_feature, cli.verbose)
+            .with_source_dir(source_dir);
         for (build_meta, _) in &planned_runs {
             rr_build::generate_all_pkgs_json(build_meta)?;
         }
@@ -189,7 +189,8 @@ fn run_bundle_rr_from_resolved(
         let build_input = rr_build::compose_build_inputs(build_inputs)?;
 
         let result = rr_build::execute_build(
-            &BuildConfig::from_flags(&cmd.build_flags, &cli.unstable_feature, cli.verbose),
+            &BuildConfig::from_flags(&cmd.build_flags, &cli.unstable_feature, cli.verbose)
+                .with_source_dir(source_dir),
             build_input,
             target_dir,
             user_log,
@@ -205,8 +206,8 @@ fn sync_and_resolve_bundle_project(
     dirs: &PackageDirs,
     user_log: &UserLog,
 ) -> anyhow::Result<moonbuild_rupes_recta::ResolveOutput> {
-    let resolve_config = moonbuild_rupes_recta::ResolveConfig::new_with_load_defaults(
-        cmd.auto_sync_flags.frozen,
+    let resolve_config = moonbuild_rupes_recta::ResolveConfig::new(
+        cmd.auto_sync_flags.clone(),
         !cmd.build_flags.std(),
         cmd.build_flags.enable_coverage,
         cli.workspace_env.clone(),
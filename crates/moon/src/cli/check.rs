@@ -32,6 +32,7 @@
 //!
 use anyhow::Context;
 use log::LevelFilter;
+use moonbuild_rupes_recta::build_lower::WarningCondition;
 use moonbuild_rupes_recta::intent::UserIntent;
 use moonbuild_rupes_recta::model::PackageId;
 use moonutil::build_options::RunMode;
@@ -257,6 +258,43 @@ pub(crate) struct CheckSubcommand {
     /// Output one complete JSON result to stdout
     #[clap(long)]
     pub json: bool,
+
+    /// Hide the diagnostics recorded in the given baseline file, reporting
+    /// only new ones. With `--deny-warn`, only new warnings fail the check
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["write_baseline", "no_render"])]
+    pub baseline: Option<PathBuf>,
+
+    /// Record the current diagnostics to the given baseline file
+    #[clap(long, value_name = "FILE", conflicts_with_all = ["watch", "no_render"])]
+    pub write_baseline: Option<PathBuf>,
+}
+
+impl CheckSubcommand {
+    fn baseline_config(&self, source_dir: &Path) -> Option<rr_build::BaselineConfig> {
+        let (file, mode) = match (&self.baseline, &self.write_baseline) {
+            (Some(file), _) => (
+                file,
+                rr_build::BaselineMode::Suppress {
+                    deny_new_warnings: self.build_flags.deny_warn,
+                },
+            ),
+            (None, Some(file)) => (file, rr_build::BaselineMode::Write),
+            (None, None) => return None,
+        };
+        Some(rr_build::BaselineConfig {
+            file: file.clone(),
+            root: source_dir.to_path_buf(),
+            mode,
+        })
+    }
+
+    /// With a baseline, `--deny-warn` is enforced on new warnings after the
+    /// build, so `moonc` must not turn the baselined ones into errors.
+    fn relax_deny_warn_for_baseline(&self, preconfig: &mut rr_build::CompilePreConfig) {
+        if self.baseline.is_some() {
+            preconfig.warning_condition = WarningCondition::Default;
+        }
+    }
 }
 
 pub(crate) fn write_check_json(
@@ -317,6 +355,7 @@ pub(crate) fn run_check_json(
         (cmd.fmt, "--fmt"),
         (cmd.build_flags.no_render, "--no-render"),
         (cmd.build_flags.output_json, "--output-json"),
+        (cmd.build_flags.message_format.is_some(), "--message-format"),
     ]
     .into_iter()
     .filter_map(|(enabled, flag)| enabled.then_some(flag))
@@ -557,7 +596,7 @@ fn run_check_for_single_file_rr(
         .or(cmd.build_flags.resolve_single_target_backend()?)
         .or(backend);
 
-    let preconfig = preconfig_compile(
+    let mut preconfig = preconfig_compile(
         &cmd.auto_sync_flags,
         cli,
         &cmd.build_flags,
@@ -565,6 +604,7 @@ fn run_check_for_single_file_rr(
         target_dir,
         RunMode::Check,
     );
+    cmd.relax_deny_warn_for_baseline(&mut preconfig);
 
     let planning_context = rr_build::prepare_resolved_build(
         &preconfig,
@@ -618,15 +658,18 @@ fn run_check_for_single_file_rr(
         &cmd.build_flags,
         &cli.unstable_feature,
         cli.verbose && json.is_none(),
-    );
+    )
+    .with_source_dir(source_dir);
     cfg.patch_file = cmd.patch_file.clone();
     cfg.explain_errors |= cmd.explain;
+    cfg.baseline = cmd.baseline_config(source_dir);
 
     if let Some(json) = json {
         let result = rr_build::execute_build_json(
             &cfg.with_suppressed_progress(true),
             build_graph,
             target_dir,
+            user_log,
         )?;
         let successful = result.successful();
         json.append_build(result, user_log);
@@ -824,9 +867,11 @@ fn run_check_normal_rr_from_resolved(
             &cmd.build_flags,
             &cli.unstable_feature,
             cli.verbose && json.is_none(),
-        );
+        )
+        .with_source_dir(source_dir);
         cfg.patch_file = cmd.patch_file.clone();
         cfg.explain_errors |= cmd.explain;
+        cfg.baseline = cmd.baseline_config(source_dir);
         for (build_meta, build_input) in &planned_runs {
             // Generate all_pkgs.json for indirect dependency resolution
             rr_build::generate_all_pkgs_json(build_meta)?;
@@ -852,6 +897,7 @@ fn run_check_normal_rr_from_resolved(
                 &cfg.with_suppressed_progress(true),
                 build_input,
                 target_dir,
+                user_log,
             )?;
             let successful = result.successful();
             json.append_build(result, user_log);
@@ -987,7 +1033,7 @@ pub(crate) fn plan_check_rr_from_resolved(
     resolve_output: moonbuild_rupes_recta::ResolveOutput,
     user_log: &UserLog,
 ) -> anyhow::Result<(rr_build::BuildMeta, rr_build::BuildInput)> {
-    let preconfig = preconfig_compile(
+    let mut preconfig = preconfig_compile(
         &cmd.auto_sync_flags,
         cli,
         &cmd.build_flags,
@@ -995,6 +1041,7 @@ pub(crate) fn plan_check_rr_from_resolved(
         target_dir,
         RunMode::Check,
     );
+    cmd.relax_deny_warn_for_baseline(&mut preconfig);
 
     let planning_context = rr_build::prepare_resolved_build(
         &preconfig,
@@ -1042,7 +1089,7 @@ fn plan_check_rr_from_selection(
     selection: ResolvedCheckSelection,
     user_log: &UserLog,
 ) -> anyhow::Result<(rr_build::BuildMeta, rr_build::BuildInput)> {
-    let preconfig = preconfig_compile(
+    let mut preconfig = preconfig_compile(
         &cmd.auto_sync_flags,
         cli,
         &cmd.build_flags,
@@ -1050,6 +1097,7 @@ fn plan_check_rr_from_selection(
         target_dir,
         RunMode::Check,
     );
+    cmd.relax_deny_warn_for_baseline(&mut preconfig);
 
     let planning_context = rr_build::prepare_resolved_build(
         &preconfig,
@@ -18,14 +18,26 @@
 
 //! CLI and utilities related to code coverage.
 
-use std::{ffi::OsStr, io::Write, path::Path};
+mod diff;
+mod formats;
+mod line_coverage;
+
+use std::{
+    ffi::OsStr,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Context;
 use clap::Parser;
-use moonutil::{command_output::CommandOutput, project::PackageDirs, user_log::UserLog};
+use moonutil::{
+    command_output::CommandOutput, locks::lock_directory, project::PackageDirs, user_log::UserLog,
+};
 use walkdir::WalkDir;
 
+use self::line_coverage::{LineCoverage, PackageNames, PackageThreshold};
 use super::{TestSubcommand, UniversalFlags, run_test};
+use crate::rr_build;
 
 #[derive(Debug, clap::Parser, Default)]
 #[clap(
@@ -41,6 +53,39 @@ pub(crate) struct CoverageReportSubcommand {
     /// Show help for the coverage utility
     #[clap(short, long)]
     pub help: bool,
+
+    /// Write an lcov tracefile to the given path
+    #[clap(long, value_name = "FILE")]
+    pub lcov: Option<PathBuf>,
+
+    /// Write a Cobertura XML report to the given path
+    #[clap(long, value_name = "FILE")]
+    pub cobertura: Option<PathBuf>,
+
+    /// Write an HTML summary to the given path
+    #[clap(long, value_name = "FILE")]
+    pub html: Option<PathBuf>,
+
+    /// Exit with an error if total line coverage is below the given percentage
+    #[clap(long, value_name = "PERCENT")]
+    pub fail_under: Option<f64>,
+
+    /// Exit with an error if the line coverage of a package is below the given
+    /// percentage. Can be repeated
+    #[clap(long, value_name = "PACKAGE=PERCENT")]
+    pub fail_under_package: Vec<PackageThreshold>,
+}
+
+impl CoverageReportSubcommand {
+    /// Whether the report is generated by moon itself rather than by
+    /// `moon_cove_report`.
+    fn is_native(&self) -> bool {
+        self.lcov.is_some()
+            || self.cobertura.is_some()
+            || self.html.is_some()
+            || self.fail_under.is_some()
+            || !self.fail_under_package.is_empty()
+    }
 }
 
 #[derive(Debug, clap::Parser)]
@@ -49,6 +94,8 @@ pub(crate) enum CoverageSubcommands {
     Analyze(CoverageAnalyzeSubcommand),
     /// Generate code coverage report
     Report(CoverageReportSubcommand),
+    /// Report coverage of the lines changed since a git revision
+    Diff(CoverageDiffSubcommand),
     /// Clean up coverage artifacts
     Clean,
 }
@@ -75,6 +122,19 @@ pub(crate) struct CoverageAnalyzeSubcommand {
     extra_flags: Vec<String>,
 }
 
+#[derive(Debug, clap::Parser)]
+pub(crate) struct CoverageDiffSubcommand {
+    /// The git revision to compare against, e.g. the target branch of a
+    /// pull request
+    #[clap(long, value_name = "REV")]
+    base: String,
+
+    /// Exit with an error if the coverage of changed lines is below the given
+    /// percentage
+    #[clap(long, value_name = "PERCENT")]
+    fail_under: Option<f64>,
+}
+
 pub(crate) fn run_coverage(
     cli: UniversalFlags,
     cmd: CoverageSubcommand,
@@ -83,6 +143,7 @@ pub(crate) fn run_coverage(
     let res = match cmd.cmd {
         CoverageSubcommands::Analyze(args) => run_coverage_analyze(cli, args, output),
         CoverageSubcommands::Report(args) => run_coverage_report(cli, args, output),
+        CoverageSubcommands::Diff(args) => run_coverage_diff(cli, args, output),
         CoverageSubcommands::Clean => run_coverage_clean(cli, output.user_log()),
     };
     res.context("Unable to run coverage command")
@@ -146,15 +207,22 @@ fn run_coverage_report(
         .ok_or_else(|| anyhow::anyhow!("Unable to get exit code"));
     }
 
-    let PackageDirs {
-        source_dir: src,
-        target_dir: _tgt,
-        ..
-    } = cli
+    let dirs = cli
         .source_tgt_dir
         .query(cli.workspace_env.clone())?
         .select(output.user_log())?
         .package_dirs()?;
+    let src = dirs.source_dir.clone();
+
+    if args.is_native() {
+        if !args.args.is_empty() {
+            anyhow::bail!(
+                "`{}` cannot be passed to the coverage utility together with report files or thresholds",
+                args.args.join(" ")
+            );
+        }
+        return run_native_coverage_report(&cli, &args, &dirs, output);
+    }
 
     let mut command = coverage_report_command(args.args, &src);
     if cli.dry_run {
@@ -168,6 +236,175 @@ fn run_coverage_report(
         .ok_or_else(|| anyhow::anyhow!("Coverage report command exited without a status code"))
 }
 
+/// Write the requested reports and check the thresholds, returning a non-zero
+/// exit code when coverage is below one of them.
+fn run_native_coverage_report(
+    cli: &UniversalFlags,
+    args: &CoverageReportSubcommand,
+    dirs: &PackageDirs,
+    output: &CommandOutput,
+) -> anyhow::Result<i32> {
+    let user_log = output.user_log();
+    let src = dirs.source_dir.as_path();
+    if cli.dry_run {
+        let command = coverage_report_command(["-f=coveralls"], src);
+        output.write_result(|writer| write_coverage_report_command(writer, &command, src))?;
+        return Ok(0);
+    }
+
+    // The tests have synced the dependencies already, and only the package
+    // directories are needed here, so `moon.lock` is left alone.
+    let resolve_config = moonbuild_rupes_recta::ResolveConfig::new_with_load_defaults(
+        true,
+        false,
+        true,
+        cli.workspace_env.clone(),
+    );
+    let resolve_output = {
+        let _lock = lock_directory(&dirs.target_dir, user_log)?;
+        rr_build::sync_and_resolve_project(&resolve_config, dirs, user_log)?
+    };
+    let packages = PackageNames::for_project(src, &resolve_output);
+    let coverage = LineCoverage::collect(src)?;
+    let package_coverage = coverage.packages(&packages);
+    // A threshold that matches nothing is most likely a typo, and must not
+    // let the check pass.
+    for threshold in &args.fail_under_package {
+        if !package_coverage.contains_key(&threshold.package) {
+            anyhow::bail!(
+                "no coverage was recorded for package `{}` given to `--fail-under-package`",
+                threshold.package
+            );
+        }
+    }
+    let write_report = |path: &Path, content: String| {
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write coverage report to {}", path.display()))
+    };
+    if let Some(path) = &args.lcov {
+        write_report(path, formats::lcov(&coverage))?;
+    }
+    if let Some(path) = &args.cobertura {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let source = src.display().to_string();
+        write_report(
+            path,
+            formats::cobertura(&coverage, &packages, &source, timestamp),
+        )?;
+    }
+    if let Some(path) = &args.html {
+        write_report(path, formats::html_summary(&coverage, &packages))?;
+    }
+
+    let totals = coverage.totals();
+    output.write_result(|writer| {
+        for (name, package) in &package_coverage {
+            writeln!(writer, "{name}: {:.2}%", package.totals.percent())?;
+        }
+        writeln!(
+            writer,
+            "Total: {:.2}% ({}/{} lines)",
+            totals.percent(),
+            totals.covered,
+            totals.coverable
+        )
+    })?;
+
+    let mut failed = false;
+    if let Some(threshold) = args.fail_under
+        && totals.percent() < threshold
+    {
+        user_log.error(format!(
+            "total coverage {:.2}% is below the threshold of {threshold}%",
+            totals.percent()
+        ));
+        failed = true;
+    }
+    for threshold in &args.fail_under_package {
+        let package = &package_coverage[&threshold.package];
+        if package.totals.percent() < threshold.percent {
+            user_log.error(format!(
+                "coverage of package `{}` {:.2}% is below the threshold of {}%",
+                threshold.package,
+                package.totals.percent(),
+                threshold.percent
+            ));
+            failed = true;
+        }
+    }
+    Ok(if failed { 1 } else { 0 })
+}
+
+/// Report the coverage of the lines changed since `--base`, using the coverage
+/// data of the last `moon coverage analyze` or `moon test --enable-coverage`.
+fn run_coverage_diff(
+    cli: UniversalFlags,
+    args: CoverageDiffSubcommand,
+    output: &CommandOutput,
+) -> anyhow::Result<i32> {
+    let user_log = output.user_log();
+    let PackageDirs {
+        source_dir: src, ..
+    } = cli
+        .source_tgt_dir
+        .query(cli.workspace_env.clone())?
+        .select(user_log)?
+        .package_dirs()?;
+    if cli.dry_run {
+        let command = coverage_report_command(["-f=coveralls"], &src);
+        output.write_result(|writer| write_coverage_report_command(writer, &command, &src))?;
+        return Ok(0);
+    }
+
+    let changed = diff::changed_lines(&src, &args.base)?;
+    let coverage = LineCoverage::collect(&src)?;
+    let files = diff::diff_coverage(&coverage, &changed);
+    let mut totals = line_coverage::Totals::default();
+    for file in files.values() {
+        totals.add(file.totals);
+    }
+
+    if totals.coverable == 0 {
+        user_log.info(format!("no coverable lines changed since `{}`", args.base));
+        return Ok(0);
+    }
+    output.write_result(|writer| {
+        for (path, file) in &files {
+            write!(
+                writer,
+                "{path}: {}/{} changed lines covered",
+                file.totals.covered, file.totals.coverable
+            )?;
+            if !file.uncovered.is_empty() {
+                write!(
+                    writer,
+                    ", missing {}",
+                    diff::format_line_ranges(&file.uncovered)
+                )?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(
+            writer,
+            "Diff coverage: {:.2}% ({}/{} lines)",
+            totals.percent(),
+            totals.covered,
+            totals.coverable
+        )
+    })?;
+
+    if let Some(threshold) = args.fail_under
+        && totals.percent() < threshold
+    {
+        user_log.error(format!(
+            "coverage of changed lines {:.2}% is below the threshold of {threshold}%",
+            totals.percent()
+        ));
+        return Ok(1);
+    }
+    Ok(0)
+}
+
 /// Clean up coverage artifacts by removing all files with name `moonbit_coverage_*.txt` in the current directory and target
 fn clean_coverage_artifacts(_src: &Path, tgt: &Path) -> anyhow::Result<()> {
     for file in WalkDir::new(tgt) {
@@ -0,0 +1,224 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Coverage of the lines changed since a git revision.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    path::Path,
+};
+
+use anyhow::Context;
+
+use super::line_coverage::{LineCoverage, Totals};
+
+/// The lines added or modified in each file, keyed by path relative to the
+/// directory the diff was taken in. Line numbers start at 1.
+pub(crate) type ChangedLines = BTreeMap<String, BTreeSet<usize>>;
+
+/// The lines changed in the working tree under `dir` since `base`.
+pub(crate) fn changed_lines(dir: &Path, base: &str) -> anyhow::Result<ChangedLines> {
+    let diff = moonutil::git::git_output(
+        dir,
+        &[
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            "--relative",
+            // Override `diff.noprefix` and `diff.mnemonicPrefix`, which
+            // change the prefixes the paths are parsed with.
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            base,
+            "--",
+            ".",
+        ],
+    )
+    .with_context(|| format!("failed to diff against `{base}`"))?;
+    parse_unified_diff(&diff)
+}
+
+/// Parse the new-side line numbers out of a unified diff.
+fn parse_unified_diff(diff: &str) -> anyhow::Result<ChangedLines> {
+    let mut changed = ChangedLines::new();
+    let mut file = None;
+    let mut files_seen = 0;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // Deleted files have `/dev/null` as their new side.
+            file = path.strip_prefix("b/").map(str::to_owned);
+            if file.is_some() || path == "/dev/null" {
+                files_seen += 1;
+            }
+        } else if let Some(hunk) = line.strip_prefix("@@ ")
+            && let Some(file) = &file
+        {
+            // @@ -old_start[,old_len] +new_start[,new_len] @@
+            let Some(new) = hunk.split(' ').find_map(|part| part.strip_prefix('+')) else {
+                continue;
+            };
+            let (start, len) = match new.split_once(',') {
+                Some((start, len)) => (start.parse::<usize>(), len.parse::<usize>()),
+                None => (new.parse(), Ok(1)),
+            };
+            if let (Ok(start), Ok(len)) = (start, len) {
+                changed
+                    .entry(file.clone())
+                    .or_default()
+                    .extend(start..start + len);
+            }
+        }
+    }
+    if files_seen == 0 && !diff.trim().is_empty() {
+        anyhow::bail!("failed to find any changed files in the output of `git diff`");
+    }
+    changed.retain(|_, lines| !lines.is_empty());
+    Ok(changed)
+}
+
+/// Coverage of the changed lines of one file.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FileDiffCoverage {
+    pub totals: Totals,
+    pub uncovered: Vec<usize>,
+}
+
+/// Intersect the changed lines with the coverable lines. Files without
+/// coverable changed lines are left out.
+pub(crate) fn diff_coverage(
+    coverage: &LineCoverage,
+    changed: &ChangedLines,
+) -> BTreeMap<String, FileDiffCoverage> {
+    let mut result = BTreeMap::new();
+    for (path, lines) in changed {
+        let Some(hits) = coverage.files.get(path) else {
+            continue;
+        };
+        let mut totals = Totals::default();
+        let mut uncovered = vec![];
+        for &line in lines {
+            let Some(Some(count)) = hits.get(line - 1) else {
+                continue;
+            };
+            totals.coverable += 1;
+            if *count > 0 {
+                totals.covered += 1;
+            } else {
+                uncovered.push(line);
+            }
+        }
+        if totals.coverable > 0 {
+            result.insert(path.clone(), FileDiffCoverage { totals, uncovered });
+        }
+    }
+    result
+}
+
+/// Format sorted line numbers as ranges, like `3, 7-9`.
+pub(crate) fn format_line_ranges(lines: &[usize]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let start = lines[i];
+        while i + 1 < lines.len() && lines[i + 1] == lines[i] + 1 {
+            i += 1;
+        }
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        if lines[i] == start {
+            let _ = write!(out, "{start}");
+        } else {
+            let _ = write!(out, "{start}-{}", lines[i]);
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_lines_are_intersected_with_coverage() {
+        let diff = "\
+diff --git a/lib/a.mbt b/lib/a.mbt
+--- a/lib/a.mbt
++++ b/lib/a.mbt
+@@ -1,0 +2,3 @@ fn f() {
++  let x = 1
++  let y = 2
++  let z = 3
+@@ -10 +12 @@
+-  old
++  new
+@@ -20,2 +21,0 @@
+-  removed
+-  removed
+diff --git a/gone.mbt b/gone.mbt
+--- a/gone.mbt
++++ /dev/null
+@@ -1 +0,0 @@
+-fn gone {}
+";
+        let changed = parse_unified_diff(diff).unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(
+            changed["lib/a.mbt"].iter().copied().collect::<Vec<_>>(),
+            [2, 3, 4, 12]
+        );
+
+        let mut coverage = LineCoverage::default();
+        let mut hits = vec![None; 12];
+        hits[1] = Some(1);
+        hits[2] = Some(0);
+        hits[3] = Some(0);
+        hits[11] = Some(4);
+        coverage.files.insert("lib/a.mbt".into(), hits);
+
+        let result = diff_coverage(&coverage, &changed);
+        assert_eq!(
+            result["lib/a.mbt"],
+            FileDiffCoverage {
+                totals: Totals {
+                    covered: 2,
+                    coverable: 4
+                },
+                uncovered: vec![3, 4],
+            }
+        );
+        assert_eq!(format_line_ranges(&[3, 4, 7, 9, 10, 11]), "3-4, 7, 9-11");
+    }
+
+    #[test]
+    fn diffs_without_recognized_files_are_rejected() {
+        assert!(parse_unified_diff("").unwrap().is_empty());
+
+        let unprefixed = "\
+diff --git lib/a.mbt lib/a.mbt
+--- lib/a.mbt
++++ lib/a.mbt
+@@ -1,0 +2 @@
++  let x = 1
+";
+        assert!(parse_unified_diff(unprefixed).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Report formats for [`LineCoverage`].
+
+use std::fmt::Write as _;
+
+use super::line_coverage::{LineCoverage, PackageNames, Totals};
+
+/// An lcov tracefile.
+pub(crate) fn lcov(coverage: &LineCoverage) -> String {
+    let mut out = String::new();
+    for (path, lines) in &coverage.files {
+        let totals = Totals::of(lines);
+        let _ = writeln!(out, "TN:\nSF:{path}");
+        for (index, hits) in lines.iter().enumerate() {
+            if let Some(hits) = hits {
+                let _ = writeln!(out, "DA:{},{hits}", index + 1);
+            }
+        }
+        let _ = writeln!(
+            out,
+            "LF:{}\nLH:{}\nend_of_record",
+            totals.coverable, totals.covered
+        );
+    }
+    out
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A Cobertura XML report, with a `<package>` per package and a `<class>` per
+/// file. `timestamp` is in milliseconds since the epoch.
+pub(crate) fn cobertura(
+    coverage: &LineCoverage,
+    packages: &PackageNames,
+    source_dir: &str,
+    timestamp: i64,
+) -> String {
+    let totals = coverage.totals();
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" ?>"#);
+    let _ = writeln!(
+        out,
+        r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<coverage line-rate="{:.4}" branch-rate="0" lines-covered="{}" lines-valid="{}" branches-covered="0" branches-valid="0" complexity="0" version="moon" timestamp="{timestamp}">"#,
+        totals.rate(),
+        totals.covered,
+        totals.coverable,
+    );
+    let _ = writeln!(
+        out,
+        "  <sources>\n    <source>{}</source>\n  </sources>\n  <packages>",
+        escape_xml(source_dir)
+    );
+    for (name, package) in coverage.packages(packages) {
+        let _ = writeln!(
+            out,
+            r#"    <package name="{}" line-rate="{:.4}" branch-rate="0" complexity="0">"#,
+            escape_xml(&name),
+            package.totals.rate()
+        );
+        let _ = writeln!(out, "      <classes>");
+        for (path, lines) in package.files {
+            let file_name = path.rsplit('/').next().unwrap_or(path);
+            let _ = writeln!(
+                out,
+                r#"        <class name="{}" filename="{}" line-rate="{:.4}" branch-rate="0" complexity="0">"#,
+                escape_xml(file_name),
+                escape_xml(path),
+                Totals::of(lines).rate()
+            );
+            let _ = writeln!(out, "          <methods/>\n          <lines>");
+            for (index, hits) in lines.iter().enumerate() {
+                if let Some(hits) = hits {
+                    let _ = writeln!(
+                        out,
+                        r#"            <line number="{}" hits="{hits}" branch="false"/>"#,
+                        index + 1
+                    );
+                }
+            }
+            let _ = writeln!(out, "          </lines>\n        </class>");
+        }
+        let _ = writeln!(out, "      </classes>\n    </package>");
+    }
+    let _ = writeln!(out, "  </packages>\n</coverage>");
+    out
+}
+
+/// A standalone HTML page summarizing the coverage of each package and file.
+pub(crate) fn html_summary(coverage: &LineCoverage, packages: &PackageNames) -> String {
+    let row = |out: &mut String, class: &str, name: &str, totals: Totals| {
+        let _ = writeln!(
+            out,
+            r#"<tr class="{class}"><td>{}</td><td>{}/{}</td><td>{:.1}%</td></tr>"#,
+            escape_xml(name),
+            totals.covered,
+            totals.coverable,
+            totals.percent()
+        );
+    };
+
+    let mut out = String::new();
+    out.push_str(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Coverage summary</title>
+<style>
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 2px 12px; text-align: left; }
+tr.package { font-weight: bold; background: #eee; }
+tr.file td:first-child { padding-left: 32px; }
+</style>
+</head>
+<body>
+"#,
+    );
+    let totals = coverage.totals();
+    let _ = writeln!(
+        out,
+        "<h1>Coverage: {:.1}% ({}/{} lines)</h1>",
+        totals.percent(),
+        totals.covered,
+        totals.coverable
+    );
+    out.push_str("<table>\n<tr><th>Package / file</th><th>Lines</th><th>Coverage</th></tr>\n");
+    for (name, package) in coverage.packages(packages) {
+        row(&mut out, "package", &name, package.totals);
+        for (path, lines) in package.files {
+            row(&mut out, "file", path, Totals::of(lines));
+        }
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    #[test]
+    fn lcov_and_cobertura_reports() {
+        let coverage = LineCoverage::from_coveralls(
+            r#"{"source_files": [{"name": "lib/a.mbt", "coverage": [null, 2, 0]}]}"#,
+        )
+        .unwrap();
+        expect![[r#"
+            TN:
+            SF:lib/a.mbt
+            DA:2,2
+            DA:3,0
+            LF:2
+            LH:1
+            end_of_record
+        "#]]
+        .assert_eq(&lcov(&coverage));
+
+        let packages = PackageNames::default();
+        expect![[r#"
+            <?xml version="1.0" ?>
+            <!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">
+            <coverage line-rate="0.5000" branch-rate="0" lines-covered="1" lines-valid="2" branches-covered="0" branches-valid="0" complexity="0" version="moon" timestamp="0">
+              <sources>
+                <source>/src</source>
+              </sources>
+              <packages>
+                <package name="lib" line-rate="0.5000" branch-rate="0" complexity="0">
+                  <classes>
+                    <class name="a.mbt" filename="lib/a.mbt" line-rate="0.5000" branch-rate="0" complexity="0">
+                      <methods/>
+                      <lines>
+                        <line number="2" hits="2" branch="false"/>
+                        <line number="3" hits="0" branch="false"/>
+                      </lines>
+                    </class>
+                  </classes>
+                </package>
+              </packages>
+            </coverage>
+        "#]]
+        .assert_eq(&cobertura(&coverage, &packages, "/src", 0));
+    }
+}
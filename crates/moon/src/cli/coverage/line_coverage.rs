@@ -0,0 +1,266 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Line coverage, merged across backends and test targets.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use moonbuild_rupes_recta::ResolveOutput;
+use serde::Deserialize;
+
+/// The hit count of each line of a file, `None` for lines without code.
+pub(crate) type LineHits = Vec<Option<u64>>;
+
+/// Line coverage of a project, keyed by file path relative to the module
+/// root.
+#[derive(Debug, Default)]
+pub(crate) struct LineCoverage {
+    pub files: BTreeMap<String, LineHits>,
+}
+
+/// The number of covered and coverable lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Totals {
+    pub covered: usize,
+    pub coverable: usize,
+}
+
+impl Totals {
+    pub fn of(lines: &[Option<u64>]) -> Self {
+        let mut totals = Totals::default();
+        for hits in lines.iter().flatten() {
+            totals.coverable += 1;
+            if *hits > 0 {
+                totals.covered += 1;
+            }
+        }
+        totals
+    }
+
+    pub fn add(&mut self, other: Totals) {
+        self.covered += other.covered;
+        self.coverable += other.coverable;
+    }
+
+    /// Percentage of covered lines. Nothing to cover counts as fully covered.
+    pub fn percent(&self) -> f64 {
+        if self.coverable == 0 {
+            100.0
+        } else {
+            self.covered as f64 * 100.0 / self.coverable as f64
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.percent() / 100.0
+    }
+}
+
+#[derive(Deserialize)]
+struct CoverallsReport {
+    source_files: Vec<CoverallsSourceFile>,
+}
+
+#[derive(Deserialize)]
+struct CoverallsSourceFile {
+    name: String,
+    coverage: LineHits,
+}
+
+impl LineCoverage {
+    /// Read the line coverage of all captured coverage sections, using the
+    /// Coveralls output of `moon_cove_report` for the mapping from counters to
+    /// source lines.
+    pub fn collect(source_dir: &Path) -> anyhow::Result<Self> {
+        let report = tempfile::NamedTempFile::new()?;
+        let status = super::coverage_report_command(
+            [
+                "-f=coveralls".to_owned(),
+                format!("-o={}", report.path().display()),
+            ],
+            source_dir,
+        )
+        .status()
+        .context("Unable to run coverage report")?;
+        if !status.success() {
+            anyhow::bail!("Coverage report command failed with {status}");
+        }
+        let content =
+            std::fs::read_to_string(report.path()).context("Unable to read the coverage report")?;
+        Self::from_coveralls(&content)
+    }
+
+    /// Parse a Coveralls report. Files reported more than once, e.g. once per
+    /// backend, are merged by adding up their hit counts.
+    pub fn from_coveralls(content: &str) -> anyhow::Result<Self> {
+        let report: CoverallsReport = serde_json_lenient::from_str(content)
+            .context("Unable to parse the Coveralls coverage report")?;
+        let mut coverage = LineCoverage::default();
+        for file in report.source_files {
+            coverage.merge_file(file.name, file.coverage);
+        }
+        Ok(coverage)
+    }
+
+    fn merge_file(&mut self, path: String, lines: LineHits) {
+        let merged = self.files.entry(path.replace('\\', "/")).or_default();
+        if merged.len() < lines.len() {
+            merged.resize(lines.len(), None);
+        }
+        for (merged, hits) in merged.iter_mut().zip(lines) {
+            *merged = match (*merged, hits) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+
+    pub fn totals(&self) -> Totals {
+        let mut totals = Totals::default();
+        for lines in self.files.values() {
+            totals.add(Totals::of(lines));
+        }
+        totals
+    }
+
+    /// Coverage totals and files of each package.
+    pub fn packages(&self, packages: &PackageNames) -> BTreeMap<String, PackageCoverage<'_>> {
+        let mut result = BTreeMap::<String, PackageCoverage<'_>>::new();
+        for (path, lines) in &self.files {
+            let package = result.entry(packages.package_of(path)).or_default();
+            package.totals.add(Totals::of(lines));
+            package.files.push((path, lines));
+        }
+        result
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PackageCoverage<'a> {
+    pub totals: Totals,
+    pub files: Vec<(&'a str, &'a LineHits)>,
+}
+
+/// Maps covered files to the packages of the resolved project that contain
+/// them. Files outside of every package are named after their directory.
+#[derive(Debug, Default)]
+pub(crate) struct PackageNames {
+    /// Package names by package directory, relative to the source directory.
+    by_dir: HashMap<PathBuf, String>,
+}
+
+impl PackageNames {
+    /// The packages of the local modules of `resolve_output`.
+    pub fn for_project(source_dir: &Path, resolve_output: &ResolveOutput) -> Self {
+        let pkg_dirs = &resolve_output.pkg_dirs;
+        let by_dir = resolve_output
+            .local_modules()
+            .iter()
+            .filter_map(|&module| pkg_dirs.packages_for_module(module))
+            .flat_map(|packages| packages.values())
+            .filter_map(|&id| {
+                let package = pkg_dirs.get_package(id);
+                let dir = package.root_path.strip_prefix(source_dir).ok()?;
+                Some((dir.to_path_buf(), package.fqn.to_string()))
+            })
+            .collect();
+        Self { by_dir }
+    }
+
+    pub fn package_of(&self, file: &str) -> String {
+        let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+        match self.by_dir.get(dir) {
+            Some(package) => package.clone(),
+            None => dir.to_string_lossy().replace('\\', "/"),
+        }
+    }
+}
+
+/// A coverage threshold for one package, from `--fail-under-package`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PackageThreshold {
+    pub package: String,
+    pub percent: f64,
+}
+
+impl FromStr for PackageThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (package, percent) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected `PACKAGE=PERCENT`, got `{s}`"))?;
+        let percent = percent
+            .parse()
+            .map_err(|_| format!("invalid percentage `{percent}`"))?;
+        Ok(PackageThreshold {
+            package: package.to_owned(),
+            percent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coveralls_files_are_merged_across_backends() {
+        let coverage = LineCoverage::from_coveralls(
+            r#"{"source_files": [
+                {"name": "lib/a.mbt", "coverage": [null, 0, 1, 0]},
+                {"name": "lib/a.mbt", "coverage": [null, 2, 0, 0, null]},
+                {"name": "main/main.mbt", "coverage": [null, 1]}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            coverage.files["lib/a.mbt"],
+            [None, Some(2), Some(1), Some(0), None]
+        );
+        assert_eq!(
+            coverage.totals(),
+            Totals {
+                covered: 3,
+                coverable: 4
+            }
+        );
+
+        let names = PackageNames {
+            by_dir: HashMap::from([
+                (PathBuf::from("lib"), "user/hello/lib".to_string()),
+                (PathBuf::from("main"), "user/hello/main".to_string()),
+            ]),
+        };
+        let packages = coverage.packages(&names);
+        assert_eq!(
+            packages["user/hello/lib"].totals,
+            Totals {
+                covered: 2,
+                coverable: 3
+            }
+        );
+        assert_eq!(packages["user/hello/main"].totals.percent(), 100.0);
+    }
+}
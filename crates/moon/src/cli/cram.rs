@@ -190,7 +190,8 @@ fn run_cram_test(
     }
 
     let lock = lock_directory(target_dir, user_log)?;
-    let cfg = BuildConfig::from_flags(&build_cmd.build_flags, &cli.unstable_feature, cli.verbose);
+    let cfg = BuildConfig::from_flags(&build_cmd.build_flags, &cli.unstable_feature, cli.verbose)
+        .with_source_dir(source_dir);
     for (build_meta, build_graph) in planned_runs {
         rr_build::generate_all_pkgs_json(&build_meta)?;
         let result = rr_build::execute_build(&cfg, build_graph, target_dir, user_log)?;
@@ -251,7 +252,10 @@ fn cram_args(cmd: CramTestSubcommand) -> ParsedCramArgs {
 
     ParsedCramArgs {
         build_flags,
-        auto_sync_flags: AutoSyncFlags { frozen: false },
+        auto_sync_flags: AutoSyncFlags {
+            frozen: false,
+            locked: false,
+        },
         cram_args: std::iter::once("test".to_string())
             .chain(cmd.args)
             .collect(),
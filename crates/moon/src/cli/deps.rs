@@ -19,8 +19,9 @@
 use anyhow::bail;
 use mooncake::{
     pkg::{
-        add::AddSubcommand, install::InstallSubcommand, remove::RemoveSubcommand,
-        sync::SyncOutputOptions, tree::TreeSubcommand,
+        add::AddSubcommand, install::InstallSubcommand, licenses::LicensesSubcommand,
+        outdated::OutdatedSubcommand, remove::RemoveSubcommand, sync::SyncOutputOptions,
+        tree::TreeSubcommand, upgrade_deps::UpgradeDepsSubcommand, vendor::VendorSubcommand,
     },
     registry::RegistryClient,
 };
@@ -85,7 +86,10 @@ pub(crate) fn install_cli(
             .package_dirs()?;
         mooncake::pkg::sync::auto_sync(
             &dirs,
-            &AutoSyncFlags { frozen: false },
+            &AutoSyncFlags {
+                frozen: false,
+                locked: false,
+            },
             SyncOutputOptions::default(),
             user_log,
             true,
@@ -200,6 +204,22 @@ pub(crate) fn remove_cli(
     mooncake::pkg::remove::remove(&module_dir, &project_manifest, username, pkgname, user_log)
 }
 
+/// Update the local registry index, returning whether the update succeeded.
+/// Failures are only fatal when there is no existing index to fall back to.
+fn update_registry_index(registry: &RegistryClient, user_log: &UserLog) -> anyhow::Result<bool> {
+    let had_index = registry.has_cached_index();
+    match registry.sync(user_log) {
+        Ok(()) => Ok(true),
+        Err(e) if had_index => {
+            user_log.warn(format!(
+                "failed to update registry index, continuing with existing index: {e}"
+            ));
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
 pub(crate) fn add_cli(
     cli: UniversalFlags,
     cmd: AddSubcommand,
@@ -217,24 +237,9 @@ pub(crate) fn add_cli(
     // - If an index already exists, update failures are treated as warnings so users can proceed
     //   with the existing local index.
     let registry = RegistryClient::configured();
-    let mut index_updated = false;
-    if !cmd.no_update && (!cmd.upgrade || !cmd.package_path.contains('@')) {
-        let had_index = registry.has_cached_index();
-        match registry.sync(user_log) {
-            Ok(()) => {
-                index_updated = true;
-            }
-            Err(e) => {
-                if had_index {
-                    user_log.warn(format!(
-                        "failed to update registry index, continuing with existing index: {e}"
-                    ));
-                } else {
-                    return Err(e);
-                }
-            }
-        }
-    }
+    let index_updated = !cmd.no_update
+        && (!cmd.upgrade || !cmd.package_path.contains('@'))
+        && update_registry_index(&registry, user_log)?;
 
     let package_path = cmd.package_path;
 
@@ -283,7 +288,7 @@ pub(crate) fn add_cli(
 
 pub(crate) fn tree_cli(
     cli: UniversalFlags,
-    _cmd: TreeSubcommand,
+    cmd: TreeSubcommand,
     user_log: &UserLog,
 ) -> anyhow::Result<i32> {
     let project = cli
@@ -294,7 +299,94 @@ pub(crate) fn tree_cli(
     let PackageDirs {
         project_manifest, ..
     } = project.package_dirs()?;
-    mooncake::pkg::tree::tree(&module_dir, &project_manifest, user_log)
+    mooncake::pkg::tree::tree(&module_dir, &project_manifest, &cmd, user_log)
+}
+
+pub(crate) fn outdated_cli(
+    cli: UniversalFlags,
+    cmd: OutdatedSubcommand,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let project = cli
+        .source_tgt_dir
+        .query(cli.workspace_env.clone())?
+        .select(user_log)?;
+    let module_dir = require_selected_module(project.context(), "outdated")?;
+    let PackageDirs {
+        project_manifest, ..
+    } = project.package_dirs()?;
+    let registry = RegistryClient::configured();
+    if !cmd.no_update {
+        update_registry_index(&registry, user_log)?;
+    }
+    mooncake::pkg::outdated::outdated(
+        &registry,
+        &module_dir,
+        &project_manifest,
+        cmd.format,
+        user_log,
+    )
+}
+
+pub(crate) fn upgrade_deps_cli(
+    cli: UniversalFlags,
+    cmd: UpgradeDepsSubcommand,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let project = cli
+        .source_tgt_dir
+        .query(cli.workspace_env.clone())?
+        .select(user_log)?;
+    let module_dir = project.context().root().to_path_buf();
+    let PackageDirs {
+        project_manifest, ..
+    } = project.package_dirs()?;
+    let registry = RegistryClient::configured();
+    if !cmd.no_update {
+        update_registry_index(&registry, user_log)?;
+    }
+    mooncake::pkg::upgrade_deps::upgrade_deps(
+        &registry,
+        &module_dir,
+        &project_manifest,
+        &cmd,
+        cli.dry_run,
+        user_log,
+    )
+}
+
+pub(crate) fn vendor_cli(
+    cli: UniversalFlags,
+    cmd: VendorSubcommand,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let project = cli
+        .source_tgt_dir
+        .query(cli.workspace_env.clone())?
+        .select(user_log)?;
+    let PackageDirs {
+        source_dir,
+        project_manifest,
+        ..
+    } = project.package_dirs()?;
+    let registry = RegistryClient::configured();
+    if !registry.has_cached_index() {
+        update_registry_index(&registry, user_log)?;
+    }
+    mooncake::pkg::vendor::vendor(&registry, &source_dir, &project_manifest, &cmd, user_log)
+}
+
+pub(crate) fn licenses_cli(
+    cli: UniversalFlags,
+    cmd: LicensesSubcommand,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let dirs = cli
+        .source_tgt_dir
+        .query(cli.workspace_env.clone())?
+        .select(user_log)?
+        .package_dirs()?;
+    mooncake::pkg::licenses::licenses(&dirs, &cmd, cli.workspace_env.clone(), user_log)
 }
 
 #[cfg(test)]
@@ -291,8 +291,8 @@ pub(crate) fn run_info(
     } = &dirs;
 
     let build_flags = BuildFlags::default();
-    let resolve_cfg = ResolveConfig::new_with_load_defaults(
-        cmd.auto_sync_flags.frozen,
+    let resolve_cfg = ResolveConfig::new(
+        cmd.auto_sync_flags.clone(),
         !build_flags.std(),
         build_flags.enable_coverage,
         cli.workspace_env.clone(),
@@ -586,7 +586,10 @@ fn build_selected_package(
         ..BuildFlags::default()
     };
     let preconfig = preconfig_compile(
-        &moonutil::cli_support::AutoSyncFlags { frozen: false },
+        &moonutil::cli_support::AutoSyncFlags {
+            frozen: false,
+            locked: false,
+        },
         cli,
         &build_flags,
         Some(TargetBackend::Native),
@@ -210,7 +210,8 @@ pub(crate) fn run_prove(
 
     let _lock = lock_directory(target_dir, user_log)?;
     rr_build::generate_all_pkgs_json(&build_meta)?;
-    let cfg = BuildConfig::from_flags(&build_flags, &cli.unstable_feature, cli.verbose);
+    let cfg = BuildConfig::from_flags(&build_flags, &cli.unstable_feature, cli.verbose)
+        .with_source_dir(project_root);
     let result = rr_build::execute_build(&cfg, build_graph, target_dir, user_log)?;
     if !cli.quiet && !build_flags.output_json {
         let _ = print_prove_summary(project_root, &proof_reports);
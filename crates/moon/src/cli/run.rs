@@ -805,7 +805,8 @@ fn build_executable_from_plan(
 
     let build_config =
         BuildConfig::from_flags(&cmd.build_flags, &cli.unstable_feature, cli.verbose)
-            .with_suppressed_progress(options.output.suppress_build_progress());
+            .with_suppressed_progress(options.output.suppress_build_progress())
+            .with_source_dir(source_dir);
     let build_result = match build_graph {
         RunBuildInput::Ordinary(build_graph) => {
             rr_build::execute_build(&build_config, build_graph, target_dir, user_log)?
@@ -248,6 +248,12 @@ fn dispatch(
         RunWasm(command) => super::run_runwasm(&flags, command, output),
         Test(command) => super::run_test(flags, command, output).map(Into::into),
         Tree(command) => super::tree_cli(flags, command, output.user_log()).map(Into::into),
+        Outdated(command) => super::outdated_cli(flags, command, output.user_log()).map(Into::into),
+        UpgradeDeps(command) => {
+            super::upgrade_deps_cli(flags, command, output.user_log()).map(Into::into)
+        }
+        Vendor(command) => super::vendor_cli(flags, command, output.user_log()).map(Into::into),
+        Licenses(command) => super::licenses_cli(flags, command, output.user_log()).map(Into::into),
         Update(command) => super::update_cli(flags, command, output.user_log()).map(Into::into),
         Upgrade(command) => super::run_upgrade(flags, command).map(Into::into),
         ShellCompletion(command) => super::gen_shellcomp(&flags, command).map(Into::into),
@@ -132,7 +132,10 @@ fn runwasm_as_run_subcommand(cmd: RunWasmSubcommand) -> RunSubcommand {
         build_flags,
         args: cmd.args,
         moonrun_policy: cmd.experimental_policy,
-        auto_sync_flags: AutoSyncFlags { frozen: false },
+        auto_sync_flags: AutoSyncFlags {
+            frozen: false,
+            locked: false,
+        },
         build_only: false,
         profile: false,
     }
@@ -18,6 +18,7 @@
 
 use crate::cli::profile;
 use crate::filter::TargetPackageGroup;
+use crate::filter::affected_packages;
 use crate::filter::canonicalize_with_filename;
 use crate::filter::ensure_packages_support_backend;
 use crate::filter::filter_pkg_by_dir;
@@ -30,8 +31,10 @@ use crate::rr_build;
 use crate::rr_build::preconfig_compile;
 use crate::rr_build::{BuildConfig, CalcUserIntentOutput};
 use crate::run::collect_test_outline;
-use crate::run::perform_promotion;
-use crate::run::{TestFilter, TestIndex, TestOutlineEntry};
+use crate::run::{TagFilter, TestFilter, TestIndex, TestOutlineEntry, TestReport, TestReporter};
+use crate::run::{last_failed, perform_promotion, select_shard};
+use crate::watch::prebuild_output::rr_get_prebuild_watch_paths;
+use crate::watch::{WatchOutput, clear_terminal, watching_changes};
 use anyhow::Context;
 use anyhow::bail;
 use clap::builder::ArgPredicate;
@@ -40,14 +43,16 @@ use moonbuild_rupes_recta::build_plan::{ArtifactKey, InputDirective};
 use moonbuild_rupes_recta::intent::UserIntent;
 use moonbuild_rupes_recta::model::BuildTarget;
 use moonbuild_rupes_recta::model::PackageId;
-use moonutil::build_options::{RunMode, TestArtifacts, TestIndexRange};
+use moonutil::build_options::{RunMode, TestArtifacts, TestIndexRange, TestShard};
 use moonutil::cli_support::AutoSyncFlags;
 use moonutil::command_output::CommandOutput;
 use moonutil::locks::lock_directory;
 use moonutil::project::{PackageDirs, ProjectProbe};
 use moonutil::target::{SurfaceTarget, TargetBackend, lower_surface_targets};
 use moonutil::user_log::UserLog;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{Level, debug, info, instrument, trace};
 
 use super::BenchSubcommand;
@@ -78,6 +83,7 @@ impl ResolvedTestSelection {
     ) -> Result<TestFilter, anyhow::Error> {
         let mut filter = TestFilter {
             name_filter: cmd.filter.clone(),
+            tags: tag_filter(cmd.tags, cmd.exclude_tags),
             ..Default::default()
         };
 
@@ -176,6 +182,7 @@ impl ResolvedTestSelection {
 fn print_test_summary(
     total: usize,
     passed: usize,
+    flaky: usize,
     quiet: bool,
     backend_hint: Option<&str>,
     user_log: &UserLog,
@@ -192,8 +199,14 @@ fn print_test_summary(
             .map(|hint| format!(" [{}]", hint))
             .unwrap_or_default();
 
+        let flaky_suffix = if flaky > 0 {
+            format!(", flaky: {}", flaky.to_string().yellow())
+        } else {
+            String::new()
+        };
+
         println!(
-            "Total tests: {}, passed: {}, failed: {}.{}",
+            "Total tests: {}, passed: {}, failed: {}{}.{}",
             total,
             passed,
             if has_failures {
@@ -201,6 +214,7 @@ fn print_test_summary(
             } else {
                 failed.to_string()
             },
+            flaky_suffix,
             backend_suffix,
         );
     }
@@ -281,6 +295,58 @@ pub(crate) struct TestSubcommand {
     #[clap(long)]
     pub no_parallelize: bool,
 
+    /// Monitor the file system and rerun the tests of the packages affected
+    /// by each change
+    #[clap(long, short, conflicts_with_all = ["build_only", "update", "outline", "profile"])]
+    pub watch: bool,
+
+    /// Kill a test executable that runs longer than the given number of
+    /// seconds. Overrides `test-timeout` in `moon.pkg`
+    #[clap(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["build_only", "outline"])]
+    pub timeout: Option<u64>,
+
+    /// Rerun failed tests up to the given number of times. Tests that pass on
+    /// a rerun are reported as flaky
+    #[clap(long, value_name = "N", default_value_t = 0, conflicts_with_all = ["build_only", "outline"])]
+    pub retries: u32,
+
+    /// Only run the tests that failed the last time they were run
+    #[clap(long, conflicts_with_all = ["file", "index", "doc_index", "build_only", "outline"])]
+    pub rerun_failed: bool,
+
+    /// Run the tests that failed the last time they were run before the others
+    #[clap(long, conflicts_with_all = ["rerun_failed", "build_only", "outline"])]
+    pub failed_first: bool,
+
+    /// Only run the K-th of N disjoint slices of the selected tests, e.g.
+    /// `--shard 2/4`
+    #[clap(long, value_name = "K/N", conflicts_with_all = ["build_only", "outline"])]
+    pub shard: Option<TestShard>,
+
+    /// Balance `--shard` slices by the test durations recorded in the last
+    /// run. Every shard must see the same recorded durations
+    #[clap(long, requires = "shard")]
+    pub shard_by_duration: bool,
+
+    /// Print the N slowest tests after the run
+    #[clap(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["build_only", "outline", "test_failure_json"]
+    )]
+    pub report_slowest: Option<usize>,
+
+    /// Run tests in a random order, within and across files. Pass the seed
+    /// printed by an earlier run to replay its order
+    #[clap(
+        long,
+        value_name = "SEED",
+        num_args = 0..=1,
+        require_equals = true,
+        conflicts_with_all = ["build_only", "outline"]
+    )]
+    pub shuffle: Option<Option<u64>>,
+
     /// Print the outline of tests to be executed and exit
     #[clap(long, conflicts_with_all = ["build_only", "update", "test_failure_json"])]
     pub outline: bool,
@@ -289,6 +355,15 @@ pub(crate) struct TestSubcommand {
     #[clap(long)]
     pub test_failure_json: bool,
 
+    /// Write a machine-readable report of every test case in the given format.
+    /// Without `--report-file`, the report replaces the usual output on stdout
+    #[clap(long, value_enum, conflicts_with_all = ["build_only", "outline", "test_failure_json"])]
+    pub reporter: Option<TestReporter>,
+
+    /// File to write the `--reporter` output to
+    #[clap(long, requires = "reporter")]
+    pub report_file: Option<PathBuf>,
+
     /// Path to the patch file
     #[clap(long, requires("package"), conflicts_with = "update")]
     pub patch_file: Option<PathBuf>,
@@ -313,6 +388,16 @@ pub(crate) struct TestSubcommand {
     /// Supports '*' (matches any sequence) and '?' (matches any single character).
     #[clap(short = 'f', short_alias = 'F', long)]
     pub filter: Option<String>,
+
+    /// Run only tests with the given tag, set by `#tag("...")` on the test
+    /// block. Can be repeated to run tests with any of the tags
+    #[clap(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Skip tests with the given tag. Can be repeated, and takes precedence
+    /// over `--tag`
+    #[clap(long = "exclude-tag", value_name = "TAG")]
+    pub exclude_tags: Vec<String>,
 }
 
 #[instrument(skip_all)]
@@ -354,6 +439,9 @@ fn run_test_impl(
         ProjectProbe::Found(_) => query.select(user_log)?.package_dirs()?,
         ProjectProbe::NotFound(not_found) => {
             // Now we're talking about real single-file scenario.
+            if cmd.watch {
+                anyhow::bail!("`--watch` can only be used within a project");
+            }
             match cmd.path.as_slice() {
                 [path] => {
                     let single_file = cli.source_tgt_dir.single_file_package_dirs(path)?;
@@ -384,6 +472,10 @@ fn run_test_impl(
         );
     }
 
+    if cmd.watch {
+        return run_test_watch(cli, cmd, &dirs, output);
+    }
+
     if cmd.build_flags.target.is_empty() {
         debug!("no explicit backend target provided; using defaults");
         let selected_target_backend = cmd.profile.then_some(TargetBackend::Native);
@@ -420,6 +512,80 @@ fn run_test_impl(
     Ok(ret_value)
 }
 
+/// Run the selected tests once, then rerun the tests of the packages affected
+/// by each change until interrupted.
+fn run_test_watch(
+    cli: &UniversalFlags,
+    cmd: &TestSubcommand,
+    dirs: &PackageDirs,
+    output: &CommandOutput,
+) -> anyhow::Result<i32> {
+    let targets = if cmd.build_flags.target.is_empty() {
+        Vec::new()
+    } else {
+        lower_surface_targets(&cmd.build_flags.target)
+    };
+    let display_backend_hint = (targets.len() > 1).then_some(());
+    let test_cmd: TestLikeSubcommand<'_> = cmd.into();
+    validate_test_or_bench_invocation(cli, &test_cmd)?;
+    watching_changes(
+        |changed| {
+            if changed.is_some() {
+                clear_terminal();
+            }
+            run_test_watch_pass(
+                cli,
+                &test_cmd,
+                dirs,
+                display_backend_hint,
+                &targets,
+                changed,
+                output,
+            )
+        },
+        &dirs.source_dir,
+        &dirs.target_dir,
+    )
+}
+
+/// A single `moon test --watch` run. The project is resolved afresh each time,
+/// since the change may have added packages or imports.
+fn run_test_watch_pass(
+    cli: &UniversalFlags,
+    cmd: &TestLikeSubcommand<'_>,
+    dirs: &PackageDirs,
+    display_backend_hint: Option<()>,
+    targets: &[TargetBackend],
+    changed: Option<&[PathBuf]>,
+    output: &CommandOutput,
+) -> anyhow::Result<WatchOutput> {
+    let resolve_output = sync_and_resolve_test_or_bench_project(cli, cmd, dirs, output.user_log())?;
+    let prebuild_list = rr_get_prebuild_watch_paths(&resolve_output);
+    let affected = changed.and_then(|paths| affected_packages(&resolve_output, paths));
+    let cmd = TestLikeSubcommand {
+        affected_packages: affected.as_ref(),
+        ..*cmd
+    };
+    let _lock;
+    if !cli.dry_run {
+        _lock = lock_directory(&dirs.target_dir, output.user_log())?;
+    }
+    let exit_code = run_test_or_bench_from_resolved(
+        cli,
+        &cmd,
+        dirs,
+        display_backend_hint,
+        targets,
+        resolve_output,
+        output,
+    )?;
+    Ok(WatchOutput {
+        ok: exit_code == 0,
+        additional_ignored_paths: prebuild_list.ignored_paths,
+        additional_watched_paths: prebuild_list.watched_paths,
+    })
+}
+
 fn ensure_test_profile_target_is_native(build_flags: &BuildFlags) -> anyhow::Result<()> {
     if !build_flags.target.is_empty()
         && build_flags.resolve_single_target_backend()? != Some(TargetBackend::Native)
@@ -514,6 +680,7 @@ fn run_test_in_single_file_rr(
 
     let mut filter = TestFilter {
         name_filter: cmd.filter.clone(),
+        tags: tag_filter(&cmd.tags, &cmd.exclude_tags),
         ..Default::default()
     };
 
@@ -624,12 +791,35 @@ pub(crate) struct TestLikeSubcommand<'a> {
     pub build_only: bool,
     pub profile: bool,
     pub no_parallelize: bool,
+    pub timeout: Option<u64>,
+    pub retries: u32,
+    pub rerun_failed: bool,
+    pub failed_first: bool,
+    pub shard: Option<TestShard>,
+    pub shard_by_duration: bool,
+    pub report_slowest: Option<usize>,
+    /// Seed for `--shuffle`, picked at random when not given.
+    pub shuffle: Option<u64>,
     pub outline: bool,
     pub test_failure_json: bool,
+    pub reporter: Option<TestReporter>,
+    pub report_file: &'a Option<PathBuf>,
     pub patch_file: &'a Option<PathBuf>,
     pub include_skipped: bool,
     /// Glob pattern to filter tests by name
     pub filter: &'a Option<String>,
+    /// Tags from `--tag`, to select tests by
+    pub tags: &'a [String],
+    /// Tags from `--exclude-tag`, to skip tests by
+    pub exclude_tags: &'a [String],
+    /// Name to save the benchmark results under, from `--save-baseline`
+    pub save_bench_baseline: &'a Option<String>,
+    /// Name of the saved benchmark results to compare with, from `--baseline`
+    pub bench_baseline: &'a Option<String>,
+    /// Slowdown in percent above which a benchmark counts as regressed
+    pub regression_threshold: f64,
+    /// In `moon test --watch` reruns, only run the tests of these packages.
+    pub affected_packages: Option<&'a HashSet<PackageId>>,
 }
 
 impl<'a> From<&'a TestSubcommand> for TestLikeSubcommand<'a> {
@@ -648,11 +838,27 @@ impl<'a> From<&'a TestSubcommand> for TestLikeSubcommand<'a> {
             build_only: cmd.build_only,
             profile: cmd.profile,
             no_parallelize: cmd.no_parallelize,
+            timeout: cmd.timeout,
+            retries: cmd.retries,
+            rerun_failed: cmd.rerun_failed,
+            failed_first: cmd.failed_first,
+            shard: cmd.shard,
+            shard_by_duration: cmd.shard_by_duration,
+            report_slowest: cmd.report_slowest,
+            shuffle: cmd.shuffle.map(|seed| seed.unwrap_or_else(rand::random)),
             outline: cmd.outline,
             test_failure_json: cmd.test_failure_json,
+            reporter: cmd.reporter,
+            report_file: &cmd.report_file,
             patch_file: &cmd.patch_file,
             include_skipped: cmd.include_skipped,
             filter: &cmd.filter,
+            tags: &cmd.tags,
+            exclude_tags: &cmd.exclude_tags,
+            save_bench_baseline: &None,
+            bench_baseline: &None,
+            regression_threshold: 0.0,
+            affected_packages: None,
         }
     }
 }
@@ -672,11 +878,27 @@ impl<'a> From<&'a BenchSubcommand> for TestLikeSubcommand<'a> {
             build_only: cmd.build_only,
             profile: false,
             no_parallelize: cmd.no_parallelize,
+            timeout: None,
+            retries: 0,
+            rerun_failed: false,
+            failed_first: false,
+            shard: None,
+            shard_by_duration: false,
+            report_slowest: None,
+            shuffle: None,
             outline: false,
             test_failure_json: false,
+            reporter: None,
+            report_file: &None,
             patch_file: &None,
             include_skipped: false,
             filter: &None,
+            tags: &[],
+            exclude_tags: &[],
+            save_bench_baseline: &cmd.save_baseline,
+            bench_baseline: &cmd.baseline,
+            regression_threshold: cmd.regression_threshold,
+            affected_packages: None,
         }
     }
 }
@@ -715,6 +937,7 @@ pub(crate) fn plan_test_or_bench_rr_from_resolved(
 
     let mut filter = TestFilter {
         name_filter: cmd.filter.clone(),
+        tags: tag_filter(cmd.tags, cmd.exclude_tags),
         ..Default::default()
     };
     let planning_context = rr_build::prepare_resolved_build(
@@ -971,8 +1194,8 @@ pub(crate) fn sync_and_resolve_test_or_bench_project(
     dirs: &PackageDirs,
     user_log: &UserLog,
 ) -> anyhow::Result<moonbuild_rupes_recta::ResolveOutput> {
-    let resolve_config = moonbuild_rupes_recta::ResolveConfig::new_with_load_defaults(
-        cmd.auto_sync_flags.frozen,
+    let resolve_config = moonbuild_rupes_recta::ResolveConfig::new(
+        cmd.auto_sync_flags.clone(),
         !cmd.build_flags.std(),
         cmd.build_flags.enable_coverage,
         cli.workspace_env.clone(),
@@ -1027,7 +1250,7 @@ pub(crate) fn run_test_or_bench_from_resolved(
         ..
     } = dirs;
     info!(run_mode = ?cmd.run_mode, update = cmd.update, build_only = cmd.build_only, "starting rupes-recta test run");
-    let planned_runs = if selected_target_backends.is_empty() {
+    let mut planned_runs = if selected_target_backends.is_empty() {
         plan_test_or_bench_rr_from_resolved_all(
             cli,
             cmd,
@@ -1057,6 +1280,11 @@ pub(crate) fn run_test_or_bench_from_resolved(
             .flatten()
             .collect::<Vec<_>>()
     };
+    if let Some(packages) = cmd.affected_packages {
+        for (_, _, filter) in &mut planned_runs {
+            filter.retain_packages(packages);
+        }
+    }
     let effective_display_backend_hint = if planned_runs.len() > 1 {
         Some(())
     } else {
@@ -1104,7 +1332,8 @@ pub(crate) fn run_test_or_bench_from_resolved(
         .map(|(meta, _)| meta)
         .collect::<Vec<_>>();
     let build_graph = rr_build::compose_build_inputs(build_inputs)?;
-    let build_config = BuildConfig::from_flags(cmd.build_flags, &cli.unstable_feature, cli.verbose);
+    let build_config = BuildConfig::from_flags(cmd.build_flags, &cli.unstable_feature, cli.verbose)
+        .with_source_dir(source_dir);
     let build_graph_backup = cmd.update.then(|| build_graph.clone());
     let result = rr_build::execute_test_build(
         &build_config,
@@ -1122,6 +1351,8 @@ pub(crate) fn run_test_or_bench_from_resolved(
         build_graph_backup,
     };
 
+    print_shuffle_seed(cmd, user_log);
+    let mut report = cmd.reporter.map(|_| TestReport::default());
     let mut exit_code = 0;
     for (build_meta, filter) in build_metas_and_filters {
         debug!(
@@ -1130,7 +1361,7 @@ pub(crate) fn run_test_or_bench_from_resolved(
             "planned rupes-recta build graph"
         );
 
-        exit_code = exit_code.max(rr_test_after_build(
+        let target_exit_code = match rr_test_after_build(
             cli,
             cmd,
             source_dir,
@@ -1140,9 +1371,23 @@ pub(crate) fn run_test_or_bench_from_resolved(
             filter,
             &built,
             build_only_artifacts.as_mut(),
+            report.as_mut(),
             output,
-        )?);
+        ) {
+            Ok(exit_code) => exit_code,
+            Err(error) => {
+                return Err(write_interrupted_test_report(
+                    cmd,
+                    report,
+                    &build_meta,
+                    error,
+                    user_log,
+                ));
+            }
+        };
+        exit_code = exit_code.max(target_exit_code);
     }
+    write_test_report(cmd, report)?;
     if !cli.dry_run
         && let Some(test_artifacts) = build_only_artifacts
     {
@@ -1485,6 +1730,14 @@ fn selected_test_index(cmd: &TestLikeSubcommand<'_>) -> anyhow::Result<Option<Te
     }
 }
 
+/// The tags selected by `--tag` and `--exclude-tag`.
+fn tag_filter(tags: &[String], exclude_tags: &[String]) -> TagFilter {
+    TagFilter {
+        include: tags.to_vec(),
+        exclude: exclude_tags.to_vec(),
+    }
+}
+
 fn package_names(
     resolve_output: &moonbuild_rupes_recta::ResolveOutput,
     packages: &[PackageId],
@@ -1668,7 +1921,9 @@ fn rr_test_from_plan(
         TestBuildExecution::Built(built) => *built,
     };
 
-    rr_test_after_build(
+    print_shuffle_seed(cmd, output.user_log());
+    let mut report = cmd.reporter.map(|_| TestReport::default());
+    let exit_code = match rr_test_after_build(
         cli,
         cmd,
         source_dir,
@@ -1678,8 +1933,61 @@ fn rr_test_from_plan(
         filter,
         &built,
         build_only_artifacts,
+        report.as_mut(),
         output,
-    )
+    ) {
+        Ok(exit_code) => exit_code,
+        Err(error) => {
+            return Err(write_interrupted_test_report(
+                cmd,
+                report,
+                build_meta,
+                error,
+                output.user_log(),
+            ));
+        }
+    };
+    write_test_report(cmd, report)?;
+    Ok(exit_code)
+}
+
+/// Write the report collected for `--reporter`, if one was requested.
+fn write_test_report(
+    cmd: &TestLikeSubcommand<'_>,
+    report: Option<TestReport>,
+) -> anyhow::Result<()> {
+    match (cmd.reporter, report) {
+        (Some(reporter), Some(report)) => report.write(reporter, cmd.report_file.as_deref()),
+        _ => Ok(()),
+    }
+}
+
+/// Write the report collected before the tests of `meta` failed with
+/// `error`, which is returned for the caller to propagate.
+fn write_interrupted_test_report(
+    cmd: &TestLikeSubcommand<'_>,
+    report: Option<TestReport>,
+    meta: &rr_build::BuildMeta,
+    error: anyhow::Error,
+    user_log: &UserLog,
+) -> anyhow::Error {
+    let report = report.map(|mut report| {
+        report.add_error(meta.target_backend(), &error);
+        report
+    });
+    if let Err(e) = write_test_report(cmd, report) {
+        user_log.warn(format!("{e:#}"));
+    }
+    error
+}
+
+/// Print the seed of `--shuffle`, so that the order of a run can be replayed.
+fn print_shuffle_seed(cmd: &TestLikeSubcommand<'_>, user_log: &UserLog) {
+    if let Some(seed) = cmd.shuffle {
+        user_log.status(format!(
+            "Shuffling tests with seed {seed}, pass `--shuffle={seed}` to replay this order"
+        ));
+    }
 }
 
 #[instrument(level = Level::DEBUG, skip_all)]
@@ -1694,6 +2002,7 @@ fn rr_test_after_build(
     filter: TestFilter,
     built: &BuiltTestExecution,
     build_only_artifacts: Option<&mut TestArtifacts>,
+    report: Option<&mut TestReport>,
     output: &CommandOutput,
 ) -> Result<i32, anyhow::Error> {
     let user_log = output.user_log();
@@ -1743,6 +2052,40 @@ fn rr_test_after_build(
         );
     }
 
+    // `--rerun-failed` narrows the selection to the tests that failed last
+    // time, while `--failed-first` only runs them ahead of the others.
+    let filter = if cmd.rerun_failed {
+        let failed = last_failed(build_meta, &filter)?;
+        if failed.is_empty() {
+            user_log.warn("no failed tests were recorded by the last test run.");
+            return Ok(0);
+        }
+        TestFilter {
+            filter: Some(failed),
+            name_filter: filter.name_filter,
+            tags: filter.tags,
+        }
+    } else {
+        filter
+    };
+    let filter = match cmd.shard {
+        Some(shard) => select_shard(
+            build_meta,
+            &filter,
+            cmd.include_skipped,
+            cmd.run_mode == RunMode::Bench,
+            shard,
+            cmd.shard_by_duration,
+            user_log,
+        )?,
+        None => filter,
+    };
+    let run_first = if cmd.failed_first {
+        Some(last_failed(build_meta, &filter)?)
+    } else {
+        None
+    };
+
     let mut test_result = crate::run::run_tests(
         build_meta,
         source_dir,
@@ -1752,6 +2095,9 @@ fn rr_test_after_build(
         cmd.run_mode == RunMode::Bench,
         cmd.no_parallelize,
         cmd.build_flags.jobs,
+        cmd.timeout.map(Duration::from_secs),
+        run_first.as_ref(),
+        cmd.shuffle,
         user_log,
     )?;
     let _initial_summary = test_result.summary();
@@ -1835,6 +2181,7 @@ fn rr_test_after_build(
             let rerun_filter = TestFilter {
                 filter: Some(rerun_filter_raw),
                 name_filter: cmd.filter.clone(),
+                tags: tag_filter(cmd.tags, cmd.exclude_tags),
             };
             let new_test_result = crate::run::run_tests(
                 build_meta,
@@ -1845,6 +2192,9 @@ fn rr_test_after_build(
                 cmd.run_mode == RunMode::Bench,
                 cmd.no_parallelize,
                 cmd.build_flags.jobs,
+                cmd.timeout.map(Duration::from_secs),
+                None,
+                cmd.shuffle,
                 user_log,
             )?;
             let _rerun_summary = new_test_result.summary();
@@ -1855,21 +2205,85 @@ fn rr_test_after_build(
         }
     }
 
-    test_result.print_result(build_meta, cli.verbose, cmd.test_failure_json);
+    for attempt in 2..=cmd.retries.saturating_add(1) {
+        let retry_filter = test_result.failed_filter()?;
+        if retry_filter.is_empty() {
+            break;
+        }
+        let failed = test_result.summary();
+        user_log.info(format!(
+            "retrying {} failed test(s), attempt {attempt} of {}",
+            failed.total - failed.passed,
+            cmd.retries + 1
+        ));
+        let retry_result = crate::run::run_tests(
+            build_meta,
+            source_dir,
+            target_dir,
+            &TestFilter {
+                filter: Some(retry_filter),
+                name_filter: cmd.filter.clone(),
+                tags: tag_filter(cmd.tags, cmd.exclude_tags),
+            },
+            cmd.include_skipped,
+            cmd.run_mode == RunMode::Bench,
+            cmd.no_parallelize,
+            cmd.build_flags.jobs,
+            cmd.timeout.map(Duration::from_secs),
+            None,
+            cmd.shuffle,
+            user_log,
+        )?;
+        test_result.merge_retry(retry_result, attempt);
+    }
+
+    if cmd.run_mode == RunMode::Test {
+        test_result.save_outcomes(build_meta)?;
+    }
+
     let summary = test_result.summary();
-    print_test_summary(
-        summary.total,
-        summary.passed,
-        cli.quiet,
-        backend_hint,
-        user_log,
-    );
+    if let Some(report) = report {
+        test_result.add_to_report(build_meta, report);
+    }
+    // A report printed to stdout replaces the human-readable output.
+    if cmd.reporter.is_none() || cmd.report_file.is_some() {
+        test_result.print_result(build_meta, cli.verbose, cmd.test_failure_json);
+        if let Some(n) = cmd.report_slowest {
+            test_result.print_slowest(build_meta, n);
+        }
+        print_test_summary(
+            summary.total,
+            summary.passed,
+            summary.flaky,
+            cli.quiet,
+            backend_hint,
+            user_log,
+        );
+    }
 
-    if summary.total == summary.passed {
-        Ok(0)
+    let mut exit_code = if summary.total == summary.passed {
+        0
     } else {
-        Ok(2)
+        2
+    };
+    // Compare before saving, so that a run can be checked against the
+    // baseline it replaces.
+    if let Some(name) = cmd.bench_baseline {
+        let regressions =
+            test_result.compare_bench_baseline(build_meta, name, cmd.regression_threshold)?;
+        if regressions > 0 {
+            user_log.error(format!(
+                "{regressions} benchmark(s) regressed by more than {}% against baseline `{name}`",
+                cmd.regression_threshold
+            ));
+            exit_code = exit_code.max(1);
+        }
+    }
+    if let Some(name) = cmd.save_bench_baseline {
+        test_result.save_bench_baseline(build_meta, name)?;
+        user_log.info(format!("Saved benchmark baseline `{name}`"));
     }
+    Ok(exit_code)
 }
 
 struct BuiltTestExecution {
@@ -1913,7 +2327,8 @@ fn execute_test_build_from_plan(
     // before executing the build
     rr_build::generate_all_pkgs_json(build_meta)?;
 
-    let build_config = BuildConfig::from_flags(cmd.build_flags, &cli.unstable_feature, cli.verbose);
+    let build_config = BuildConfig::from_flags(cmd.build_flags, &cli.unstable_feature, cli.verbose)
+        .with_source_dir(source_dir);
 
     // since n2 build consumes the graph, we back it up for reruns
     let build_graph_backup = cmd.update.then(|| build_graph.clone());
@@ -154,7 +154,10 @@ pub(crate) fn run_build_binary_dep(
             ..BuildFlags::default()
         };
         let preconfig = preconfig_compile(
-            &AutoSyncFlags { frozen: false },
+            &AutoSyncFlags {
+                frozen: false,
+                locked: false,
+            },
             cli,
             &build_flags,
             Some(target),
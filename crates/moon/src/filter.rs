@@ -29,7 +29,7 @@ use anyhow::Context;
 use moonbuild_rupes_recta::{ResolveOutput, fmt::FmtResolveOutput, model::PackageId};
 use moonutil::resolution::{DirSyncResult, ResolvedEnv};
 use moonutil::{
-    constants::{MOON_PKG, MOON_PKG_JSON, is_moon_pkg_exist},
+    constants::{MOON_PKG, MOON_PKG_JSON, is_moon_mod, is_moon_pkg_exist, is_moon_work},
     target::TargetBackend,
     user_log::UserLog,
 };
@@ -202,6 +202,53 @@ pub(crate) fn filter_pkg_by_dir(
         })
 }
 
+/// Find the local packages whose tests may be affected by changes to `paths`:
+/// the packages containing the paths, and every package that depends on them.
+///
+/// Returns `None` when some path does not belong to a single package, e.g. a
+/// module manifest, in which case any test may be affected. `paths` should be
+/// canonicalized like package root paths are.
+pub(crate) fn affected_packages(
+    resolve_output: &ResolveOutput,
+    paths: &[PathBuf],
+) -> Option<HashSet<PackageId>> {
+    let local_packages = crate::rr_build::local_packages(resolve_output)
+        .map(|id| (id, &resolve_output.pkg_dirs.get_package(id).root_path))
+        .collect::<Vec<_>>();
+
+    let mut affected = HashSet::new();
+    for path in paths {
+        let file_name = path.file_name().map(|name| name.to_string_lossy());
+        if file_name.is_some_and(|name| is_moon_mod(&name) || is_moon_work(&name)) {
+            return None;
+        }
+        // Nested packages live under their parent's directory, so the deepest
+        // package root containing the path is the one it belongs to.
+        let (pkg, _) = local_packages
+            .iter()
+            .filter(|(_, root)| path.starts_with(root))
+            .max_by_key(|(_, root)| root.components().count())?;
+        affected.insert(*pkg);
+    }
+
+    // Dependency edges point from dependents to dependencies, so walk them
+    // backwards to find everything that may observe the change.
+    let dep_graph = &resolve_output.pkg_rel.dep_graph;
+    let mut pending = dep_graph
+        .nodes()
+        .filter(|target| affected.contains(&target.package))
+        .collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+    while let Some(target) = pending.pop() {
+        if !visited.insert(target) {
+            continue;
+        }
+        affected.insert(target.package);
+        pending.extend(dep_graph.neighbors_directed(target, petgraph::Direction::Incoming));
+    }
+    Some(affected)
+}
+
 /// Given an invalid input path, report a helpful error message indicating why
 /// no package could be found.
 pub(crate) fn report_package_not_found(
@@ -669,4 +716,44 @@ mod tests {
             external_pkg
         );
     }
+
+    #[test]
+    fn affected_packages_include_reverse_dependencies() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write_file(
+            &root.join(MOON_MOD_JSON),
+            "{ \"name\": \"user/proj\", \"version\": \"0.1.0\" }",
+        );
+        write_file(&root.join("lib").join(MOON_PKG_JSON), "{}");
+        write_file(
+            &root.join("app").join(MOON_PKG_JSON),
+            "{ \"import\": [\"user/proj/lib\"] }",
+        );
+        write_file(&root.join("other").join(MOON_PKG_JSON), "{}");
+
+        let root = canonical(root);
+        let resolved = resolve_output(&root);
+        let affected = |paths: &[&str]| {
+            let paths = paths.iter().map(|p| root.join(p)).collect::<Vec<_>>();
+            super::affected_packages(&resolved, &paths).map(|packages| {
+                let mut names = packages
+                    .into_iter()
+                    .map(|pkg| resolved.pkg_dirs.fqn(pkg).to_string())
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+        };
+
+        assert_eq!(
+            affected(&["lib/lib.mbt"]).unwrap(),
+            ["user/proj/app", "user/proj/lib"]
+        );
+        assert_eq!(
+            affected(&["app/app.mbt", "other/moon.pkg.json"]).unwrap(),
+            ["user/proj/app", "user/proj/other"]
+        );
+        assert_eq!(affected(&[MOON_MOD_JSON]), None);
+    }
 }
@@ -66,6 +66,11 @@ impl ActionIdentityContext {
 pub struct ActionDigest([u8; 32]);
 
 impl ActionDigest {
+    #[cfg(test)]
+    pub(crate) fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
     pub fn as_bytes(&self) -> &[u8; 32] {
         &self.0
     }
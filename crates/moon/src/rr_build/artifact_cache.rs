@@ -0,0 +1,705 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Global reuse of package compiler outputs by canonical action identity.
+//!
+//! The store is content addressed. Each published output is an immutable
+//! object named by its BLAKE3 digest, and a small action record maps an
+//! [`ActionDigest`] to the complete set of objects that action produced.
+//! Objects are published before the record, both through a same-filesystem
+//! staging file and a rename, so a reader sees either a complete result or a
+//! miss.
+//!
+//! ```text
+//! <build-cache>/v1/actions/<action digest>
+//! <build-cache>/v1/objects/<output digest>
+//! <build-cache>/v1/tmp/
+//! ```
+//!
+//! Restored outputs are copies in the invocation's private `_build`; the
+//! shared store is never used as a live build directory.
+//!
+//! An optional [`RemoteCache`] sits behind the local store. Local misses are
+//! looked up remotely, verified, and kept locally; local publications are
+//! uploaded unless the remote tier is read-only.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use moonbuild_rupes_recta::{
+    build_plan::ArtifactKey,
+    execution_plan::{ActionId, ExecutionPlan, InputObservation},
+};
+use moonutil::cache::{CacheRoot, RemoteCacheConfig};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{
+    action_identity::{ActionDigest, ActionIdentityContext, compute_action_identities},
+    remote_cache::RemoteCache,
+};
+
+const LAYOUT_VERSION: &str = "v1";
+
+/// Some filesystems record modification times with a coarse granularity.
+/// Outputs stamped this close before execution started still count as fresh.
+const MTIME_SLACK: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// An initialized build-artifact cache root.
+pub(super) struct ArtifactCache {
+    root: PathBuf,
+    remote: Option<RemoteCache>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ActionRecord {
+    outputs: Vec<RecordedOutput>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedOutput {
+    path: PathBuf,
+    object: String,
+    size: u64,
+}
+
+impl RecordedOutput {
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() as u64 == self.size && blake3::hash(bytes).to_hex().as_str() == self.object
+    }
+}
+
+/// Object names come from records that may have been downloaded, so they are
+/// checked before being used as paths.
+fn is_object_name(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+impl ArtifactCache {
+    /// Claim a configured root. Disabled roots yield no cache, and the remote
+    /// tier is only used behind an enabled local root.
+    pub(super) fn open(
+        root: &CacheRoot,
+        remote: Option<&RemoteCacheConfig>,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(root) = root.initialize()? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            root: root.join(LAYOUT_VERSION),
+            remote: remote.map(RemoteCache::connect).transpose()?,
+        }))
+    }
+
+    #[cfg(test)]
+    fn at(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            remote: None,
+        }
+    }
+
+    fn record_path(&self, digest: ActionDigest) -> PathBuf {
+        self.root.join("actions").join(digest.to_hex())
+    }
+
+    fn object_path(&self, object: &str) -> PathBuf {
+        self.root.join("objects").join(object)
+    }
+
+    fn staging_dir(&self) -> anyhow::Result<PathBuf> {
+        let dir = self.root.join("tmp");
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// Restore the complete output set of `digest` into `outputs`.
+    ///
+    /// Returns `false` for a miss, including a record whose outputs differ from
+    /// the requested set or whose objects fail validation. Nothing is written
+    /// unless every object has been validated. Outputs whose bytes already
+    /// match are left untouched so that consumers do not observe a new mtime.
+    pub(super) fn restore(
+        &self,
+        digest: ActionDigest,
+        outputs: &[PathBuf],
+    ) -> anyhow::Result<bool> {
+        let (bytes, downloaded) = match std::fs::read(self.record_path(digest)) {
+            Ok(record) => (record, false),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                match self
+                    .remote
+                    .as_ref()
+                    .and_then(|remote| remote.get_record(digest))
+                {
+                    Some(record) => (record, true),
+                    None => return Ok(false),
+                }
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let record = match serde_json::from_slice::<ActionRecord>(&bytes) {
+            Ok(record)
+                if record
+                    .outputs
+                    .iter()
+                    .all(|output| is_object_name(&output.object)) =>
+            {
+                record
+            }
+            _ => {
+                warn!("ignoring malformed build cache record for {digest:?}");
+                return Ok(false);
+            }
+        };
+
+        let requested = outputs.iter().collect::<HashSet<_>>();
+        let recorded = record
+            .outputs
+            .iter()
+            .map(|output| &output.path)
+            .collect::<HashSet<_>>();
+        if requested != recorded || record.outputs.len() != outputs.len() {
+            return Ok(false);
+        }
+
+        let mut contents = Vec::with_capacity(record.outputs.len());
+        for output in &record.outputs {
+            let Some(bytes) = self.load_object(output)? else {
+                return Ok(false);
+            };
+            contents.push((&output.path, bytes));
+        }
+
+        for (path, bytes) in contents {
+            if std::fs::read(path).is_ok_and(|existing| existing == bytes) {
+                continue;
+            }
+            let parent = path
+                .parent()
+                .with_context(|| format!("output {} has no parent", path.display()))?;
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+            let mut staged = tempfile::NamedTempFile::new_in(parent)?;
+            staged.write_all(&bytes)?;
+            staged
+                .persist(path)
+                .with_context(|| format!("failed to restore {}", path.display()))?;
+        }
+        if downloaded {
+            // Every object is stored locally by now, so the record may follow.
+            if let Err(error) = self.store_record(digest, &bytes) {
+                warn!("failed to keep downloaded build cache record for {digest:?}: {error:#}");
+            }
+        }
+        Ok(true)
+    }
+
+    /// Read and verify one recorded object, downloading it on a local miss.
+    fn load_object(&self, output: &RecordedOutput) -> anyhow::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.object_path(&output.object)) {
+            Ok(bytes) if output.matches(&bytes) => return Ok(Some(bytes)),
+            Ok(_) => {
+                warn!("ignoring corrupted build cache object {}", output.object);
+                return Ok(None);
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => return Err(error.into()),
+        }
+        let Some(bytes) = self
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.get_object(&output.object))
+        else {
+            return Ok(None);
+        };
+        if !output.matches(&bytes) {
+            warn!(
+                "ignoring remote build cache object {} that does not match its digest",
+                output.object
+            );
+            return Ok(None);
+        }
+        self.store_object(&output.object, &bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Publish one immutable object. An existing object is kept as is.
+    fn store_object(&self, object: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let object_path = self.object_path(object);
+        if object_path.is_file() {
+            return Ok(());
+        }
+        let objects = object_path.parent().expect("object path has a parent");
+        std::fs::create_dir_all(objects)
+            .with_context(|| format!("failed to create {}", objects.display()))?;
+        let mut staged = tempfile::NamedTempFile::new_in(self.staging_dir()?)?;
+        staged.write_all(bytes)?;
+        match staged.persist_noclobber(&object_path) {
+            Ok(_) => Ok(()),
+            Err(error) if error.error.kind() == ErrorKind::AlreadyExists => Ok(()),
+            Err(error) => Err(error.error.into()),
+        }
+    }
+
+    fn store_record(&self, digest: ActionDigest, bytes: &[u8]) -> anyhow::Result<()> {
+        let record_path = self.record_path(digest);
+        let records = record_path.parent().expect("record path has a parent");
+        std::fs::create_dir_all(records)
+            .with_context(|| format!("failed to create {}", records.display()))?;
+        let mut staged = tempfile::NamedTempFile::new_in(self.staging_dir()?)?;
+        staged.write_all(bytes)?;
+        staged.persist(&record_path)?;
+        Ok(())
+    }
+
+    /// Publish the outputs of one successful action under `digest`.
+    ///
+    /// Objects are published first and the action record last, locally and
+    /// then remotely. Concurrent writers of the same action produce the same
+    /// objects, so replacing a record is harmless.
+    pub(super) fn publish(&self, digest: ActionDigest, outputs: &[PathBuf]) -> anyhow::Result<()> {
+        let remote = self.remote.as_ref().filter(|remote| remote.is_writable());
+        let mut uploads = Vec::new();
+        let mut record = ActionRecord {
+            outputs: Vec::with_capacity(outputs.len()),
+        };
+        for path in outputs {
+            let metadata = std::fs::symlink_metadata(path)
+                .with_context(|| format!("failed to inspect output {}", path.display()))?;
+            anyhow::ensure!(
+                metadata.is_file(),
+                "output is not a regular file: {}",
+                path.display()
+            );
+            let bytes = std::fs::read(path)
+                .with_context(|| format!("failed to read output {}", path.display()))?;
+            let object = blake3::hash(&bytes).to_hex().to_string();
+            self.store_object(&object, &bytes)?;
+            record.outputs.push(RecordedOutput {
+                path: path.clone(),
+                object: object.clone(),
+                size: bytes.len() as u64,
+            });
+            if remote.is_some() {
+                uploads.push((object, bytes));
+            }
+        }
+
+        let record = serde_json::to_vec(&record)?;
+        self.store_record(digest, &record)?;
+        if let Some(remote) = remote {
+            for (object, bytes) in uploads {
+                remote.put_object(&object, bytes)?;
+            }
+            remote.put_record(digest, record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether every declared output of an action is a package compiler artifact
+/// that may be shared: `.mi` interfaces and `.core` IR.
+fn produces_reusable_artifacts(plan: &ExecutionPlan, action: ActionId) -> bool {
+    let outputs = plan.action(action).outputs();
+    !outputs.is_empty()
+        && outputs.iter().all(|path| {
+            plan.declared_output(path)
+                .and_then(|output| output.artifact())
+                .is_some_and(|artifact| {
+                    matches!(
+                        artifact,
+                        ArtifactKey::CheckMi { .. }
+                            | ArtifactKey::BuildMi { .. }
+                            | ArtifactKey::CoreIr { .. }
+                            | ArtifactKey::VirtualContractMi { .. }
+                    )
+                })
+        })
+}
+
+/// The part of one execution phase left for the executor after cache lookup.
+pub(super) struct CacheLookup {
+    /// Selected actions that still have to run, in their original order.
+    pub(super) action_ids: Vec<ActionId>,
+    /// Executed actions whose outputs may be published after success.
+    publishable: HashMap<ActionId, ActionDigest>,
+    started: SystemTime,
+}
+
+impl CacheLookup {
+    /// A lookup that restores nothing and publishes nothing.
+    pub(super) fn uncached(action_ids: Vec<ActionId>) -> Self {
+        Self {
+            action_ids,
+            publishable: HashMap::new(),
+            started: SystemTime::now(),
+        }
+    }
+}
+
+/// Restore cache hits among the selected actions and prune work they make
+/// unnecessary.
+///
+/// Starting from the actions whose outputs no selected action consumes, a
+/// restored action stops the walk: its producers run only if another
+/// executed action still needs them. Lookup failures are treated as misses.
+pub(super) fn restore_cached_actions(
+    cache: &ArtifactCache,
+    plan: &ExecutionPlan,
+    selected: Vec<ActionId>,
+) -> anyhow::Result<CacheLookup> {
+    let started = SystemTime::now();
+    let context = ActionIdentityContext::new(
+        std::env::current_dir().context("failed to read the current directory")?,
+        std::env::vars_os().collect(),
+    );
+    let identities = match compute_action_identities(plan, &context) {
+        Ok(identities) => identities,
+        Err(error) => {
+            // Identity calculation reads every eligible external input. A
+            // plan it cannot model still builds; it just cannot be shared.
+            info!("build cache disabled for this execution: {error:#}");
+            return Ok(CacheLookup::uncached(selected));
+        }
+    };
+
+    let identity_by_id = plan.action_ids().zip(identities).collect::<HashMap<_, _>>();
+    let selected_set = selected.iter().copied().collect::<HashSet<_>>();
+    let mut producers = HashMap::<ActionId, Vec<ActionId>>::new();
+    let mut consumed = HashSet::new();
+    for &id in &selected {
+        for input in plan.action(id).inputs() {
+            let InputObservation::File(path) = input else {
+                continue;
+            };
+            let Some(output) = plan.declared_output(path) else {
+                continue;
+            };
+            if selected_set.contains(&output.producer()) {
+                producers.entry(id).or_default().push(output.producer());
+                consumed.insert(path.as_path());
+            }
+        }
+    }
+
+    let mut pending = selected
+        .iter()
+        .copied()
+        .filter(|id| {
+            plan.action(*id)
+                .outputs()
+                .iter()
+                .any(|path| !consumed.contains(path.as_path()))
+        })
+        .collect::<Vec<_>>();
+    let mut visited = HashSet::new();
+    let mut executed = HashSet::new();
+    let mut publishable = HashMap::new();
+    let mut restored = 0usize;
+    while let Some(id) = pending.pop() {
+        if !visited.insert(id) {
+            continue;
+        }
+        let identity = identity_by_id[&id];
+        if identity.is_cacheable() && produces_reusable_artifacts(plan, id) {
+            match cache.restore(identity.digest(), plan.action(id).outputs()) {
+                Ok(true) => {
+                    restored += 1;
+                    continue;
+                }
+                Ok(false) => {}
+                Err(error) => warn!("build cache lookup failed for {id:?}: {error:#}"),
+            }
+            publishable.insert(id, identity.digest());
+        }
+        executed.insert(id);
+        pending.extend(producers.get(&id).into_iter().flatten().copied());
+    }
+    if restored != 0 {
+        info!("restored {restored} actions from the build cache");
+    }
+
+    Ok(CacheLookup {
+        action_ids: selected
+            .into_iter()
+            .filter(|id| executed.contains(id))
+            .collect(),
+        publishable,
+        started,
+    })
+}
+
+/// Publish executed actions after a successful execution.
+///
+/// An action is published only if it emitted no output, so a later hit cannot
+/// hide its diagnostics, and only if all its outputs were written during this
+/// execution rather than left up to date from an earlier one.
+pub(super) fn publish_executed_actions(
+    cache: &ArtifactCache,
+    plan: &ExecutionPlan,
+    lookup: &CacheLookup,
+    actions_with_output: &HashSet<ActionId>,
+) {
+    let fresh_after = lookup
+        .started
+        .checked_sub(MTIME_SLACK)
+        .unwrap_or(lookup.started);
+    for (&id, &digest) in &lookup.publishable {
+        if actions_with_output.contains(&id) {
+            continue;
+        }
+        let outputs = plan.action(id).outputs();
+        let fresh = outputs.iter().all(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified >= fresh_after)
+        });
+        if !fresh {
+            continue;
+        }
+        if let Err(error) = cache.publish(digest, outputs) {
+            warn!("failed to publish {id:?} to the build cache: {error:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use moonutil::cache::{RemoteCacheLocation, RemoteCacheMode};
+
+    use super::*;
+
+    fn digest(seed: u8) -> ActionDigest {
+        ActionDigest::from_bytes([seed; 32])
+    }
+
+    fn outputs(root: &Path) -> Vec<PathBuf> {
+        vec![
+            root.join("_build/pkg/pkg.mi"),
+            root.join("_build/pkg/pkg.core"),
+        ]
+    }
+
+    fn write_outputs(paths: &[PathBuf]) {
+        for path in paths {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, path.display().to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn published_outputs_are_restored_as_a_unit() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let cache = ArtifactCache::at(sandbox.path().join("cache"));
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+
+        cache.publish(digest(1), &paths).unwrap();
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+
+        assert!(cache.restore(digest(1), &paths).unwrap());
+        for path in &paths {
+            assert_eq!(
+                fs::read_to_string(path).unwrap(),
+                path.display().to_string()
+            );
+        }
+        assert!(!cache.restore(digest(2), &paths).unwrap());
+    }
+
+    #[test]
+    fn a_different_output_set_is_a_miss() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let cache = ArtifactCache::at(sandbox.path().join("cache"));
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+        cache.publish(digest(1), &paths).unwrap();
+
+        assert!(!cache.restore(digest(1), &paths[..1]).unwrap());
+        let mut moved = paths.clone();
+        moved[1] = sandbox.path().join("_build/other/pkg.core");
+        assert!(!cache.restore(digest(1), &moved).unwrap());
+        assert!(!moved[1].exists());
+    }
+
+    #[test]
+    fn corrupted_objects_are_misses_and_nothing_is_written() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let cache = ArtifactCache::at(sandbox.path().join("cache"));
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+        cache.publish(digest(1), &paths).unwrap();
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+
+        let corrupted = blake3::hash(paths[1].display().to_string().as_bytes())
+            .to_hex()
+            .to_string();
+        fs::write(sandbox.path().join("cache/objects").join(corrupted), "bad").unwrap();
+
+        assert!(!cache.restore(digest(1), &paths).unwrap());
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+
+    fn with_remote(root: &Path, remote: &Path, mode: RemoteCacheMode) -> ArtifactCache {
+        let config = RemoteCacheConfig {
+            location: RemoteCacheLocation::Directory(remote.to_path_buf()),
+            mode,
+        };
+        ArtifactCache {
+            root: root.to_path_buf(),
+            remote: Some(RemoteCache::connect(&config).unwrap()),
+        }
+    }
+
+    #[test]
+    fn remote_results_are_verified_and_kept_locally() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let remote = sandbox.path().join("remote");
+        let ci = with_remote(
+            &sandbox.path().join("ci"),
+            &remote,
+            RemoteCacheMode::ReadWrite,
+        );
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+        ci.publish(digest(1), &paths).unwrap();
+        assert!(remote.join("ac").join(digest(1).to_hex()).is_file());
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+
+        let laptop_root = sandbox.path().join("laptop");
+        let laptop = with_remote(&laptop_root, &remote, RemoteCacheMode::ReadOnly);
+        assert!(laptop.restore(digest(1), &paths).unwrap());
+        assert!(paths.iter().all(|path| path.is_file()));
+
+        // The local store now answers without the remote tier.
+        fs::remove_dir_all(&remote).unwrap();
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+        assert!(
+            ArtifactCache::at(&laptop_root)
+                .restore(digest(1), &paths)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn read_only_clients_do_not_upload() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let remote = sandbox.path().join("remote");
+        let cache = with_remote(
+            &sandbox.path().join("cache"),
+            &remote,
+            RemoteCacheMode::ReadOnly,
+        );
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+
+        cache.publish(digest(1), &paths).unwrap();
+
+        assert!(cache.restore(digest(1), &paths).unwrap());
+        assert!(!remote.exists());
+    }
+
+    #[test]
+    fn tampered_remote_objects_are_misses() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let remote = sandbox.path().join("remote");
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+        with_remote(
+            &sandbox.path().join("ci"),
+            &remote,
+            RemoteCacheMode::ReadWrite,
+        )
+        .publish(digest(1), &paths)
+        .unwrap();
+        for path in &paths {
+            fs::remove_file(path).unwrap();
+        }
+        for object in fs::read_dir(remote.join("cas")).unwrap() {
+            fs::write(object.unwrap().path(), "tampered").unwrap();
+        }
+
+        let laptop_root = sandbox.path().join("laptop");
+        let laptop = with_remote(&laptop_root, &remote, RemoteCacheMode::ReadOnly);
+        assert!(!laptop.restore(digest(1), &paths).unwrap());
+        assert!(paths.iter().all(|path| !path.exists()));
+        assert!(!laptop_root.join("objects").exists());
+        assert!(!laptop_root.join("actions").exists());
+    }
+
+    #[test]
+    fn remote_records_cannot_name_paths_outside_the_store() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let remote = sandbox.path().join("remote");
+        let paths = outputs(sandbox.path());
+        let record = ActionRecord {
+            outputs: paths
+                .iter()
+                .map(|path| RecordedOutput {
+                    path: path.clone(),
+                    object: "../../secret".into(),
+                    size: 0,
+                })
+                .collect(),
+        };
+        fs::create_dir_all(remote.join("ac")).unwrap();
+        fs::write(
+            remote.join("ac").join(digest(1).to_hex()),
+            serde_json::to_vec(&record).unwrap(),
+        )
+        .unwrap();
+
+        let cache = with_remote(
+            &sandbox.path().join("cache"),
+            &remote,
+            RemoteCacheMode::ReadOnly,
+        );
+        assert!(!cache.restore(digest(1), &paths).unwrap());
+    }
+
+    #[test]
+    fn identical_outputs_keep_their_modification_time() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let cache = ArtifactCache::at(sandbox.path().join("cache"));
+        let paths = outputs(sandbox.path());
+        write_outputs(&paths);
+        cache.publish(digest(1), &paths).unwrap();
+
+        let before = fs::metadata(&paths[0]).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(cache.restore(digest(1), &paths).unwrap());
+        assert_eq!(fs::metadata(&paths[0]).unwrap().modified().unwrap(), before);
+    }
+}
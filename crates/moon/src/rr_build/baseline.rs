@@ -0,0 +1,284 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Diagnostic baselines for `moon check --baseline` and `--write-baseline`.
+//!
+//! A baseline records the diagnostics of a codebase at one point in time, so
+//! that only new ones are reported afterwards. Diagnostics are identified by
+//! their path, error code and the (whitespace-normalized) source lines they
+//! point at, rather than by line numbers, so that unrelated edits that shift
+//! code around don't invalidate the baseline.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context;
+use moonbuild::entry::ResultCatcher;
+use moonutil::render::MooncDiagnostic;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+const BASELINE_VERSION: u32 = 1;
+
+/// How a build uses a diagnostic baseline.
+#[derive(Debug, Clone)]
+pub struct BaselineConfig {
+    /// The baseline file
+    pub file: PathBuf,
+    /// Diagnostic paths are recorded relative to this directory
+    pub root: PathBuf,
+    pub mode: BaselineMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineMode {
+    /// Hide the diagnostics recorded in the baseline. With
+    /// `deny_new_warnings`, any other warning fails the build.
+    Suppress { deny_new_warnings: bool },
+    /// Record all diagnostics of the build to the baseline.
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct Fingerprint {
+    path: String,
+    error_code: u32,
+    snippet: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineEntry {
+    #[serde(flatten)]
+    fingerprint: Fingerprint,
+    /// How many diagnostics share this fingerprint
+    count: usize,
+    /// The message of the diagnostic, only for readers of the file
+    message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    diagnostics: Vec<BaselineEntry>,
+}
+
+/// Computes fingerprints, reading each source file at most once.
+struct Fingerprinter<'a> {
+    root: &'a Path,
+    sources: HashMap<PathBuf, Option<String>>,
+}
+
+impl<'a> Fingerprinter<'a> {
+    fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            sources: HashMap::new(),
+        }
+    }
+
+    fn fingerprint(&mut self, diagnostic: &MooncDiagnostic) -> Fingerprint {
+        let path = Path::new(&diagnostic.path);
+        let full_path = self.root.join(path);
+        let relative = full_path.strip_prefix(self.root).unwrap_or(path);
+        let relative = relative
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let (start, end) = (diagnostic.loc.start.line, diagnostic.loc.end.line);
+        let lines = (!diagnostic.path.is_empty() && start > 0)
+            .then(|| {
+                self.sources
+                    .entry(full_path.clone())
+                    .or_insert_with(|| std::fs::read_to_string(&full_path).ok())
+                    .as_deref()
+            })
+            .flatten()
+            .map(|content| {
+                content
+                    .lines()
+                    .skip(start - 1)
+                    .take(end.max(start) - start + 1)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            });
+        // Fall back to the message when the source is unavailable.
+        let snippet = lines.as_deref().unwrap_or(&diagnostic.message);
+
+        Fingerprint {
+            path: relative,
+            error_code: diagnostic.error_code,
+            snippet: snippet.split_whitespace().collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Apply the baseline to the captured output of a build, returning the number
+/// of warnings that are not in the baseline.
+pub(super) fn apply(
+    config: &BaselineConfig,
+    outputs: &mut [&mut ResultCatcher],
+) -> anyhow::Result<usize> {
+    match config.mode {
+        BaselineMode::Write => {
+            write(config, outputs)?;
+            Ok(0)
+        }
+        BaselineMode::Suppress { .. } => suppress(config, outputs),
+    }
+}
+
+fn write(config: &BaselineConfig, outputs: &[&mut ResultCatcher]) -> anyhow::Result<()> {
+    let mut fingerprinter = Fingerprinter::new(&config.root);
+    let mut entries = BTreeMap::<Fingerprint, BaselineEntry>::new();
+    for diagnostic in outputs.iter().flat_map(|output| diagnostics(output)) {
+        let fingerprint = fingerprinter.fingerprint(&diagnostic);
+        entries
+            .entry(fingerprint.clone())
+            .or_insert_with(|| BaselineEntry {
+                fingerprint,
+                count: 0,
+                message: diagnostic.message.clone(),
+            })
+            .count += 1;
+    }
+
+    let file = BaselineFile {
+        version: BASELINE_VERSION,
+        diagnostics: entries.into_values().collect(),
+    };
+    let content = serde_json::to_string_pretty(&file)? + "\n";
+    std::fs::write(&config.file, content).with_context(|| {
+        format!(
+            "failed to write diagnostic baseline to {}",
+            config.file.display()
+        )
+    })
+}
+
+fn suppress(config: &BaselineConfig, outputs: &mut [&mut ResultCatcher]) -> anyhow::Result<usize> {
+    let content = std::fs::read_to_string(&config.file).with_context(|| {
+        format!(
+            "failed to read diagnostic baseline from {}",
+            config.file.display()
+        )
+    })?;
+    let file: BaselineFile = serde_json_lenient::from_str(&content).with_context(|| {
+        format!(
+            "failed to parse diagnostic baseline at {}",
+            config.file.display()
+        )
+    })?;
+    if file.version != BASELINE_VERSION {
+        anyhow::bail!(
+            "unsupported diagnostic baseline version {} in {}, expected {BASELINE_VERSION}",
+            file.version,
+            config.file.display()
+        );
+    }
+
+    let mut remaining: HashMap<Fingerprint, usize> = file
+        .diagnostics
+        .into_iter()
+        .map(|entry| (entry.fingerprint, entry.count))
+        .collect();
+    let mut fingerprinter = Fingerprinter::new(&config.root);
+    let mut suppressed = 0;
+    let mut new_warnings = 0;
+    for output in outputs {
+        output.content_writer.retain(|content| {
+            let Ok(diagnostic) = serde_json::from_str::<MooncDiagnostic>(content) else {
+                return true;
+            };
+            if let Some(count) = remaining.get_mut(&fingerprinter.fingerprint(&diagnostic))
+                && *count > 0
+            {
+                *count -= 1;
+                suppressed += 1;
+                return false;
+            }
+            if matches!(diagnostic.level.as_str(), "warn" | "warning") {
+                new_warnings += 1;
+            }
+            true
+        });
+    }
+    info!(suppressed, new_warnings, "applied diagnostic baseline");
+    Ok(new_warnings)
+}
+
+fn diagnostics(output: &ResultCatcher) -> impl Iterator<Item = MooncDiagnostic> + '_ {
+    output
+        .content_writer
+        .iter()
+        .filter_map(|content| serde_json::from_str(content).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catcher(diagnostics: &[(&str, &str, &str)]) -> ResultCatcher {
+        let mut catcher = ResultCatcher::default();
+        for (path, loc, level) in diagnostics {
+            let diagnostic = serde_json::json!({
+                "path": path,
+                "loc": loc,
+                "level": level,
+                "error_code": 2,
+                "message": "Unused variable",
+            });
+            catcher.append_content(diagnostic.to_string(), None);
+        }
+        catcher.append_content("not a diagnostic", None);
+        catcher
+    }
+
+    #[test]
+    fn baselined_diagnostics_survive_line_shifts() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("a.mbt");
+        std::fs::write(&source, "fn f() {\n  let x = 1\n  let y = 2\n}\n").unwrap();
+        let mut config = BaselineConfig {
+            file: dir.path().join("baseline.json"),
+            root: dir.path().to_path_buf(),
+            mode: BaselineMode::Write,
+        };
+        let mut recorded = catcher(&[("./a.mbt", "2:7-2:8", "warning")]);
+        apply(&config, &mut [&mut recorded]).unwrap();
+
+        // Shift the recorded warning down a line and add a new one.
+        std::fs::write(&source, "// doc\nfn f() {\n  let x = 1\n  let y = 2\n}\n").unwrap();
+        config.mode = BaselineMode::Suppress {
+            deny_new_warnings: true,
+        };
+        let mut current = catcher(&[
+            (source.to_str().unwrap(), "3:7-3:8", "warning"),
+            (source.to_str().unwrap(), "4:7-4:8", "warning"),
+        ]);
+        let new_warnings = apply(&config, &mut [&mut current]).unwrap();
+
+        assert_eq!(new_warnings, 1);
+        assert_eq!(current.content_writer.len(), 2);
+        assert!(current.content_writer[0].contains("4:7-4:8"));
+    }
+}
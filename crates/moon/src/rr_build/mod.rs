@@ -55,6 +55,7 @@ use moonbuild_rupes_recta::{
 };
 use moonutil::{
     build_options::RunMode,
+    cache::{CacheKind, resolve_cache_root, resolve_remote_build_cache},
     cli_support::AutoSyncFlags,
     cli_support::UniversalFlags,
     compiler_flags::{self, CC},
@@ -72,8 +73,14 @@ use tracing::{Level, info, instrument};
 
 use crate::build_flags::{BuildFlags, OutputStyle};
 
+use self::artifact_cache::{ArtifactCache, CacheLookup};
+
 pub mod action_identity;
+mod artifact_cache;
+mod baseline;
 mod dry_run;
+mod remote_cache;
+pub use baseline::{BaselineConfig, BaselineMode};
 pub use dry_run::{
     format_dry_run_command, write_dry_run, write_dry_run_all, write_standalone_dry_run,
 };
@@ -299,7 +306,7 @@ impl BuildResult {
 /// This type might be subject to change.
 #[derive(Debug)]
 pub struct CompilePreConfig {
-    frozen: bool,
+    sync_flags: AutoSyncFlags,
     target_backend: Option<TargetBackend>,
     opt_level: BuildProfile,
     action: RunMode,
@@ -325,8 +332,8 @@ pub struct CompilePreConfig {
 
 impl CompilePreConfig {
     pub(crate) fn resolve_config(&self) -> ResolveConfig {
-        ResolveConfig::new_with_load_defaults(
-            self.frozen,
+        ResolveConfig::new(
+            self.sync_flags.clone(),
             !self.use_std,
             self.enable_coverage,
             self.workspace_env.clone(),
@@ -529,7 +536,7 @@ pub fn preconfig_compile(
     let opt_level = build_flags.effective_profile(action);
 
     CompilePreConfig {
-        frozen: auto_sync_flags.frozen,
+        sync_flags: auto_sync_flags.clone(),
         target_dir: target_dir.to_owned(),
         target_backend: selected_target_backend,
         opt_level,
@@ -1039,8 +1046,17 @@ pub struct BuildConfig {
     verbose: bool,
     suppress_progress: bool,
 
+    /// Reuse and publish package compiler outputs through `MOON_BUILD_CACHE`
+    use_build_cache: bool,
+
     /// The patch file to use
     pub patch_file: Option<PathBuf>,
+
+    /// The diagnostic baseline to suppress known diagnostics with, or to record
+    pub baseline: Option<BaselineConfig>,
+    /// The project source directory, which paths in `--message-format`
+    /// output are relative to
+    pub source_dir: Option<PathBuf>,
 }
 
 impl BuildConfig {
@@ -1059,7 +1075,10 @@ impl BuildConfig {
             n2_explain: unstable_features.rr_n2_explain,
             verbose,
             suppress_progress: false,
+            use_build_cache: true,
             patch_file: None,
+            baseline: None,
+            source_dir: None,
         }
     }
 
@@ -1067,6 +1086,11 @@ impl BuildConfig {
         self.suppress_progress = suppress_progress;
         self
     }
+
+    pub(crate) fn with_source_dir(mut self, source_dir: &Path) -> Self {
+        self.source_dir = Some(source_dir.to_path_buf());
+        self
+    }
 }
 
 impl Default for BuildConfig {
@@ -1081,7 +1105,10 @@ impl Default for BuildConfig {
             n2_explain: false,
             verbose: false,
             suppress_progress: false,
+            use_build_cache: false,
             patch_file: None,
+            baseline: None,
+            source_dir: None,
         }
     }
 }
@@ -1185,13 +1212,14 @@ impl BuildInput {
             .adapt_to_n2(self.action_ids.iter().copied())?;
         let (graph, _, action_by_build) = adapted.into_parts_with_actions();
         let backend_by_build = action_by_build
-            .into_iter()
-            .map(|(build, action)| (build, self.action_backends.get(&action).copied().flatten()))
+            .iter()
+            .map(|(build, action)| (*build, self.action_backends.get(action).copied().flatten()))
             .collect();
         Ok(N2ExecutionInput {
             graph,
             db_path: self.db_path,
             backend_by_build,
+            action_by_build,
         })
     }
 }
@@ -1200,6 +1228,7 @@ struct N2ExecutionInput {
     graph: n2::graph::Graph,
     db_path: PathBuf,
     backend_by_build: HashMap<n2::graph::BuildId, Option<TargetBackend>>,
+    action_by_build: HashMap<n2::graph::BuildId, ActionId>,
 }
 
 pub(crate) fn compose_build_inputs(inputs: Vec<BuildInput>) -> anyhow::Result<BuildInput> {
@@ -1212,6 +1241,7 @@ struct CapturedBuildExecution {
 }
 
 struct CapturedActionOutput {
+    build: n2::graph::BuildId,
     target_backend: Option<TargetBackend>,
     content: ResultCatcher,
 }
@@ -1267,7 +1297,7 @@ pub fn execute_build(
     target_dir: &Path,
     user_log: &UserLog,
 ) -> anyhow::Result<N2RunStats> {
-    let execution = execute_build_capturing(cfg, input, target_dir)?;
+    let execution = execute_build_capturing(cfg, input, target_dir, user_log)?;
     Ok(finish_captured_build(cfg, &execution, None, user_log))
 }
 
@@ -1302,8 +1332,9 @@ pub fn execute_build_json(
     cfg: &BuildConfig,
     input: BuildInput,
     target_dir: &Path,
+    user_log: &UserLog,
 ) -> anyhow::Result<JsonBuildOutput> {
-    let execution = execute_build_capturing(cfg, input, target_dir)?;
+    let execution = execute_build_capturing(cfg, input, target_dir, user_log)?;
     // Keep the existing per-backend diagnostic-limit semantics while all
     // backends execute in one n2 graph. Shared actions have no backend and are
     // collected in their own group.
@@ -1364,7 +1395,7 @@ pub fn execute_standalone_build(
         return execute_build(cfg, input.script, target_dir, user_log);
     };
 
-    let dependency_execution = execute_build_capturing(cfg, dependencies, target_dir)?;
+    let dependency_execution = execute_build_capturing(cfg, dependencies, target_dir, user_log)?;
     if !dependency_execution.successful() {
         return Ok(finish_captured_build(
             cfg,
@@ -1374,7 +1405,7 @@ pub fn execute_standalone_build(
         ));
     }
 
-    let script_execution = match execute_build_capturing(cfg, input.script, target_dir) {
+    let script_execution = match execute_build_capturing(cfg, input.script, target_dir, user_log) {
         Ok(execution) => execution,
         Err(error) => {
             // Preserve dependency output if the second executor fails before it
@@ -1410,7 +1441,7 @@ pub fn execute_test_build(
     build_metas: &[&BuildMeta],
     user_log: &UserLog,
 ) -> anyhow::Result<N2RunStats> {
-    let execution = execute_build_capturing(cfg, input, target_dir)?;
+    let execution = execute_build_capturing(cfg, input, target_dir, user_log)?;
     let sources = execution.diagnostic_sources(build_metas.iter().copied());
     let processed = process_captured_diagnostics(&sources, cfg);
     processed.warn_if_limited(user_log);
@@ -1443,6 +1474,7 @@ pub fn execute_build_partial(
         graph,
         db_path,
         backend_by_build,
+        ..
     } = input.into_n2_execution()?;
     let execution = execute_n2_graph_capturing(
         cfg,
@@ -1457,16 +1489,58 @@ pub fn execute_build_partial(
 
 fn execute_build_capturing(
     cfg: &BuildConfig,
-    input: BuildInput,
+    mut input: BuildInput,
     target_dir: &Path,
+    user_log: &UserLog,
 ) -> anyhow::Result<CapturedBuildExecution> {
+    // A baseline is matched against the diagnostics of every action, but
+    // actions that are up to date or restored from a cache print nothing.
+    // Such builds therefore bypass the caches and start from an empty n2 DB
+    // of their own, which leaves the project's DB intact for later builds.
+    let rerun_all = cfg.baseline.is_some();
+    let cache = if cfg.use_build_cache && !rerun_all {
+        // The remote tier is optional, so a bad configuration must not fail
+        // builds that could do without it.
+        let remote = resolve_remote_build_cache().unwrap_or_else(|e| {
+            user_log.warn(format!("ignoring the remote build cache: {e:#}"));
+            None
+        });
+        ArtifactCache::open(
+            &resolve_cache_root(CacheKind::BuildArtifacts)?,
+            remote.as_ref(),
+        )
+        .context("Failed to open the build artifact cache")?
+    } else {
+        None
+    };
+    let execution_plan = Rc::clone(&input.execution_plan);
+    let lookup = match &cache {
+        Some(cache) => artifact_cache::restore_cached_actions(
+            cache,
+            &execution_plan,
+            std::mem::take(&mut input.action_ids),
+        )?,
+        None => CacheLookup::uncached(std::mem::take(&mut input.action_ids)),
+    };
+    input.action_ids = lookup.action_ids.clone();
+
     let N2ExecutionInput {
         graph,
         db_path,
         backend_by_build,
+        action_by_build,
     } = input.into_n2_execution()?;
+    let scratch_db = if rerun_all {
+        Some(tempfile::tempdir().context("Failed to create a scratch build cache DB")?)
+    } else {
+        None
+    };
+    let db_path = match &scratch_db {
+        Some(dir) => dir.path().join("baseline_db"),
+        None => db_path,
+    };
     let start_nodes = graph.get_start_nodes();
-    execute_n2_graph_capturing(
+    let mut execution = execute_n2_graph_capturing(
         cfg,
         graph,
         db_path,
@@ -1479,7 +1553,43 @@ fn execute_build_capturing(
             }
             Ok(())
         }),
-    )
+    )?;
+
+    if let Some(cache) = &cache
+        && execution.successful()
+    {
+        let actions_with_output = execution
+            .action_outputs
+            .iter()
+            .filter(|output| !output.content.content_writer.is_empty())
+            .filter_map(|output| action_by_build.get(&output.build).copied())
+            .collect();
+        artifact_cache::publish_executed_actions(
+            cache,
+            &execution_plan,
+            &lookup,
+            &actions_with_output,
+        );
+    }
+
+    if let Some(config) = &cfg.baseline {
+        let mut outputs: Vec<_> = execution
+            .action_outputs
+            .iter_mut()
+            .map(|output| &mut output.content)
+            .collect();
+        let new_warnings = baseline::apply(config, &mut outputs)?;
+        let deny_new_warnings = matches!(
+            config.mode,
+            BaselineMode::Suppress {
+                deny_new_warnings: true
+            }
+        );
+        if deny_new_warnings && new_warnings > 0 {
+            execution.n_tasks_executed = None;
+        }
+    }
+    Ok(execution)
 }
 
 fn execute_n2_graph_capturing(
@@ -1531,6 +1641,7 @@ fn execute_n2_graph_capturing(
             }
             captured_output_sender
                 .send(CapturedActionOutput {
+                    build: build_id,
                     target_backend,
                     content: captured,
                 })
@@ -1799,6 +1910,24 @@ fn process_captured_diagnostics(
         }
         return collected.processed;
     }
+    if let OutputStyle::Message(format) = cfg.output_style {
+        let collected = collect_json_diagnostics(sources, cfg, false);
+        for content in &collected.non_diagnostic_output {
+            eprintln!("{content}");
+        }
+        let mut diagnostics = Vec::<MooncDiagnostic>::new();
+        for content in &collected.diagnostics {
+            match serde_json::from_str(content) {
+                Ok(diagnostic) => diagnostics.push(diagnostic),
+                Err(_) => eprintln!("{content}"),
+            }
+        }
+        // Without a project, e.g. when building a single file, paths are
+        // kept absolute.
+        let base = cfg.source_dir.as_deref().unwrap_or(Path::new(""));
+        print!("{}", format.format(&diagnostics, base));
+        return collected.processed;
+    }
 
     let mut catcher = ResultCatcher::default();
     for source in sources {
@@ -1821,7 +1950,7 @@ fn process_captured_diagnostics(
     });
 
     match cfg.output_style {
-        OutputStyle::Json => unreachable!(),
+        OutputStyle::Json | OutputStyle::Message(_) => unreachable!(),
         OutputStyle::Fancy => {
             let mut by_file = BTreeMap::<String, BTreeSet<MooncDiagnostic>>::new();
             for (content, build_succeeded) in captured {
@@ -0,0 +1,196 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Shared remote tier of the build-artifact cache.
+//!
+//! The remote protocol is a plain content-addressed key-value store with the
+//! same records and objects as the local store:
+//!
+//! ```text
+//! GET/PUT <base>/ac/<action digest>    action record
+//! GET/PUT <base>/cas/<blake3 digest>   output object
+//! ```
+//!
+//! A `404` response is a miss. `file://` locations use the same layout in a
+//! directory. Remote contents are untrusted: callers verify every object
+//! against its digest before it reaches a build directory.
+//!
+//! The remote tier is an optimization. After the first transport failure it is
+//! disabled for the rest of the invocation, and the build continues locally.
+
+use std::{
+    cell::Cell,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use moonutil::cache::{RemoteCacheConfig, RemoteCacheLocation, RemoteCacheMode};
+use reqwest::StatusCode;
+use tracing::warn;
+
+use super::action_identity::ActionDigest;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+enum Store {
+    Http {
+        client: reqwest::blocking::Client,
+        base: String,
+    },
+    Directory(PathBuf),
+}
+
+pub(super) struct RemoteCache {
+    store: Store,
+    mode: RemoteCacheMode,
+    available: Cell<bool>,
+}
+
+impl RemoteCache {
+    pub(super) fn connect(config: &RemoteCacheConfig) -> anyhow::Result<Self> {
+        let store = match &config.location {
+            RemoteCacheLocation::Http(base) => Store::Http {
+                client: reqwest::blocking::Client::builder()
+                    .user_agent(format!("moon/{}", env!("CARGO_PKG_VERSION")))
+                    .connect_timeout(CONNECT_TIMEOUT)
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                    .context("failed to create the remote build cache HTTP client")?,
+                base: base.clone(),
+            },
+            RemoteCacheLocation::Directory(path) => Store::Directory(path.clone()),
+        };
+        Ok(Self {
+            store,
+            mode: config.mode,
+            available: Cell::new(true),
+        })
+    }
+
+    pub(super) fn is_writable(&self) -> bool {
+        self.mode == RemoteCacheMode::ReadWrite && self.available.get()
+    }
+
+    /// Download the record of one action. Failures are misses.
+    pub(super) fn get_record(&self, digest: ActionDigest) -> Option<Vec<u8>> {
+        self.get(&format!("ac/{}", digest.to_hex()))
+    }
+
+    /// Download one object. The caller must verify it. Failures are misses.
+    pub(super) fn get_object(&self, object: &str) -> Option<Vec<u8>> {
+        self.get(&format!("cas/{object}"))
+    }
+
+    pub(super) fn put_record(&self, digest: ActionDigest, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.put(&format!("ac/{}", digest.to_hex()), bytes)
+    }
+
+    pub(super) fn put_object(&self, object: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        self.put(&format!("cas/{object}"), bytes)
+    }
+
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        if !self.available.get() {
+            return None;
+        }
+        match self.try_get(key) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                warn!("disabling the remote build cache: {error:#}");
+                self.available.set(false);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        if !self.is_writable() {
+            return Ok(());
+        }
+        let result = self.try_put(key, bytes);
+        if result.is_err() {
+            self.available.set(false);
+        }
+        result
+    }
+
+    fn try_get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match &self.store {
+            Store::Http { client, base } => {
+                let url = format!("{base}/{key}");
+                let response = client
+                    .get(&url)
+                    .send()
+                    .with_context(|| format!("failed to download {url}"))?;
+                match response.status() {
+                    StatusCode::NOT_FOUND => Ok(None),
+                    status if status.is_success() => Ok(Some(
+                        response
+                            .bytes()
+                            .with_context(|| format!("failed to download {url}"))?
+                            .to_vec(),
+                    )),
+                    status => bail!("failed to download {url}: {status}"),
+                }
+            }
+            Store::Directory(root) => {
+                let path = root.join(key);
+                match std::fs::read(&path) {
+                    Ok(bytes) => Ok(Some(bytes)),
+                    Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+                    Err(error) => {
+                        Err(error).with_context(|| format!("failed to read {}", path.display()))
+                    }
+                }
+            }
+        }
+    }
+
+    fn try_put(&self, key: &str, bytes: Vec<u8>) -> anyhow::Result<()> {
+        match &self.store {
+            Store::Http { client, base } => {
+                let url = format!("{base}/{key}");
+                let status = client
+                    .put(&url)
+                    .body(bytes)
+                    .send()
+                    .with_context(|| format!("failed to upload {url}"))?
+                    .status();
+                if !status.is_success() {
+                    bail!("failed to upload {url}: {status}");
+                }
+                Ok(())
+            }
+            Store::Directory(root) => {
+                let path = root.join(key);
+                let parent = path.parent().expect("remote cache keys have a parent");
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+                let mut staged = tempfile::NamedTempFile::new_in(parent)?;
+                staged.write_all(&bytes)?;
+                staged
+                    .persist(&path)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                Ok(())
+            }
+        }
+    }
+}
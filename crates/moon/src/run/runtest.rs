@@ -57,13 +57,20 @@
     Check the discussion at [core#2684](https://github.com/moonbitlang/core/issues/2684).
 */
 
+mod bench_baseline;
 mod filter;
+mod last_run;
 mod promotion;
+mod report;
+mod retry;
+mod shard;
+mod shuffle;
 
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use anyhow::Context;
@@ -92,8 +99,11 @@ use tracing::{debug, info, instrument, trace, warn};
 use crate::{rr_build::BuildMeta, run::default_rt};
 
 use filter::apply_filter;
-pub(crate) use filter::{PackageFilter, TestFilter, TestIndex};
+pub(crate) use filter::{PackageFilter, TagFilter, TestFilter, TestIndex};
+pub(crate) use last_run::last_failed;
 pub(crate) use promotion::perform_promotion;
+pub(crate) use report::{TestReport, TestReporter};
+pub(crate) use shard::select_shard;
 
 #[derive(Debug, Clone)]
 pub(crate) struct TestOutlineEntry {
@@ -104,7 +114,8 @@ pub(crate) struct TestOutlineEntry {
     pub line_number: Option<usize>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
 enum TestResultKind {
     Passed,
     ExpectTestFailed,
@@ -121,12 +132,23 @@ struct TestCaseResult {
     // The metadata structure is read per-executable, so we better own it.
     // Known issue: line numbers can be stale if we are promoting tests
     meta: MbtTestInfo,
+    /// Time between the driver's start and result events for this test, if
+    /// both were observed.
+    duration: Option<Duration>,
+    /// The run that produced this result, counting from 1. Only retries of
+    /// failed tests make this larger than 1.
+    attempts: u32,
 }
 
 impl TestCaseResult {
     pub(crate) fn passed(&self) -> bool {
         matches!(self.kind, TestResultKind::Passed)
     }
+
+    /// Whether the test failed at first but passed on a retry.
+    pub(crate) fn flaky(&self) -> bool {
+        self.passed() && self.attempts > 1
+    }
 }
 
 enum ParsedTestDriverEvent {
@@ -206,9 +228,18 @@ pub(crate) fn run_tests(
     bench: bool,
     no_parallelize: bool,
     parallelism: Option<usize>,
+    timeout: Option<Duration>,
+    run_first: Option<&PackageFilter>,
+    shuffle_seed: Option<u64>,
     user_log: &moonutil::user_log::UserLog,
 ) -> anyhow::Result<ReplaceableTestResults> {
-    let invocations = collect_test_invocations(build_meta, filter, include_skipped, bench)?;
+    let mut invocations = collect_test_invocations(build_meta, filter, include_skipped, bench)?;
+    if let Some(seed) = shuffle_seed {
+        shuffle::shuffle(&mut invocations, seed);
+    }
+    if let Some(first) = run_first {
+        last_run::run_first(&mut invocations, first);
+    }
     debug!(count = invocations.len(), "collected test invocations");
 
     // Parallelism is opt-in: sequential by default, parallel only when -j is given
@@ -231,11 +262,22 @@ pub(crate) fn run_tests(
         parallelism,
         || default_rt().context("Failed to create runtime"),
         |rt, invocation| {
+            // Benchmarks are expected to run long, so only an explicit
+            // timeout applies to them.
+            let package_timeout = || {
+                let package = build_meta
+                    .resolve_output
+                    .pkg_dirs
+                    .get_package(invocation.target.package);
+                package.raw.test_timeout.map(Duration::from_secs)
+            };
+            let timeout = timeout.or_else(|| (!bench).then(package_timeout).flatten());
             let ctx = TestRunCtx {
                 build_meta,
                 rt,
                 source_dir,
                 target_dir,
+                timeout,
                 user_log,
             };
             debug!(
@@ -294,6 +336,8 @@ struct TestRunCtx<'a> {
     source_dir: &'a Path,
     /// Target directory; coverage output destination
     target_dir: &'a Path,
+    /// Kill the test executable if it runs longer than this.
+    timeout: Option<Duration>,
     /// User-facing verbose command output.
     user_log: &'a moonutil::user_log::UserLog,
 }
@@ -316,6 +360,8 @@ struct TargetTestResult {
 pub(crate) struct TestSummary {
     pub total: usize,
     pub passed: usize,
+    /// Tests among `passed` that only passed on a retry.
+    pub flaky: usize,
 }
 
 impl ReplaceableTestResults {
@@ -368,21 +414,85 @@ impl ReplaceableTestResults {
         }
     }
 
+    /// The `n` slowest timed test cases, slowest first.
+    fn slowest(&self, n: usize) -> Vec<(BuildTarget, &TestCaseResult, Duration)> {
+        let mut timed = self
+            .map
+            .iter()
+            .flat_map(|(target, result)| {
+                result
+                    .map
+                    .values()
+                    .flat_map(IndexMap::values)
+                    .filter_map(move |case| Some((*target, case, case.duration?)))
+            })
+            .collect::<Vec<_>>();
+        // Stable sort, so ties keep the order the results were printed in.
+        timed.sort_by_key(|(_, _, duration)| std::cmp::Reverse(*duration));
+        timed.truncate(n);
+        timed
+    }
+
+    /// Print the `n` slowest test cases for `--report-slowest`.
+    pub(crate) fn print_slowest(&self, meta: &BuildMeta, n: usize) {
+        let slowest = self.slowest(n);
+        if slowest.is_empty() {
+            return;
+        }
+        println!("Slowest {} test(s):", slowest.len());
+        for (target, case, duration) in slowest {
+            let module_name = meta
+                .resolve_output
+                .pkg_dirs
+                .get_package(target.package)
+                .fqn
+                .module()
+                .name()
+                .to_string();
+            let formatter = CompactTestFormatter::new(&module_name, &case.raw, Some(&case.meta));
+            print!("  ");
+            let _ = formatter.write_duration(&mut std::io::stdout(), duration);
+            println!();
+        }
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub(crate) fn summary(&self) -> TestSummary {
         let mut total = 0;
         let mut passed = 0;
+        let mut flaky = 0;
         for result in self.map.values() {
             for file_map in result.map.values() {
                 total += file_map.len();
                 passed += file_map.values().filter(|r| r.passed()).count();
+                flaky += file_map.values().filter(|r| r.flaky()).count();
             }
         }
-        TestSummary { total, passed }
+        TestSummary {
+            total,
+            passed,
+            flaky,
+        }
     }
 }
 
 impl TargetTestResult {
+    fn contains(&self, file: &str, index: u32) -> bool {
+        self.map
+            .get(file)
+            .is_some_and(|cases| cases.contains_key(&index))
+    }
+
+    fn case_count(&self) -> usize {
+        self.map.values().map(|cases| cases.len()).sum()
+    }
+
+    fn extend(&mut self, other: TargetTestResult) {
+        for (file, cases) in other.map {
+            self.map.entry(file).or_default().extend(cases);
+        }
+    }
+
     #[instrument(level = "trace", skip(self, result))]
     pub(crate) fn add(&mut self, file: &str, index: u32, result: TestCaseResult) {
         trace!(file = file, index, kind = ?result.kind, "adding test case result");
@@ -444,6 +554,7 @@ fn parse_test_driver_record(line: &str, package: &str) -> anyhow::Result<ParsedT
             index: index.to_string(),
             test_name: String::new(),
             message,
+            duration_ms: None,
         }),
     })
 }
@@ -521,34 +632,19 @@ fn active_tests_at_exit(test: &TestInvocation, test_output: Option<&str>) -> Vec
 fn format_test_executable_failure(
     test: &TestInvocation,
     exit_status: std::process::ExitStatus,
-    test_output: Option<&str>,
-    pkg_src: &impl PackageSrcResolver,
 ) -> String {
-    const MAX_ACTIVE_TESTS_TO_PRINT: usize = 8;
-
-    let mut message = format!(
+    format!(
         "Failed to run the test: {}\nThe test executable exited with {}",
         test.executable.display(),
         exit_status
-    );
-    let active = active_tests_at_exit(test, test_output);
-    if active.is_empty() {
-        return message;
-    }
+    )
+}
 
-    if active.len() == 1 {
-        message.push_str("\nActive test at executable exit:");
-    } else {
-        message.push_str("\nActive tests at executable exit:");
-    }
-    for (file, index) in active.iter().take(MAX_ACTIVE_TESTS_TO_PRINT) {
-        message.push_str("\n  - ");
-        message.push_str(&format_test_identity(test, file, *index, pkg_src));
-    }
-    if active.len() > MAX_ACTIVE_TESTS_TO_PRINT {
-        message.push_str("\n  - ... more");
-    }
-    message
+fn format_test_executable_timeout(test: &TestInvocation, limit: Duration) -> String {
+    format!(
+        "Failed to run the test: {}\nThe test executable timed out after {limit:?} and was killed",
+        test.executable.display(),
+    )
 }
 
 /// Build test invocation args for one target based on metadata and CLI filter.
@@ -583,6 +679,7 @@ pub(crate) fn build_test_args_for_target(
         include_skipped,
         bench,
         filter.name_filter.as_deref(),
+        &filter.tags,
     );
 
     Some(test_args)
@@ -618,6 +715,7 @@ pub(crate) fn collect_test_outline(
             include_skipped,
             bench,
             filter.name_filter.as_deref(),
+            &filter.tags,
         );
 
         let mut allowed_indices: IndexMap<String, std::collections::HashSet<u32>> = IndexMap::new();
@@ -723,6 +821,15 @@ fn gather_tests(build_meta: &BuildMeta) -> Vec<TestExecutableToRun<'_>> {
     results
 }
 
+fn read_test_metadata(path: &Path) -> anyhow::Result<MooncGenTestInfo> {
+    let meta_bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to read test metadata at {}", path.display()))?;
+    let meta = serde_json_lenient::from_slice(&meta_bytes)
+        .with_context(|| format!("Failed to parse test metadata at {}", path.display()))?;
+    trace!(path = %path.display(), "loaded test metadata");
+    Ok(meta)
+}
+
 pub(crate) fn collect_test_invocations(
     build_meta: &BuildMeta,
     filter: &TestFilter,
@@ -731,11 +838,7 @@ pub(crate) fn collect_test_invocations(
 ) -> anyhow::Result<Vec<TestInvocation>> {
     let mut invocations = Vec::new();
     for test in gather_tests(build_meta) {
-        let meta_bytes = std::fs::read(test.meta)
-            .with_context(|| format!("Failed to read test metadata at {}", test.meta.display()))?;
-        let meta: MooncGenTestInfo = serde_json_lenient::from_slice(&meta_bytes)
-            .with_context(|| format!("Failed to parse test metadata at {}", test.meta.display()))?;
-        trace!(path = %test.meta.display(), "loaded test metadata");
+        let meta = read_test_metadata(test.meta)?;
 
         let Some(args) = build_test_args_for_target(
             build_meta,
@@ -771,11 +874,88 @@ pub(crate) fn collect_test_invocations(
     Ok(invocations)
 }
 
+/// How a single launch of a test executable ended.
+enum TestLaunch {
+    /// The executable ran to completion.
+    Finished(TargetTestResult),
+    /// The executable crashed or was killed before reporting every test.
+    Aborted {
+        /// Results reported before the executable went down.
+        finished: TargetTestResult,
+        /// Tests that started but never reported a result.
+        active: Vec<(String, u32)>,
+        /// Why the executable went down, recorded on the active tests.
+        reason: String,
+        /// The error to report if no active test can be blamed.
+        error: String,
+    },
+}
+
+/// Run a test executable, isolating crashes: if the executable aborts, the
+/// tests that were running are recorded as failed and the executable is
+/// relaunched for the tests that have not reported a result yet.
 #[instrument(level = "debug", skip(ctx, test))]
 fn run_one_test_executable(
     ctx: &TestRunCtx<'_>,
     test: &TestInvocation,
 ) -> Result<TargetTestResult, anyhow::Error> {
+    let pkg_src = &ctx.build_meta.resolve_output.pkg_dirs;
+    let mut results = TargetTestResult::default();
+    let mut args = test.args.clone();
+    loop {
+        let (finished, active, reason, error) = match launch_test_executable(ctx, test, &args)? {
+            TestLaunch::Finished(finished) => {
+                results.extend(finished);
+                return Ok(results);
+            }
+            TestLaunch::Aborted {
+                finished,
+                active,
+                reason,
+                error,
+            } => (finished, active, reason, error),
+        };
+        // Without a test to blame, relaunching would not make progress.
+        if active.is_empty() {
+            anyhow::bail!("{error}");
+        }
+
+        let recorded = results.case_count();
+        results.extend(finished);
+        for (file, index) in active {
+            ctx.user_log.warn(format!(
+                "{reason} while running {}",
+                format_test_identity(test, &file, index, pkg_src)
+            ));
+            results.add(
+                &file,
+                index,
+                aborted_test_result(test, &file, index, &reason),
+            );
+        }
+        // A relaunch that recorded nothing new would select the same tests again.
+        if results.case_count() == recorded {
+            anyhow::bail!("{error}");
+        }
+
+        args = remaining_test_args(&test.args, &results);
+        if args
+            .file_and_index
+            .iter()
+            .all(|(_, ranges)| ranges.is_empty())
+        {
+            return Ok(results);
+        }
+        info!(package = %test.args.package, "relaunching test executable for the remaining tests");
+    }
+}
+
+/// Launch the test executable once for the tests selected by `args`.
+fn launch_test_executable(
+    ctx: &TestRunCtx<'_>,
+    test: &TestInvocation,
+    args: &TestArgs,
+) -> Result<TestLaunch, anyhow::Error> {
     let package = ctx
         .build_meta
         .resolve_output
@@ -792,7 +972,7 @@ fn run_one_test_executable(
     let mut cmd = crate::run::command_for(
         crate::run::ExecutionMode::from(&ctx.build_meta.backend),
         &executable,
-        Some(&test.args),
+        Some(args),
     );
     cmd.current_dir(module_root);
     let mut cov_cap = mk_coverage_capture();
@@ -803,46 +983,79 @@ fn run_one_test_executable(
             ctx.source_dir,
         ));
     }
-    info!(package = %test.args.package, executable = %test.executable.display(), "launching test executable");
-
-    let exit_status = ctx
-        .rt
-        .block_on(crate::run::run(
-            &mut [&mut cov_cap, &mut test_cap],
-            true,
-            cmd,
-        ))
-        .with_context(|| format!("Failed to run test for {fqn} {:?}", test.target.kind))?;
-    debug!(?exit_status, "test process finished");
-    let test_output = test_cap.finish();
-
-    if !exit_status.success() {
-        #[cfg(windows)]
-        if is_windows_app_control_block(&exit_status) {
-            anyhow::bail!(
-                "Windows App Control/Device Guard blocked generated executable. Disable Smart App Control, use audit mode, or sign/allow generated executables.\nExecutable: {}\nExit status: {}",
-                test.executable.display(),
-                exit_status
-            );
+    info!(package = %args.package, executable = %test.executable.display(), "launching test executable");
+
+    let mut captures = [&mut cov_cap, &mut test_cap];
+    let run = crate::run::run(&mut captures, true, cmd);
+    let exit_status = match ctx.timeout {
+        // Dropping the unfinished future kills the child process.
+        Some(limit) => ctx
+            .rt
+            .block_on(async { tokio::time::timeout(limit, run).await })
+            .ok(),
+        None => Some(ctx.rt.block_on(run)),
+    };
+    let (reason, error) = match exit_status {
+        None => {
+            let limit = ctx.timeout.unwrap_or_default();
+            warn!(?limit, "test executable timed out");
+            (
+                format!("the test executable timed out after {limit:?} and was killed"),
+                format_test_executable_timeout(test, limit),
+            )
         }
+        Some(exit_status) => {
+            let exit_status = exit_status
+                .with_context(|| format!("Failed to run test for {fqn} {:?}", test.target.kind))?;
+            debug!(?exit_status, "test process finished");
+            if exit_status.success() {
+                handle_finished_coverage(ctx.target_dir, cov_cap)?;
+                let (test_output, line_times) = test_cap.finish_timed().unzip();
+                return parse_test_results(
+                    test.meta.clone(),
+                    test_output,
+                    line_times.unwrap_or_default(),
+                    &args.package,
+                    &ctx.build_meta.resolve_output.pkg_dirs,
+                )
+                .map(TestLaunch::Finished)
+                .with_context(|| {
+                    format!(
+                        "Failed to parse test results for {fqn} {:?}",
+                        test.target.kind
+                    )
+                });
+            }
 
-        anyhow::bail!(
-            "{}",
-            format_test_executable_failure(
-                test,
-                exit_status,
-                test_output.as_deref(),
-                &ctx.build_meta.resolve_output.pkg_dirs,
-            )
-        );
-    }
+            #[cfg(windows)]
+            if is_windows_app_control_block(&exit_status) {
+                anyhow::bail!(
+                    "Windows App Control/Device Guard blocked generated executable. Disable Smart App Control, use audit mode, or sign/allow generated executables.\nExecutable: {}\nExit status: {}",
+                    test.executable.display(),
+                    exit_status
+                );
+            }
 
-    handle_finished_coverage(ctx.target_dir, cov_cap)?;
+            (
+                format!("the test executable exited with {exit_status}"),
+                format_test_executable_failure(test, exit_status),
+            )
+        }
+    };
 
-    parse_test_results(
+    // The process went down mid-run, so the last line may be cut short.
+    let (mut test_output, mut line_times) = test_cap.finish_timed_partial().unwrap_or_default();
+    if !test_output.ends_with('\n') {
+        let complete = test_output.rfind('\n').map_or(0, |i| i + 1);
+        test_output.truncate(complete);
+        line_times.truncate(test_output.lines().count());
+    }
+    let active = active_tests_at_exit(test, Some(&test_output));
+    let finished = parse_test_results(
         test.meta.clone(),
-        test_output,
-        &test.args.package,
+        Some(test_output),
+        line_times,
+        &args.package,
         &ctx.build_meta.resolve_output.pkg_dirs,
     )
     .with_context(|| {
@@ -850,9 +1063,85 @@ fn run_one_test_executable(
             "Failed to parse test results for {fqn} {:?}",
             test.target.kind
         )
+    })?;
+    Ok(TestLaunch::Aborted {
+        finished,
+        active,
+        reason,
+        error,
     })
 }
 
+/// The result recorded for a test that was running when its executable
+/// crashed or was killed. Tests missing from the metadata get a placeholder
+/// one, so that they are still recorded and not relaunched.
+fn aborted_test_result(
+    test: &TestInvocation,
+    file: &str,
+    index: u32,
+    reason: &str,
+) -> TestCaseResult {
+    let meta = match find_test_info(&test.meta, file, index) {
+        Some(meta) => meta.clone(),
+        None => {
+            warn!(file, index, "no test metadata for aborted test");
+            MbtTestInfo {
+                index,
+                func: String::new(),
+                name: None,
+                line_number: None,
+                attrs: vec![],
+            }
+        }
+    };
+    TestCaseResult {
+        kind: TestResultKind::RuntimeError,
+        raw: Arc::new(TestStatistics {
+            package: test.args.package.clone(),
+            filename: file.to_string(),
+            index: index.to_string(),
+            test_name: meta.name.clone().unwrap_or_default(),
+            message: reason.to_string(),
+            duration_ms: None,
+        }),
+        meta,
+        duration: None,
+        attempts: 1,
+    }
+}
+
+/// The tests selected by `args` that have no result in `done` yet.
+fn remaining_test_args(args: &TestArgs, done: &TargetTestResult) -> TestArgs {
+    let file_and_index = args
+        .file_and_index
+        .iter()
+        .map(|(file, ranges)| {
+            let remaining = ranges
+                .iter()
+                .cloned()
+                .flatten()
+                .filter(|&index| !done.contains(file, index));
+            (file.clone(), to_ranges(remaining))
+        })
+        .collect();
+    TestArgs {
+        package: args.package.clone(),
+        file_and_index,
+    }
+}
+
+/// Collapse ascending test indices into ranges of consecutive indices.
+fn to_ranges(indices: impl IntoIterator<Item = u32>) -> Vec<std::ops::Range<u32>> {
+    let mut ranges: Vec<std::ops::Range<u32>> = vec![];
+    for index in indices {
+        match ranges.last_mut() {
+            Some(last) if last.end == index => last.end = index + 1,
+            _ => ranges.push(index..index + 1),
+        }
+    }
+    ranges
+}
+
 fn mk_coverage_capture() -> SectionCapture<'static> {
     SectionCapture::new(
         MOON_COVERAGE_DELIMITER_BEGIN,
@@ -867,7 +1156,7 @@ fn is_windows_app_control_block(exit_status: &std::process::ExitStatus) -> bool
 }
 
 fn make_test_capture() -> SectionCapture<'static> {
-    SectionCapture::new(MOON_TEST_DELIMITER_BEGIN, MOON_TEST_DELIMITER_END, false)
+    SectionCapture::new(MOON_TEST_DELIMITER_BEGIN, MOON_TEST_DELIMITER_END, false).timed()
 }
 
 #[instrument(level = "trace", skip(cap))]
@@ -890,10 +1179,13 @@ fn handle_finished_coverage(target_dir: &Path, cap: SectionCapture) -> anyhow::R
     Ok(())
 }
 
-#[instrument(level = "debug", skip(meta, cap, pkg_src))]
+/// Parse the captured test driver output. `line_times` holds the arrival time
+/// of each captured line and is used to time individual tests.
+#[instrument(level = "debug", skip(meta, cap, line_times, pkg_src))]
 fn parse_test_results(
     meta: MooncGenTestInfo,
     cap: Option<String>,
+    line_times: Vec<Instant>,
     package: &str,
     pkg_src: &impl PackageSrcResolver,
 ) -> anyhow::Result<TargetTestResult> {
@@ -924,15 +1216,22 @@ fn parse_test_results(
 
     // Actual handling of each test case result
     let mut res = TargetTestResult::default();
-    for line in s.lines() {
+    let mut started = HashMap::<(String, u32), Instant>::new();
+    for (line_index, line) in s.lines().enumerate() {
         if line.is_empty() {
             continue;
         }
+        let arrived = line_times.get(line_index).copied();
 
-        let ParsedTestDriverEvent::Result(mut stat) = parse_test_driver_record(line, package)?
-        else {
-            trace!("parsed test start event");
-            continue;
+        let mut stat = match parse_test_driver_record(line, package)? {
+            ParsedTestDriverEvent::Start { file, index } => {
+                trace!("parsed test start event");
+                if let Some(arrived) = arrived {
+                    started.insert((file, index), arrived);
+                }
+                continue;
+            }
+            ParsedTestDriverEvent::Result(stat) => stat,
         };
 
         // Repopulate name.
@@ -962,11 +1261,18 @@ fn parse_test_results(
         let test_name = stat.test_name.clone();
         let result_kind = parse_one_test_result(&stat, &test_name, pkg_src)?;
         trace!(file = %stat.filename, index, kind = ?result_kind, "parsed test case");
+        let duration = started
+            .remove(&(stat.filename.clone(), index))
+            .zip(arrived)
+            .map(|(start, end)| end.saturating_duration_since(start));
+        stat.duration_ms = duration.map(|d| d.as_secs_f64() * 1000.0);
         let stat = Arc::new(stat);
         let case_result = TestCaseResult {
             kind: result_kind,
             raw: Arc::clone(&stat),
             meta,
+            duration,
+            attempts: 1,
         };
         res.add(&stat.filename, index, case_result);
     }
@@ -1056,6 +1362,7 @@ fn print_test_result_json(res: &TestCaseResult) {
                 index: res.raw.index.clone(),
                 test_name,
                 message,
+                duration_ms: res.raw.duration_ms,
             };
 
             // Print compact JSON line
@@ -1080,6 +1387,9 @@ fn print_test_result_normal(
                 let _ = formatter.write_bench(&mut std::io::stdout());
                 println!();
                 render_batch_bench_summary(message);
+            } else if res.flaky() {
+                let _ = formatter.write_flaky(&mut std::io::stdout(), res.attempts);
+                println!();
             } else if verbose {
                 let _ = formatter.write_success(&mut std::io::stdout());
                 println!();
@@ -1171,4 +1481,90 @@ mod tests {
         assert_started_before_release(1, 1);
         assert_started_before_release(2, 2);
     }
+
+    #[test]
+    fn test_remaining_args_skip_finished_and_aborted_tests() {
+        let tests = (0..5)
+            .map(|index| format!(r#"{{"index": {index}, "func": "t{index}", "name": "t{index}"}}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        let meta = serde_json_lenient::from_str(&format!(
+            r#"{{"no_args_tests": {{"a.mbt": [{tests}]}}, "with_args_tests": {{}}}}"#
+        ))
+        .unwrap();
+        let test = super::TestInvocation {
+            target: moonbuild_rupes_recta::model::BuildTarget {
+                package: Default::default(),
+                kind: moonbuild_rupes_recta::model::TargetKind::Source,
+            },
+            executable: "a.wasm".into(),
+            args: moonbuild::entry::TestArgs {
+                package: "user/pkg".to_string(),
+                file_and_index: vec![("a.mbt".to_string(), vec![0..3, 3..5])],
+            },
+            meta,
+        };
+
+        let mut done = super::TargetTestResult::default();
+        for index in [0, 2] {
+            let result = super::aborted_test_result(&test, "a.mbt", index, "crashed");
+            assert!(matches!(result.kind, super::TestResultKind::RuntimeError));
+            assert_eq!(result.raw.message, "crashed");
+            done.add("a.mbt", index, result);
+        }
+
+        let remaining = super::remaining_test_args(&test.args, &done);
+        assert_eq!(
+            remaining.file_and_index,
+            vec![("a.mbt".to_string(), vec![1..2, 3..5])]
+        );
+
+        // Tests missing from the metadata are still recorded.
+        let unknown = super::aborted_test_result(&test, "a.mbt", 7, "crashed");
+        assert!(matches!(unknown.kind, super::TestResultKind::RuntimeError));
+        assert_eq!(unknown.meta.index, 7);
+    }
+
+    #[test]
+    fn test_slowest_skips_untimed_cases_and_sorts_descending() {
+        let meta = serde_json_lenient::from_str(
+            r#"{"no_args_tests": {"a.mbt": [
+                {"index": 0, "func": "t0", "name": "t0"},
+                {"index": 1, "func": "t1", "name": "t1"},
+                {"index": 2, "func": "t2", "name": "t2"},
+                {"index": 3, "func": "t3", "name": "t3"}
+            ]}, "with_args_tests": {}}"#,
+        )
+        .unwrap();
+        let target = moonbuild_rupes_recta::model::BuildTarget {
+            package: Default::default(),
+            kind: moonbuild_rupes_recta::model::TargetKind::Source,
+        };
+        let test = super::TestInvocation {
+            target,
+            executable: "a.wasm".into(),
+            args: moonbuild::entry::TestArgs {
+                package: "user/pkg".to_string(),
+                file_and_index: vec![],
+            },
+            meta,
+        };
+
+        let mut result = super::TargetTestResult::default();
+        for (index, ms) in [(0, Some(5)), (1, None), (2, Some(30)), (3, Some(10))] {
+            let mut case = super::aborted_test_result(&test, "a.mbt", index, "");
+            case.duration = ms.map(Duration::from_millis);
+            result.add("a.mbt", index, case);
+        }
+        let mut results = super::ReplaceableTestResults::default();
+        results.merge_with_target(target, result);
+
+        let slowest = results
+            .slowest(2)
+            .into_iter()
+            .map(|(_, case, duration)| (case.meta.index, duration.as_millis()))
+            .collect::<Vec<_>>();
+        assert_eq!(slowest, [(2, 30), (3, 10)]);
+        assert_eq!(results.slowest(10).len(), 3);
+    }
 }
@@ -0,0 +1,327 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Named sets of benchmark results, for `moon bench --save-baseline` and
+//! `--baseline`.
+//!
+//! Benchmarks are keyed by package name, file and test name rather than
+//! index, so that adding a benchmark does not shift the baseline of the ones
+//! after it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use colored::Colorize;
+use indexmap::IndexMap;
+use moonbuild::benchmark::{
+    BenchChange, BenchSummary, auto_select_unit, parse_batch_bench_summaries,
+};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::rr_build::BuildMeta;
+
+use super::ReplaceableTestResults;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BenchBaseline {
+    benches: Vec<StoredBench>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBench {
+    package: String,
+    file: String,
+    test: String,
+    summary: BenchSummary,
+}
+
+type BenchKey = (String, String, String, Option<String>);
+
+impl StoredBench {
+    fn key(&self) -> BenchKey {
+        (
+            self.package.clone(),
+            self.file.clone(),
+            self.test.clone(),
+            self.summary.name.clone(),
+        )
+    }
+
+    fn label(&self) -> String {
+        let mut label = format!("{}/{}::{}", self.package, self.file, self.test);
+        if let Some(name) = &self.summary.name {
+            label.push_str("::");
+            label.push_str(name);
+        }
+        label
+    }
+}
+
+fn baseline_path(meta: &BuildMeta, name: &str) -> PathBuf {
+    meta.artifact_paths
+        .target_layout()
+        .bench_baseline_of_build_target(meta.target_backend(), name)
+}
+
+fn load(path: &Path) -> anyhow::Result<BenchBaseline> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read benchmark baseline {}", path.display()))?;
+    serde_json_lenient::from_str(&content)
+        .with_context(|| format!("Failed to parse benchmark baseline {}", path.display()))
+}
+
+/// How a benchmark compares with its baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+    Regressed,
+    Improved,
+    Unchanged,
+}
+
+impl Verdict {
+    fn of(change: BenchChange, threshold_pct: f64) -> Self {
+        if !change.significant {
+            Verdict::Unchanged
+        } else if change.mean_change_pct > threshold_pct {
+            Verdict::Regressed
+        } else if change.mean_change_pct < -threshold_pct {
+            Verdict::Improved
+        } else {
+            Verdict::Unchanged
+        }
+    }
+}
+
+struct Comparison<'a> {
+    current: &'a StoredBench,
+    /// The baseline of this benchmark and how it changed, if it has one.
+    baseline: Option<(&'a BenchSummary, BenchChange, Verdict)>,
+}
+
+fn compare<'a>(
+    baseline: &'a IndexMap<BenchKey, StoredBench>,
+    current: &'a [StoredBench],
+    threshold_pct: f64,
+) -> Vec<Comparison<'a>> {
+    current
+        .iter()
+        .map(|bench| Comparison {
+            current: bench,
+            baseline: baseline.get(&bench.key()).map(|base| {
+                let change = bench.summary.change_from(&base.summary);
+                (&base.summary, change, Verdict::of(change, threshold_pct))
+            }),
+        })
+        .collect()
+}
+
+impl ReplaceableTestResults {
+    /// The summaries of every benchmark that passed in this run.
+    fn bench_results(&self, meta: &BuildMeta) -> Vec<StoredBench> {
+        let pkg_dirs = &meta.resolve_output.pkg_dirs;
+        let mut benches = vec![];
+        for (target, target_result) in &self.map {
+            let package = pkg_dirs.fqn(target.package).to_string();
+            for (file, cases) in &target_result.map {
+                for case in cases.values().filter(|case| case.passed()) {
+                    let Some(batch) = parse_batch_bench_summaries(&case.raw.message) else {
+                        continue;
+                    };
+                    let test = case
+                        .meta
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| case.meta.func.clone());
+                    benches.extend(batch.summaries.into_iter().map(|summary| StoredBench {
+                        package: package.clone(),
+                        file: file.clone(),
+                        test: test.clone(),
+                        summary,
+                    }));
+                }
+            }
+        }
+        benches
+    }
+
+    /// Save the benchmarks of this run as the baseline `name`. Benchmarks
+    /// that were not run this time keep their saved results.
+    pub(crate) fn save_bench_baseline(&self, meta: &BuildMeta, name: &str) -> anyhow::Result<()> {
+        let path = baseline_path(meta, name);
+        let mut benches = if path.exists() {
+            load(&path)?.benches
+        } else {
+            vec![]
+        }
+        .into_iter()
+        .map(|bench| (bench.key(), bench))
+        .collect::<IndexMap<_, _>>();
+        for bench in self.bench_results(meta) {
+            benches.insert(bench.key(), bench);
+        }
+
+        let baseline = BenchBaseline {
+            benches: benches.into_values().collect(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&baseline)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write benchmark baseline {}", path.display()))?;
+        debug!(path = %path.display(), count = baseline.benches.len(), "saved benchmark baseline");
+        Ok(())
+    }
+
+    /// Print how the benchmarks of this run compare with the baseline `name`,
+    /// and return the number of them that regressed by more than
+    /// `threshold_pct` percent.
+    pub(crate) fn compare_bench_baseline(
+        &self,
+        meta: &BuildMeta,
+        name: &str,
+        threshold_pct: f64,
+    ) -> anyhow::Result<usize> {
+        let path = baseline_path(meta, name);
+        if !path.exists() {
+            anyhow::bail!(
+                "no benchmark baseline named `{name}` for backend {}, save one with `moon bench --save-baseline {name}`",
+                meta.target_backend()
+            );
+        }
+        let baseline = load(&path)?
+            .benches
+            .into_iter()
+            .map(|bench| (bench.key(), bench))
+            .collect::<IndexMap<_, _>>();
+        let current = self.bench_results(meta);
+        let comparisons = compare(&baseline, &current, threshold_pct);
+
+        println!("Comparing with benchmark baseline `{name}`:");
+        let label_width = comparisons
+            .iter()
+            .map(|c| c.current.label().len())
+            .max()
+            .unwrap_or(0);
+        let mut regressions = 0;
+        for Comparison { current, baseline } in &comparisons {
+            let label = format!("{:<label_width$}", current.label());
+            let Some((base, change, verdict)) = baseline else {
+                println!(
+                    "  {label}  {}  {}",
+                    auto_select_unit(current.summary.mean),
+                    "no baseline".bright_black()
+                );
+                continue;
+            };
+            let verdict = match verdict {
+                Verdict::Regressed => {
+                    regressions += 1;
+                    "regressed".red().bold()
+                }
+                Verdict::Improved => "improved".green().bold(),
+                Verdict::Unchanged if change.significant => "within threshold".normal(),
+                Verdict::Unchanged => "no significant change".bright_black(),
+            };
+            println!(
+                "  {label}  {} -> {}  {:>+7.2}%  {verdict}",
+                auto_select_unit(base.mean),
+                auto_select_unit(current.summary.mean),
+                change.mean_change_pct,
+            );
+        }
+        Ok(regressions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bench(test: &str, mean: f64) -> StoredBench {
+        StoredBench {
+            package: "user/pkg".to_string(),
+            file: "a_test.mbt".to_string(),
+            test: test.to_string(),
+            summary: BenchSummary {
+                name: None,
+                min: mean - 1.0,
+                max: mean + 1.0,
+                mean,
+                median: mean,
+                variance: 1.0,
+                std_dev: 1.0,
+                std_dev_pct: 100.0 / mean,
+                median_abs_dev: 0.5,
+                median_abs_dev_pct: 50.0 / mean,
+                quartiles: (mean - 0.5, mean, mean + 0.5),
+                iqr: 1.0,
+                batch_size: 1000,
+                runs: 10,
+            },
+        }
+    }
+
+    #[test]
+    fn benches_are_compared_by_name() {
+        let baseline = [
+            bench("fib", 100.0),
+            bench("sort", 100.0),
+            bench("hash", 100.0),
+        ]
+        .into_iter()
+        .map(|bench| (bench.key(), bench))
+        .collect::<IndexMap<_, _>>();
+        let current = [
+            bench("hash", 103.0),
+            bench("sort", 80.0),
+            bench("fib", 120.0),
+            bench("parse", 50.0),
+        ];
+
+        let verdicts = compare(&baseline, &current, 5.0)
+            .into_iter()
+            .map(|c| (c.current.test.as_str(), c.baseline.map(|(_, _, v)| v)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            verdicts,
+            [
+                ("hash", Some(Verdict::Unchanged)),
+                ("sort", Some(Verdict::Improved)),
+                ("fib", Some(Verdict::Regressed)),
+                ("parse", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn baseline_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("main.json");
+        let saved = BenchBaseline {
+            benches: vec![bench("fib", 100.0)],
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&saved).unwrap()).unwrap();
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.benches.len(), 1);
+        assert_eq!(loaded.benches[0].key(), saved.benches[0].key());
+        assert_eq!(loaded.benches[0].summary, saved.benches[0].summary);
+    }
+}
@@ -16,7 +16,10 @@
 //
 // For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
 
-use std::{collections::BTreeSet, ops::Range};
+use std::{
+    collections::{BTreeSet, HashSet},
+    ops::Range,
+};
 
 use indexmap::IndexMap;
 use moonbuild::test_utils::indices_to_ranges;
@@ -116,6 +119,25 @@ pub(crate) struct TestFilter {
     pub filter: Option<PackageFilter>,
     /// Glob pattern to filter tests by name
     pub name_filter: Option<String>,
+    /// Tags to select or exclude tests by
+    pub tags: TagFilter,
+}
+
+/// Tag-based selection from `--tag` and `--exclude-tag`. Tags come from
+/// `#tag(...)` attributes on test blocks.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct TagFilter {
+    /// When non-empty, only tests with at least one of these tags are allowed.
+    pub include: Vec<String>,
+    /// Tests with any of these tags are never allowed.
+    pub exclude: Vec<String>,
+}
+
+impl TagFilter {
+    fn matches(&self, test: &MbtTestInfo) -> bool {
+        let has_any = |tags: &[String]| test.tags().any(|tag| tags.iter().any(|t| t == tag));
+        (self.include.is_empty() || has_any(&self.include)) && !has_any(&self.exclude)
+    }
 }
 
 impl TestFilter {
@@ -205,6 +227,34 @@ impl TestFilter {
             },
         }
     }
+
+    /// Restrict the filter to the test targets of `packages`.
+    pub(crate) fn retain_packages(&mut self, packages: &HashSet<PackageId>) {
+        let pf = self.filter.get_or_insert_with(|| {
+            let mut pf = PackageFilter::default();
+            for &pkg in packages {
+                for &target in TargetKind::all_tests() {
+                    pf.add_one(pkg.build_target(target), None, None);
+                }
+            }
+            pf
+        });
+        pf.0.retain(|target, _| packages.contains(&target.package));
+    }
+
+    /// Whether the single test case `file` + `index` of `package` is selected.
+    /// Does not consider `name_filter`.
+    pub(crate) fn allows(&self, package: BuildTarget, file: &str, index: u32) -> bool {
+        match self.check_package(package) {
+            (false, _) => false,
+            (true, None) => true,
+            (true, Some(ff)) => match ff.0.get(file) {
+                None => false,
+                Some(None) => true,
+                Some(Some(indices)) => indices.contains(index),
+            },
+        }
+    }
 }
 
 /// Package-level helpers for constructing filters.
@@ -275,6 +325,7 @@ fn all_ranges(
     infos: &[MbtTestInfo],
     include_skipped: bool,
     name_matcher: Option<&GlobPatternMatcher<'_>>,
+    tags: &TagFilter,
 ) -> Vec<Range<u32>> {
     // Use actual indices from test metadata instead of assuming contiguous 0..max_index
     let actual_indices: Vec<u32> = infos
@@ -285,6 +336,7 @@ fn all_ranges(
             (Some(_), None) => false,
             (None, _) => true,
         })
+        .filter(|t| tags.matches(t))
         .map(|t| t.index)
         .collect();
     indices_to_ranges(actual_indices)
@@ -297,6 +349,7 @@ pub(crate) fn apply_filter(
     include_skipped: bool,
     bench: bool,
     name_filter: Option<&str>,
+    tags: &TagFilter,
 ) {
     let name_matcher = name_filter.map(GlobPatternMatcher::new);
     let lists = if bench {
@@ -316,7 +369,7 @@ pub(crate) fn apply_filter(
             for test_list in lists {
                 for (filename, test_infos) in test_list {
                     let this_file_index =
-                        all_ranges(test_infos, include_skipped, name_matcher.as_ref());
+                        all_ranges(test_infos, include_skipped, name_matcher.as_ref(), tags);
                     files_and_index.push((filename.clone(), this_file_index));
                 }
             }
@@ -336,6 +389,7 @@ pub(crate) fn apply_filter(
                                     tests,
                                     include_skipped,
                                     name_matcher.as_ref(),
+                                    tags,
                                 ));
                             }
                             Some(ixf) => {
@@ -347,7 +401,7 @@ pub(crate) fn apply_filter(
                                             (Some(_), None) => false,
                                             (None, _) => true,
                                         };
-                                        if name_matches {
+                                        if name_matches && tags.matches(t) {
                                             // for single test, the `#skip` attribute is ignored
                                             this_file_index.push(t.index..t.index + 1);
                                         }
@@ -471,7 +525,15 @@ mod test {
     fn test_no_file_filter() {
         let meta = example_meta();
         let mut out = vec![];
-        super::apply_filter(None, &meta, &mut out, false, false, None);
+        super::apply_filter(
+            None,
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect![[r#"[("file1.mbt", [0..2, 4..5]), ("file2.mbt", [2..3]), ("doc_tests.mbt", [0..2]), ("file1.mbt", [2..3]), ("my_file.mbt", []), ("param_file.mbt", [0..1])]"#]]
         .assert_eq(&format!("{:?}", out));
@@ -487,7 +549,15 @@ mod test {
         expect![[r#"FileFilter({"file1.mbt": None})"#]].assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect![[r#"[("file1.mbt", [0..2, 4..5, 2..3])]"#]].assert_eq(&format!("{:?}", out));
     }
@@ -504,7 +574,15 @@ mod test {
             .assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect![[r#"[("file1.mbt", [1..2, 4..5])]"#]].assert_eq(&format!("{:?}", out));
     }
@@ -519,7 +597,15 @@ mod test {
             .assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
         expect![[r#"[("file1.mbt", [0..1, 1..2])]"#]].assert_eq(&format!("{:?}", out));
     }
 
@@ -540,7 +626,15 @@ mod test {
         .assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect![[
             r#"[("file1.mbt", [0..1]), ("doc_tests.mbt", [0..2]), ("param_file.mbt", [0..1])]"#
@@ -556,7 +650,15 @@ mod test {
         expect!["FileFilter({})"].assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect!["[]"].assert_eq(&format!("{:?}", out));
     }
@@ -571,7 +673,15 @@ mod test {
         expect![[r#"FileFilter({"my_file.mbt": None})"#]].assert_eq(&format!("{:?}", ff));
 
         let mut out = vec![];
-        super::apply_filter(Some(&ff), &meta, &mut out, false, false, None);
+        super::apply_filter(
+            Some(&ff),
+            &meta,
+            &mut out,
+            false,
+            false,
+            None,
+            &Default::default(),
+        );
 
         expect![[r#"[("my_file.mbt", [])]"#]].assert_eq(&format!("{:?}", out));
     }
@@ -608,11 +718,35 @@ mod test {
         let meta = example_meta();
         let mut out = vec![];
         // Filter by name pattern - should match tests with 'o' in name
-        super::apply_filter(None, &meta, &mut out, false, false, Some("*o*"));
+        super::apply_filter(
+            None,
+            &meta,
+            &mut out,
+            false,
+            false,
+            Some("*o*"),
+            &Default::default(),
+        );
 
         // Tests with names containing 'o': zero (0), one (1), four (4), two (2), doctest a (0), doctest b (1)
         // Note: file1 param (index 2) has name "file1 param" which doesn't match "*o*"
         expect![[r#"[("file1.mbt", [0..2, 4..5]), ("file2.mbt", [2..3]), ("doc_tests.mbt", [0..2]), ("file1.mbt", []), ("my_file.mbt", []), ("param_file.mbt", [])]"#]]
         .assert_eq(&format!("{:?}", out));
     }
+    #[test]
+    fn test_tag_filter_includes_and_excludes() {
+        let mut meta = example_meta();
+        let file1 = meta.no_args_tests.get_mut("file1.mbt").unwrap();
+        file1[0].attrs = vec!["#tag(\"slow\")".into()];
+        file1[2].attrs = vec!["#tag(\"slow\", \"network\")".into()];
+        let tags = super::TagFilter {
+            include: vec!["slow".into()],
+            exclude: vec!["network".into()],
+        };
+
+        let mut out = vec![];
+        super::apply_filter(None, &meta, &mut out, false, false, None, &tags);
+        expect![[r#"[("file1.mbt", [0..1]), ("file2.mbt", []), ("doc_tests.mbt", []), ("file1.mbt", []), ("my_file.mbt", []), ("param_file.mbt", [])]"#]]
+        .assert_eq(&format!("{:?}", out));
+    }
 }
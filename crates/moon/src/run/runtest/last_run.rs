@@ -0,0 +1,425 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Persists the outcome of every test between runs, for `--rerun-failed` and
+//! `--failed-first`.
+//!
+//! Outcomes are keyed by package name rather than `BuildTarget`, since
+//! package IDs are not stable across invocations.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use indexmap::IndexMap;
+use moonbuild_rupes_recta::model::TargetKind;
+use moonutil::build_options::TestIndexRange;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::rr_build::BuildMeta;
+use crate::run::{PackageFilter, TestFilter};
+
+use super::{ReplaceableTestResults, TestInvocation, gather_tests, read_test_metadata, to_ranges};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LastTestResults {
+    tests: Vec<StoredOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredOutcome {
+    package: String,
+    target: StoredTargetKind,
+    file: String,
+    index: u32,
+    passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum StoredTargetKind {
+    Whitebox,
+    Blackbox,
+    Inline,
+}
+
+impl StoredTargetKind {
+    fn from_kind(kind: TargetKind) -> Option<Self> {
+        match kind {
+            TargetKind::WhiteboxTest => Some(Self::Whitebox),
+            TargetKind::BlackboxTest => Some(Self::Blackbox),
+            TargetKind::InlineTest => Some(Self::Inline),
+            TargetKind::Source | TargetKind::SubPackage => None,
+        }
+    }
+
+    fn kind(self) -> TargetKind {
+        match self {
+            Self::Whitebox => TargetKind::WhiteboxTest,
+            Self::Blackbox => TargetKind::BlackboxTest,
+            Self::Inline => TargetKind::InlineTest,
+        }
+    }
+}
+
+type OutcomeKey = (String, StoredTargetKind, String, u32);
+
+impl StoredOutcome {
+    fn key(&self) -> OutcomeKey {
+        (
+            self.package.clone(),
+            self.target,
+            self.file.clone(),
+            self.index,
+        )
+    }
+}
+
+fn results_path(meta: &BuildMeta) -> PathBuf {
+    meta.artifact_paths
+        .target_layout()
+        .last_test_results_of_build_target(meta.target_backend())
+}
+
+/// A missing or unreadable store is treated as empty: it only guides which
+/// tests to run, so a stale file should never fail the run.
+fn load(path: &Path) -> LastTestResults {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return LastTestResults::default();
+    };
+    serde_json_lenient::from_str(&content).unwrap_or_else(|err| {
+        warn!(?err, path = %path.display(), "ignoring unreadable last test results");
+        LastTestResults::default()
+    })
+}
+
+/// The tests of test executables, by package and target kind, as
+/// `(file, index)`.
+type ExistingTests = HashMap<(String, StoredTargetKind), HashSet<(String, u32)>>;
+
+/// The tests of every test executable in this build.
+fn existing_tests(meta: &BuildMeta) -> anyhow::Result<ExistingTests> {
+    let pkg_dirs = &meta.resolve_output.pkg_dirs;
+    let mut existing = HashMap::new();
+    for test in gather_tests(meta) {
+        let Some(kind) = StoredTargetKind::from_kind(test.target.kind) else {
+            continue;
+        };
+        let info = read_test_metadata(test.meta)?;
+        let tests = [
+            &info.no_args_tests,
+            &info.with_args_tests,
+            &info.with_bench_args_tests,
+            &info.async_tests,
+            &info.async_tests_with_args,
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|(file, tests)| tests.iter().map(|test| (file.clone(), test.index)))
+        .collect();
+        let package = pkg_dirs.fqn(test.target.package).to_string();
+        existing.insert((package, kind), tests);
+    }
+    Ok(existing)
+}
+
+/// Whether a stored outcome still refers to a test of the project. Outcomes
+/// of executables missing from `existing` are kept, as nothing is known about
+/// their tests.
+fn still_exists(
+    outcome: &StoredOutcome,
+    has_package: impl Fn(&str) -> bool,
+    existing: &ExistingTests,
+) -> bool {
+    has_package(&outcome.package)
+        && existing
+            .get(&(outcome.package.clone(), outcome.target))
+            .is_none_or(|tests| tests.contains(&(outcome.file.clone(), outcome.index)))
+}
+
+impl ReplaceableTestResults {
+    /// Record the outcome of every test in this run. Outcomes of tests that
+    /// were not run this time are kept, unless their package is gone or the
+    /// metadata of this build no longer has them.
+    pub(crate) fn save_outcomes(&self, meta: &BuildMeta) -> anyhow::Result<()> {
+        let path = results_path(meta);
+        let pkg_dirs = &meta.resolve_output.pkg_dirs;
+        let existing = existing_tests(meta)?;
+        let mut outcomes = load(&path)
+            .tests
+            .into_iter()
+            .filter(|outcome| {
+                still_exists(
+                    outcome,
+                    |package| pkg_dirs.get_package_id_by_name(package).is_some(),
+                    &existing,
+                )
+            })
+            .map(|outcome| (outcome.key(), outcome))
+            .collect::<IndexMap<_, _>>();
+
+        for (target, target_result) in &self.map {
+            let Some(kind) = StoredTargetKind::from_kind(target.kind) else {
+                continue;
+            };
+            let package = pkg_dirs.fqn(target.package).to_string();
+            for (file, cases) in &target_result.map {
+                for (&index, case) in cases {
+                    let outcome = StoredOutcome {
+                        package: package.clone(),
+                        target: kind,
+                        file: file.clone(),
+                        index,
+                        passed: case.passed(),
+                        duration_ms: case.duration.map(|d| d.as_secs_f64() * 1000.0),
+                    };
+                    outcomes.insert(outcome.key(), outcome);
+                }
+            }
+        }
+
+        let results = LastTestResults {
+            tests: outcomes.into_values().collect(),
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(&results)?;
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write test results to {}", path.display()))?;
+        debug!(path = %path.display(), count = results.tests.len(), "saved test outcomes");
+        Ok(())
+    }
+}
+
+/// The tests that failed when they last ran, limited to those selected by
+/// `filter`.
+pub(crate) fn last_failed(meta: &BuildMeta, filter: &TestFilter) -> anyhow::Result<PackageFilter> {
+    let pkg_dirs = &meta.resolve_output.pkg_dirs;
+    let mut failed = PackageFilter::default();
+    for outcome in load(&results_path(meta)).tests {
+        if outcome.passed {
+            continue;
+        }
+        // Packages may have been removed or renamed since.
+        let Some(package) = pkg_dirs.get_package_id_by_name(&outcome.package) else {
+            continue;
+        };
+        let target = package.build_target(outcome.target.kind());
+        if !filter.allows(target, &outcome.file, outcome.index) {
+            continue;
+        }
+        failed.add_one(
+            target,
+            Some(&outcome.file),
+            Some(TestIndexRange::from_single(outcome.index)?),
+        );
+    }
+    Ok(failed)
+}
+
+/// How long each test took the last time it ran, keyed by package, target
+/// kind, file and index.
+pub(super) fn last_durations(
+    meta: &BuildMeta,
+) -> HashMap<(String, TargetKind, String, u32), Duration> {
+    load(&results_path(meta))
+        .tests
+        .into_iter()
+        .filter_map(|outcome| {
+            let duration = Duration::try_from_secs_f64(outcome.duration_ms? / 1000.0).ok()?;
+            Some((
+                (
+                    outcome.package,
+                    outcome.target.kind(),
+                    outcome.file,
+                    outcome.index,
+                ),
+                duration,
+            ))
+        })
+        .collect()
+}
+
+/// Reorder `invocations` so that the tests selected by `first` run before the
+/// others.
+pub(super) fn run_first(invocations: &mut [TestInvocation], first: &PackageFilter) {
+    invocations.sort_by_key(|invocation| !first.0.contains_key(&invocation.target));
+    for invocation in invocations {
+        let Some(Some(files)) = first.0.get(&invocation.target) else {
+            continue;
+        };
+        let is_first = |file: &str, index: u32| {
+            files
+                .0
+                .get(file)
+                .is_some_and(|indices| indices.as_ref().is_none_or(|i| i.contains(index)))
+        };
+
+        let mut front = vec![];
+        let mut back = vec![];
+        for (file, ranges) in &invocation.args.file_and_index {
+            let (first_indices, rest): (Vec<u32>, Vec<u32>) = ranges
+                .iter()
+                .cloned()
+                .flatten()
+                .partition(|&index| is_first(file, index));
+            if !first_indices.is_empty() {
+                front.push((file.clone(), to_ranges(first_indices)));
+            }
+            if !rest.is_empty() {
+                back.push((file.clone(), to_ranges(rest)));
+            }
+        }
+        front.extend(back);
+        invocation.args.file_and_index = front;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moonbuild::entry::TestArgs;
+    use moonbuild_rupes_recta::model::BuildTarget;
+
+    use super::*;
+
+    fn invocation(kind: TargetKind, files: &[(&str, std::ops::Range<u32>)]) -> TestInvocation {
+        TestInvocation {
+            target: BuildTarget {
+                package: Default::default(),
+                kind,
+            },
+            executable: "t.wasm".into(),
+            args: TestArgs {
+                package: "user/pkg".to_string(),
+                file_and_index: files
+                    .iter()
+                    .map(|(file, range)| (file.to_string(), vec![range.clone()]))
+                    .collect(),
+            },
+            meta: serde_json_lenient::from_str(r#"{"no_args_tests": {}, "with_args_tests": {}}"#)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    #[allow(clippy::single_range_in_vec_init)] // ranges of test indices
+    fn previously_failed_tests_run_first() {
+        let mut invocations = [
+            invocation(TargetKind::WhiteboxTest, &[("w_wbtest.mbt", 0..2)]),
+            invocation(
+                TargetKind::BlackboxTest,
+                &[("a_test.mbt", 0..4), ("b_test.mbt", 0..2)],
+            ),
+        ];
+        let blackbox = invocations[1].target;
+        let mut first = PackageFilter::default();
+        for (file, index) in [("a_test.mbt", 2), ("b_test.mbt", 1)] {
+            first.add_one(
+                blackbox,
+                Some(file),
+                Some(TestIndexRange::from_single(index).unwrap()),
+            );
+        }
+
+        run_first(&mut invocations, &first);
+        assert_eq!(invocations[0].target, blackbox);
+        assert_eq!(
+            invocations[0].args.file_and_index,
+            vec![
+                ("a_test.mbt".to_string(), vec![2..3]),
+                ("b_test.mbt".to_string(), vec![1..2]),
+                ("a_test.mbt".to_string(), vec![0..2, 3..4]),
+                ("b_test.mbt".to_string(), vec![0..1]),
+            ]
+        );
+        assert_eq!(
+            invocations[1].args.file_and_index,
+            vec![("w_wbtest.mbt".to_string(), vec![0..2])]
+        );
+    }
+
+    #[test]
+    fn outcomes_of_removed_tests_are_stale() {
+        let outcome = |package: &str, target, file: &str, index| StoredOutcome {
+            package: package.to_string(),
+            target,
+            file: file.to_string(),
+            index,
+            passed: false,
+            duration_ms: None,
+        };
+        let existing = ExistingTests::from([(
+            ("user/pkg".to_string(), StoredTargetKind::Blackbox),
+            HashSet::from([("a_test.mbt".to_string(), 0)]),
+        )]);
+        let has_package = |package: &str| package == "user/pkg";
+
+        let kept = [
+            outcome("user/pkg", StoredTargetKind::Blackbox, "a_test.mbt", 0),
+            // Not built this time.
+            outcome("user/pkg", StoredTargetKind::Whitebox, "a_wbtest.mbt", 3),
+        ];
+        let stale = [
+            outcome("user/pkg", StoredTargetKind::Blackbox, "a_test.mbt", 1),
+            outcome(
+                "user/pkg",
+                StoredTargetKind::Blackbox,
+                "removed_test.mbt",
+                0,
+            ),
+            outcome("user/removed", StoredTargetKind::Blackbox, "a_test.mbt", 0),
+        ];
+        for outcome in &kept {
+            assert!(still_exists(outcome, has_package, &existing), "{outcome:?}");
+        }
+        for outcome in &stale {
+            assert!(
+                !still_exists(outcome, has_package, &existing),
+                "{outcome:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unreadable_results_are_treated_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(moonutil::constants::LAST_TEST_RESULTS_JSON);
+        assert!(load(&path).tests.is_empty());
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(load(&path).tests.is_empty());
+
+        std::fs::write(
+            &path,
+            r#"{"tests": [{"package": "user/pkg", "target": "blackbox", "file": "a_test.mbt", "index": 3, "passed": false}]}"#,
+        )
+        .unwrap();
+        let tests = load(&path).tests;
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].target.kind(), TargetKind::BlackboxTest);
+        assert!(!tests[0].passed);
+    }
+}
@@ -0,0 +1,471 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Machine-readable test reports (JUnit XML, TAP and JSON).
+//!
+//! Results from every target of a `moon test` invocation are collected into a
+//! single [`TestReport`], which is written once all targets have run.
+
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::Context;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use moonutil::target::TargetBackend;
+
+use crate::rr_build::BuildMeta;
+
+use super::{ReplaceableTestResults, TestCaseResult, TestResultKind};
+
+/// Format of the report written by `moon test --reporter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum TestReporter {
+    /// JUnit XML, as consumed by Jenkins and GitLab
+    Junit,
+    /// Test Anything Protocol, version 13
+    Tap,
+    /// A JSON document listing every test case
+    Json,
+}
+
+/// One test case, as it appears in a report.
+#[derive(Debug, Clone, Serialize)]
+struct ReportedTestCase {
+    backend: String,
+    package: String,
+    file: String,
+    index: u32,
+    name: String,
+    kind: TestResultKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+    attempts: u32,
+    flaky: bool,
+}
+
+impl ReportedTestCase {
+    fn passed(&self) -> bool {
+        matches!(self.kind, TestResultKind::Passed)
+    }
+
+    /// Runtime errors are reported as errors rather than assertion failures.
+    fn errored(&self) -> bool {
+        matches!(self.kind, TestResultKind::RuntimeError)
+    }
+
+    fn duration_secs(&self) -> f64 {
+        self.duration_ms.unwrap_or(0.0) / 1000.0
+    }
+}
+
+/// Test results accumulated across all targets of one invocation.
+#[derive(Debug, Default)]
+pub(crate) struct TestReport {
+    cases: Vec<ReportedTestCase>,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    flaky: usize,
+    tests: &'a [ReportedTestCase],
+}
+
+impl ReplaceableTestResults {
+    /// Append every test case of this run to `report`.
+    pub(crate) fn add_to_report(&self, meta: &BuildMeta, report: &mut TestReport) {
+        let backend = meta.target_backend().to_backend_ext();
+        for result in self.map.values() {
+            for (file, cases) in &result.map {
+                for (&index, case) in cases {
+                    report.cases.push(reported_case(backend, file, index, case));
+                }
+            }
+        }
+    }
+}
+
+fn reported_case(backend: &str, file: &str, index: u32, case: &TestCaseResult) -> ReportedTestCase {
+    let name = case
+        .meta
+        .name
+        .clone()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("{file}#{index}"));
+    let message = match case.kind {
+        TestResultKind::Passed => None,
+        TestResultKind::ExpectPanic if case.raw.message.is_empty() => {
+            Some("panic is expected".to_string())
+        }
+        _ => Some(case.raw.message.clone()),
+    };
+    ReportedTestCase {
+        backend: backend.to_string(),
+        package: case.raw.package.clone(),
+        file: file.to_string(),
+        index,
+        name,
+        kind: case.kind,
+        message,
+        duration_ms: case.duration.map(|d| d.as_secs_f64() * 1000.0),
+        attempts: case.attempts,
+        flaky: case.flaky(),
+    }
+}
+
+impl TestReport {
+    /// Record an error that stopped the tests of `backend` from finishing, so
+    /// that a report of an interrupted run is never mistaken for a passing one.
+    pub(crate) fn add_error(&mut self, backend: TargetBackend, error: &anyhow::Error) {
+        self.cases.push(ReportedTestCase {
+            backend: backend.to_backend_ext().to_string(),
+            package: "moon".to_string(),
+            file: String::new(),
+            index: 0,
+            name: "test run".to_string(),
+            kind: TestResultKind::RuntimeError,
+            message: Some(format!("{error:#}")),
+            duration_ms: None,
+            attempts: 1,
+            flaky: false,
+        });
+    }
+
+    pub(crate) fn render(&self, reporter: TestReporter) -> String {
+        match reporter {
+            TestReporter::Junit => render_junit(&self.cases),
+            TestReporter::Tap => render_tap(&self.cases),
+            TestReporter::Json => {
+                let passed = self.cases.iter().filter(|case| case.passed()).count();
+                let report = JsonReport {
+                    total: self.cases.len(),
+                    passed,
+                    failed: self.cases.len() - passed,
+                    flaky: self.cases.iter().filter(|case| case.flaky).count(),
+                    tests: &self.cases,
+                };
+                serde_json::to_string_pretty(&report).expect("test report is serializable") + "\n"
+            }
+        }
+    }
+
+    /// Write the report to `path`, or to stdout if no path is given.
+    pub(crate) fn write(&self, reporter: TestReporter, path: Option<&Path>) -> anyhow::Result<()> {
+        let rendered = self.render(reporter);
+        match path {
+            Some(path) => {
+                if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("failed to create directory `{}`", parent.display())
+                    })?;
+                }
+                std::fs::write(path, rendered)
+                    .with_context(|| format!("failed to write test report `{}`", path.display()))
+            }
+            None => {
+                print!("{rendered}");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Escape text for use in XML attributes and character data, dropping
+/// control characters that XML 1.0 cannot represent.
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn render_junit(cases: &[ReportedTestCase]) -> String {
+    // One suite per package and backend, in first-seen order.
+    let mut suites = IndexMap::<(&str, &str), Vec<&ReportedTestCase>>::new();
+    for case in cases {
+        suites
+            .entry((case.package.as_str(), case.backend.as_str()))
+            .or_default()
+            .push(case);
+    }
+    let count = |cases: &[&ReportedTestCase]| {
+        let failures = cases.iter().filter(|c| !c.passed() && !c.errored()).count();
+        let errors = cases.iter().filter(|c| c.errored()).count();
+        let time = cases.iter().map(|c| c.duration_secs()).sum::<f64>();
+        (failures, errors, time)
+    };
+
+    let all = cases.iter().collect::<Vec<_>>();
+    let (failures, errors, time) = count(&all);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"moon test\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+        cases.len()
+    );
+    for ((package, backend), cases) in &suites {
+        let (failures, errors, time) = count(cases);
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">",
+            xml_escape(&format!("{package} [{backend}]")),
+            cases.len()
+        );
+        for case in cases {
+            let _ = write!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" time=\"{:.3}\"",
+                xml_escape(&case.name),
+                xml_escape(package),
+                xml_escape(&case.file),
+                case.duration_secs()
+            );
+            if case.flaky {
+                // Surefire's convention for a test that passed on a rerun.
+                out.push_str(">\n");
+                let _ = writeln!(
+                    out,
+                    "      <flakyFailure message=\"passed on attempt {}\"/>",
+                    case.attempts
+                );
+                out.push_str("    </testcase>\n");
+                continue;
+            }
+            if case.passed() {
+                out.push_str("/>\n");
+                continue;
+            }
+            out.push_str(">\n");
+            let element = if case.errored() { "error" } else { "failure" };
+            let message = case.message.as_deref().unwrap_or_default();
+            let summary = message.lines().next().unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "      <{element} type=\"{:?}\" message=\"{}\">{}</{element}>",
+                case.kind,
+                xml_escape(summary),
+                xml_escape(message)
+            );
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn render_tap(cases: &[ReportedTestCase]) -> String {
+    let mut out = String::from("TAP version 13\n");
+    let _ = writeln!(out, "1..{}", cases.len());
+    for (number, case) in cases.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "{} {} - {} [{}] {}",
+            if case.passed() { "ok" } else { "not ok" },
+            number + 1,
+            case.package,
+            case.backend,
+            case.name.replace('#', "\\#")
+        );
+        if case.flaky {
+            out.push_str("  ---\n");
+            out.push_str("  flaky: true\n");
+            let _ = writeln!(out, "  attempts: {}", case.attempts);
+            out.push_str("  ...\n");
+            continue;
+        }
+        if case.passed() {
+            continue;
+        }
+        out.push_str("  ---\n");
+        let _ = writeln!(out, "  kind: {:?}", case.kind);
+        if !case.file.is_empty() {
+            let _ = writeln!(out, "  file: {}", case.file);
+        }
+        let _ = writeln!(out, "  index: {}", case.index);
+        if case.attempts > 1 {
+            let _ = writeln!(out, "  attempts: {}", case.attempts);
+        }
+        if let Some(ms) = case.duration_ms {
+            let _ = writeln!(out, "  duration_ms: {ms:.3}");
+        }
+        if let Some(message) = &case.message {
+            out.push_str("  message: |-\n");
+            for line in message.lines() {
+                let line = line
+                    .chars()
+                    .filter(|&c| c == '\t' || !c.is_control())
+                    .collect::<String>();
+                let _ = writeln!(out, "    {line}");
+            }
+        }
+        out.push_str("  ...\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::expect;
+
+    use super::*;
+
+    fn sample() -> TestReport {
+        let case = |index, name: &str, kind, message: Option<&str>| ReportedTestCase {
+            backend: "wasm-gc".to_string(),
+            package: "user/lib".to_string(),
+            file: "lib_test.mbt".to_string(),
+            index,
+            name: name.to_string(),
+            kind,
+            message: message.map(str::to_string),
+            duration_ms: Some(1.5),
+            attempts: 1,
+            flaky: false,
+        };
+        TestReport {
+            cases: vec![
+                case(0, "adds", TestResultKind::Passed, None),
+                case(
+                    1,
+                    "lib_test.mbt#1",
+                    TestResultKind::Failed,
+                    Some("expected <1> & got\n\"2\""),
+                ),
+                case(
+                    2,
+                    "aborts",
+                    TestResultKind::RuntimeError,
+                    Some("abort\u{1b}"),
+                ),
+                ReportedTestCase {
+                    attempts: 2,
+                    flaky: true,
+                    ..case(3, "retried", TestResultKind::Passed, None)
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn junit_groups_cases_by_package_and_backend() {
+        expect![[r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <testsuites name="moon test" tests="4" failures="1" errors="1" time="0.006">
+              <testsuite name="user/lib [wasm-gc]" tests="4" failures="1" errors="1" time="0.006">
+                <testcase name="adds" classname="user/lib" file="lib_test.mbt" time="0.002"/>
+                <testcase name="lib_test.mbt#1" classname="user/lib" file="lib_test.mbt" time="0.002">
+                  <failure type="Failed" message="expected &lt;1&gt; &amp; got">expected &lt;1&gt; &amp; got
+            &quot;2&quot;</failure>
+                </testcase>
+                <testcase name="aborts" classname="user/lib" file="lib_test.mbt" time="0.002">
+                  <error type="RuntimeError" message="abort">abort</error>
+                </testcase>
+                <testcase name="retried" classname="user/lib" file="lib_test.mbt" time="0.002">
+                  <flakyFailure message="passed on attempt 2"/>
+                </testcase>
+              </testsuite>
+            </testsuites>
+        "#]]
+        .assert_eq(&sample().render(TestReporter::Junit));
+    }
+
+    #[test]
+    fn tap_reports_failures_with_a_yaml_block() {
+        expect![[r#"
+            TAP version 13
+            1..4
+            ok 1 - user/lib [wasm-gc] adds
+            not ok 2 - user/lib [wasm-gc] lib_test.mbt\#1
+              ---
+              kind: Failed
+              file: lib_test.mbt
+              index: 1
+              duration_ms: 1.500
+              message: |-
+                expected <1> & got
+                "2"
+              ...
+            not ok 3 - user/lib [wasm-gc] aborts
+              ---
+              kind: RuntimeError
+              file: lib_test.mbt
+              index: 2
+              duration_ms: 1.500
+              message: |-
+                abort
+              ...
+            ok 4 - user/lib [wasm-gc] retried
+              ---
+              flaky: true
+              attempts: 2
+              ...
+        "#]]
+        .assert_eq(&sample().render(TestReporter::Tap));
+    }
+
+    #[test]
+    fn errors_are_reported_as_failed_cases() {
+        let mut report = TestReport::default();
+        report.add_error(
+            TargetBackend::WasmGC,
+            &anyhow::anyhow!("test runner crashed"),
+        );
+        expect![[r#"
+            TAP version 13
+            1..1
+            not ok 1 - moon [wasm-gc] test run
+              ---
+              kind: RuntimeError
+              index: 0
+              message: |-
+                test runner crashed
+              ...
+        "#]]
+        .assert_eq(&report.render(TestReporter::Tap));
+    }
+
+    #[test]
+    fn json_report_includes_a_summary() {
+        let rendered = sample().render(TestReporter::Json);
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value["total"], 4);
+        assert_eq!(value["passed"], 2);
+        assert_eq!(value["failed"], 2);
+        assert_eq!(value["flaky"], 1);
+        assert_eq!(value["tests"][3]["attempts"], 2);
+        assert_eq!(value["tests"][1]["kind"], "failed");
+        assert_eq!(value["tests"][1]["duration_ms"], 1.5);
+        assert!(value["tests"][0].get("message").is_none());
+    }
+}
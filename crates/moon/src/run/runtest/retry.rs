@@ -0,0 +1,145 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Handles retrying failed tests
+
+use moonutil::build_options::TestIndexRange;
+use tracing::debug;
+
+use crate::run::PackageFilter;
+
+use super::ReplaceableTestResults;
+
+impl ReplaceableTestResults {
+    /// A filter selecting exactly the test cases that failed, for rerunning
+    /// them.
+    pub(crate) fn failed_filter(&self) -> anyhow::Result<PackageFilter> {
+        let mut filter = PackageFilter::default();
+        for (target, target_result) in &self.map {
+            for (file, cases) in &target_result.map {
+                for (&index, case) in cases {
+                    if !case.passed() {
+                        filter.add_one(
+                            *target,
+                            Some(file),
+                            Some(TestIndexRange::from_single(index)?),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Merge the results of rerunning failed tests, where `attempt` counts
+    /// the runs so far including this one. A test that passes now is flaky.
+    pub(crate) fn merge_retry(&mut self, retry: ReplaceableTestResults, attempt: u32) {
+        for (target, target_result) in retry.map {
+            for (file, cases) in target_result.map {
+                for (index, mut case) in cases {
+                    debug!(
+                        ?target,
+                        file,
+                        index,
+                        attempt,
+                        passed = case.passed(),
+                        "retried test"
+                    );
+                    case.attempts = attempt;
+                    self.map.entry(target).or_default().add(&file, index, case);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use moonbuild::runtest::TestStatistics;
+    use moonbuild_rupes_recta::model::{BuildTarget, TargetKind};
+    use moonutil::test_metadata::MbtTestInfo;
+
+    use super::super::{TargetTestResult, TestCaseResult, TestResultKind};
+    use super::*;
+
+    fn case(index: u32, kind: TestResultKind) -> TestCaseResult {
+        TestCaseResult {
+            kind,
+            raw: Arc::new(TestStatistics {
+                package: "user/pkg".to_string(),
+                filename: "a_test.mbt".to_string(),
+                index: index.to_string(),
+                test_name: String::new(),
+                message: String::new(),
+                duration_ms: None,
+            }),
+            meta: MbtTestInfo {
+                index,
+                func: format!("t{index}"),
+                name: None,
+                line_number: None,
+                attrs: vec![],
+            },
+            duration: None,
+            attempts: 1,
+        }
+    }
+
+    fn results(cases: impl IntoIterator<Item = (u32, TestResultKind)>) -> ReplaceableTestResults {
+        let target = BuildTarget {
+            package: Default::default(),
+            kind: TargetKind::BlackboxTest,
+        };
+        let mut result = TargetTestResult::default();
+        for (index, kind) in cases {
+            result.add("a_test.mbt", index, case(index, kind));
+        }
+        let mut results = ReplaceableTestResults::default();
+        results.merge_with_target(target, result);
+        results
+    }
+
+    #[test]
+    fn retries_select_failed_cases_and_mark_recovered_ones_flaky() {
+        let mut first = results([
+            (0, TestResultKind::Passed),
+            (1, TestResultKind::Failed),
+            (2, TestResultKind::RuntimeError),
+        ]);
+        let filter = first.failed_filter().unwrap();
+        let (_, files) = filter.0.first().unwrap();
+        let indices = files.as_ref().unwrap().0["a_test.mbt"].as_ref().unwrap();
+        assert!(!indices.contains(0));
+        assert!(indices.contains(1));
+        assert!(indices.contains(2));
+
+        first.merge_retry(
+            results([(1, TestResultKind::Passed), (2, TestResultKind::Failed)]),
+            2,
+        );
+        let summary = first.summary();
+        assert_eq!((summary.total, summary.passed, summary.flaky), (3, 2, 1));
+
+        first.merge_retry(results([(2, TestResultKind::Passed)]), 3);
+        let summary = first.summary();
+        assert_eq!((summary.total, summary.passed, summary.flaky), (3, 3, 2));
+        assert!(first.failed_filter().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Splits the selected tests into disjoint shards for `moon test --shard K/N`.
+//!
+//! Every shard enumerates the same test cases in the same order, so as long as
+//! all shards see the same sources (and, when balancing by duration, the same
+//! recorded timings), each test runs on exactly one shard.
+
+use std::time::Duration;
+
+use moonbuild_rupes_recta::model::{BuildTarget, TargetKind};
+use moonutil::build_options::{TestIndexRange, TestShard};
+use moonutil::user_log::UserLog;
+use tracing::debug;
+
+use crate::rr_build::BuildMeta;
+use crate::run::{PackageFilter, TestFilter};
+
+use super::{collect_test_invocations, last_run};
+
+struct ShardCase {
+    target: BuildTarget,
+    package: String,
+    file: String,
+    index: u32,
+}
+
+impl ShardCase {
+    fn key(&self) -> (&str, TargetKind, &str, u32) {
+        (&self.package, self.target.kind, &self.file, self.index)
+    }
+}
+
+/// Restrict `filter` to the tests that belong to `shard`. With `by_duration`,
+/// shards are balanced by the durations recorded in the last run instead of
+/// by test count.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn select_shard(
+    meta: &BuildMeta,
+    filter: &TestFilter,
+    include_skipped: bool,
+    bench: bool,
+    shard: TestShard,
+    by_duration: bool,
+    user_log: &UserLog,
+) -> anyhow::Result<TestFilter> {
+    let mut cases = vec![];
+    for invocation in collect_test_invocations(meta, filter, include_skipped, bench)? {
+        for (file, ranges) in &invocation.args.file_and_index {
+            for index in ranges.iter().cloned().flatten() {
+                cases.push(ShardCase {
+                    target: invocation.target,
+                    package: invocation.args.package.clone(),
+                    file: file.clone(),
+                    index,
+                });
+            }
+        }
+    }
+    cases.sort_by(|a, b| a.key().cmp(&b.key()));
+    cases.dedup_by(|a, b| a.key() == b.key());
+
+    let weights = if by_duration {
+        let weights = duration_weights(meta, &cases);
+        if weights.is_none() {
+            user_log
+                .warn("no test durations were recorded by earlier runs, sharding by test count.");
+        }
+        weights
+    } else {
+        None
+    };
+    let assignment = match &weights {
+        Some(weights) => assign_by_duration(weights, shard.count),
+        None => assign_by_count(cases.len(), shard.count),
+    };
+
+    let mut selected = PackageFilter::default();
+    let mut selected_count = 0;
+    for (case, assigned) in cases.iter().zip(assignment) {
+        if assigned + 1 == shard.index {
+            selected.add_one(
+                case.target,
+                Some(&case.file),
+                Some(TestIndexRange::from_single(case.index)?),
+            );
+            selected_count += 1;
+        }
+    }
+    debug!(
+        shard = shard.index,
+        count = shard.count,
+        selected_count,
+        total = cases.len(),
+        "selected test shard"
+    );
+    user_log.info(format!(
+        "shard {}/{}: running {selected_count} of {} tests",
+        shard.index,
+        shard.count,
+        cases.len()
+    ));
+
+    Ok(TestFilter {
+        filter: Some(selected),
+        name_filter: filter.name_filter.clone(),
+        tags: filter.tags.clone(),
+    })
+}
+
+/// The recorded duration of each case, using the mean for cases without one.
+/// `None` if no case has a recorded duration.
+fn duration_weights(meta: &BuildMeta, cases: &[ShardCase]) -> Option<Vec<Duration>> {
+    let recorded = last_run::last_durations(meta);
+    let known = cases
+        .iter()
+        .map(|case| {
+            recorded
+                .get(&(
+                    case.package.clone(),
+                    case.target.kind,
+                    case.file.clone(),
+                    case.index,
+                ))
+                .copied()
+        })
+        .collect::<Vec<_>>();
+    let timed = known.iter().flatten().count();
+    if timed == 0 {
+        return None;
+    }
+    let mean = known.iter().flatten().sum::<Duration>() / timed as u32;
+    Some(known.into_iter().map(|d| d.unwrap_or(mean)).collect())
+}
+
+/// Deal the cases out round-robin.
+fn assign_by_count(len: usize, count: u32) -> Vec<u32> {
+    (0..len).map(|i| (i % count as usize) as u32).collect()
+}
+
+/// Give each case, longest first, to the shard with the least total duration
+/// so far.
+fn assign_by_duration(weights: &[Duration], count: u32) -> Vec<u32> {
+    let mut order = (0..weights.len()).collect::<Vec<_>>();
+    // Stable, so equal durations keep the order of the sorted cases.
+    order.sort_by(|&a, &b| weights[b].cmp(&weights[a]));
+
+    let mut loads = vec![Duration::ZERO; count as usize];
+    let mut assignment = vec![0; weights.len()];
+    for case in order {
+        let (lightest, _) = loads
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, load)| **load)
+            .expect("there is at least one shard");
+        loads[lightest] += weights[case];
+        assignment[case] = lightest as u32;
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shard_specs_are_validated() {
+        let shard = "2/4".parse::<TestShard>().unwrap();
+        assert_eq!((shard.index, shard.count), (2, 4));
+        for (spec, error) in [
+            ("3", "expected `K/N`, e.g. `1/4`"),
+            ("a/2", "invalid number `a`"),
+            ("1/0", "shard count must be at least 1"),
+            ("0/2", "shard index must be between 1 and 2"),
+            ("3/2", "shard index must be between 1 and 2"),
+        ] {
+            assert_eq!(spec.parse::<TestShard>().unwrap_err().to_string(), error);
+        }
+    }
+
+    #[test]
+    fn count_sharding_deals_cases_round_robin() {
+        assert_eq!(assign_by_count(5, 2), [0, 1, 0, 1, 0]);
+        assert_eq!(assign_by_count(2, 3), [0, 1]);
+    }
+
+    #[test]
+    fn duration_sharding_balances_total_time() {
+        let ms = Duration::from_millis;
+        let weights = [ms(10), ms(70), ms(20), ms(30), ms(30)];
+        let assignment = assign_by_duration(&weights, 2);
+        assert_eq!(assignment, [0, 0, 1, 1, 1]);
+
+        let mut loads = [Duration::ZERO; 2];
+        for (weight, shard) in weights.iter().zip(&assignment) {
+            loads[*shard as usize] += *weight;
+        }
+        assert_eq!(loads, [ms(80), ms(80)]);
+    }
+}
@@ -0,0 +1,128 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Random test ordering for `moon test --shuffle`.
+//!
+//! The test drivers of every backend run tests in the order they are passed,
+//! so shuffling only needs to permute the arguments of each invocation.
+
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+use super::{TestInvocation, to_ranges};
+
+/// Permute the order of `invocations`, and of the tests within each of them,
+/// across files. The same seed and test selection always give the same order.
+pub(super) fn shuffle(invocations: &mut [TestInvocation], seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    invocations.shuffle(&mut rng);
+    for invocation in invocations {
+        let mut tests = invocation
+            .args
+            .file_and_index
+            .iter()
+            .flat_map(|(file, ranges)| {
+                ranges
+                    .iter()
+                    .cloned()
+                    .flatten()
+                    .map(move |index| (file, index))
+            })
+            .collect::<Vec<_>>();
+        tests.shuffle(&mut rng);
+
+        // Consecutive tests of the same file still share one argument.
+        let mut runs: Vec<(&String, Vec<u32>)> = vec![];
+        for (file, index) in tests {
+            match runs.last_mut() {
+                Some((last, indices)) if *last == file => indices.push(index),
+                _ => runs.push((file, vec![index])),
+            }
+        }
+        invocation.args.file_and_index = runs
+            .into_iter()
+            .map(|(file, indices)| (file.clone(), to_ranges(indices)))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use moonbuild::entry::TestArgs;
+    use moonbuild_rupes_recta::model::{BuildTarget, TargetKind};
+
+    use super::*;
+
+    fn invocation() -> TestInvocation {
+        TestInvocation {
+            target: BuildTarget {
+                package: Default::default(),
+                kind: TargetKind::BlackboxTest,
+            },
+            executable: "a.wasm".into(),
+            args: TestArgs {
+                package: "user/pkg".to_string(),
+                file_and_index: vec![
+                    ("a_test.mbt".to_string(), vec![0..2, 2..4]),
+                    ("b_test.mbt".to_string(), vec![0..2, 5..7]),
+                ],
+            },
+            meta: serde_json_lenient::from_str(r#"{"no_args_tests": {}, "with_args_tests": {}}"#)
+                .unwrap(),
+        }
+    }
+
+    fn order(invocation: &TestInvocation) -> Vec<(String, u32)> {
+        invocation
+            .args
+            .file_and_index
+            .iter()
+            .flat_map(|(file, ranges)| {
+                ranges
+                    .iter()
+                    .cloned()
+                    .flatten()
+                    .map(move |index| (file.clone(), index))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation_reproducible_by_seed() {
+        let original = order(&invocation());
+
+        let shuffled = |seed| {
+            let mut invocations = [invocation()];
+            shuffle(&mut invocations, seed);
+            order(&invocations[0])
+        };
+        let first = shuffled(42);
+        assert_eq!(first, shuffled(42));
+        assert_ne!(first, original);
+
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(sorted, original);
+
+        // Tests of different files are interleaved under some seed.
+        assert!((0..16).map(shuffled).any(|order| {
+            order
+                .windows(3)
+                .any(|w| w[0].0 != w[1].0 && w[1].0 != w[2].0)
+        }));
+    }
+}
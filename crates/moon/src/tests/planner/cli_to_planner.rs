@@ -69,6 +69,7 @@ fn command_string_run_parses_as_expected() {
                     deny_warn: false,
                     no_render: false,
                     output_json: false,
+                    message_format: None,
                     warn_list: None,
                     enable_value_tracing: false,
                     jobs: None,
@@ -79,6 +80,7 @@ fn command_string_run_parses_as_expected() {
                 moonrun_policy: None,
                 auto_sync_flags: AutoSyncFlags {
                     frozen: false,
+                    locked: false,
                 },
                 build_only: false,
                 profile: false,
@@ -174,6 +176,7 @@ fn native_target_dry_run_test_command_parses_as_expected() {
                     deny_warn: false,
                     no_render: false,
                     output_json: false,
+                    message_format: None,
                     warn_list: None,
                     enable_value_tracing: false,
                     jobs: None,
@@ -188,17 +191,31 @@ fn native_target_dry_run_test_command_parses_as_expected() {
                 limit: 256,
                 auto_sync_flags: AutoSyncFlags {
                     frozen: false,
+                    locked: false,
                 },
                 build_only: false,
                 profile: false,
                 no_parallelize: false,
+                watch: false,
+                timeout: None,
+                retries: 0,
+                rerun_failed: false,
+                failed_first: false,
+                shard: None,
+                shard_by_duration: false,
+                report_slowest: None,
+                shuffle: None,
                 outline: false,
                 test_failure_json: false,
+                reporter: None,
+                report_file: None,
                 patch_file: None,
                 doc_test: false,
                 path: [],
                 include_skipped: false,
                 filter: None,
+                tags: [],
+                exclude_tags: [],
             },
         )
     "#]]
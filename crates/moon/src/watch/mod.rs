@@ -25,6 +25,7 @@ use moonutil::constants::is_watch_relevant_project_file;
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
 use std::collections::HashSet;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
@@ -53,13 +54,23 @@ pub(crate) fn watching(
     run: impl Fn() -> anyhow::Result<WatchOutput>,
     watch_root: &Path,
     ignored_subtree: &Path,
+) -> anyhow::Result<i32> {
+    watching_changes(|_| run(), watch_root, ignored_subtree)
+}
+
+/// Like [`watching`], but passes the changed paths that triggered each rerun
+/// to `run`. The initial run receives `None`.
+pub(crate) fn watching_changes(
+    run: impl Fn(Option<&[PathBuf]>) -> anyhow::Result<WatchOutput>,
+    watch_root: &Path,
+    ignored_subtree: &Path,
 ) -> anyhow::Result<i32> {
     // Initial run
     debug!(
         watch_root = %watch_root.display(),
         "Initial run before starting watcher"
     );
-    let mut additional_paths = run_and_print(&run);
+    let mut additional_paths = run_and_print(|| run(None));
 
     // Setup watcher
     let (tx, rx) = std::sync::mpsc::channel();
@@ -133,13 +144,15 @@ pub(crate) fn watching(
             }
 
             debug!("Debounced {} filesystem event(s)", evt_list.len());
-            if !check_rerun_trigger(watch_root, ignored_subtree, &evt_list, &additional_paths) {
+            let changed =
+                rerun_trigger_paths(watch_root, ignored_subtree, &evt_list, &additional_paths);
+            if changed.is_empty() {
                 trace!("No rerun triggered after checking events");
                 continue;
             }
 
             debug!("Rerun triggered; executing task");
-            additional_paths = run_and_print(&run);
+            additional_paths = run_and_print(|| run(Some(&changed)));
         }
     }
     Ok(0)
@@ -173,13 +186,14 @@ fn is_event_relevant(event: &notify::Event) -> bool {
     true
 }
 
-/// Determine if we should rerun based on the event. Returns true if we should rerun.
-fn check_rerun_trigger(
+/// Determine if we should rerun based on the event. Returns the normalized
+/// paths that triggered the rerun, or nothing if we should not rerun.
+fn rerun_trigger_paths(
     repo_root: &Path,
     ignored_subtree: &Path,
     event_lst: &[notify::Event],
     additional_paths: &AdditionalWatchPaths,
-) -> bool {
+) -> Vec<PathBuf> {
     debug!(
         "Evaluating {} filesystem event(s) for relevance",
         event_lst.len()
@@ -191,7 +205,7 @@ fn check_rerun_trigger(
 
     if relevant_events.is_empty() {
         trace!("No relevant changes detected; skipping run");
-        return false;
+        return Vec::new();
     }
 
     let trigger = check_paths(
@@ -200,17 +214,18 @@ fn check_rerun_trigger(
         additional_paths,
         &relevant_events,
     );
-    debug!("Have we triggered a rebuild?: {}", trigger);
+    debug!("Have we triggered a rebuild?: {}", !trigger.is_empty());
     trigger
 }
 
-// Check the paths in the events against the ignore rules.
+// Check the paths in the events against the ignore rules, returning the ones
+// that pass.
 fn check_paths(
     repo_root: &Path,
     ignored_subtree: &Path,
     additional_paths: &AdditionalWatchPaths,
     relevant_events: &[&notify::Event],
-) -> bool {
+) -> Vec<PathBuf> {
     // Check if any of the relevant events are in ignored dirs.
     // Note: `_build/` and `.mooncakes/` are always ignored by default.
     let mut ignore_builder = filter_files::FileFilterBuilder::new(repo_root);
@@ -218,6 +233,7 @@ fn check_paths(
     // An equal or ancestor directory would also suppress source events.
     let filters_output_subtree =
         ignored_subtree != repo_root && ignored_subtree.starts_with(repo_root);
+    let mut triggered = Vec::new();
 
     for evt in relevant_events {
         for path in &evt.paths {
@@ -268,12 +284,15 @@ fn check_paths(
                     path.display(),
                     evt.kind
                 );
-                return true;
+                let path = normalize_watch_path(path);
+                if !triggered.contains(&path) {
+                    triggered.push(path);
+                }
             }
         }
     }
 
-    false
+    triggered
 }
 
 fn path_matches(paths: &HashSet<PathBuf>, path: &Path) -> bool {
@@ -307,6 +326,14 @@ fn normalize_watch_path(path: &Path) -> PathBuf {
     path.to_path_buf()
 }
 
+/// Clear the terminal, if stdout is one, so that a rerun redraws its output
+/// from the top.
+pub(crate) fn clear_terminal() {
+    if std::io::stdout().is_terminal() {
+        print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+    }
+}
+
 /// Clear the terminal and run the given function, printing success or error.
 /// Returns additional paths to watch or ignore in the next run.
 fn run_and_print(run: impl FnOnce() -> anyhow::Result<WatchOutput>) -> AdditionalWatchPaths {
@@ -368,6 +395,15 @@ mod tests {
         }
     }
 
+    fn check_rerun_trigger(
+        repo_root: &Path,
+        ignored_subtree: &Path,
+        event_lst: &[notify::Event],
+        additional_paths: &AdditionalWatchPaths,
+    ) -> bool {
+        !rerun_trigger_paths(repo_root, ignored_subtree, event_lst, additional_paths).is_empty()
+    }
+
     fn check_events(
         root: &Path,
         events: &[notify::Event],
@@ -385,6 +421,39 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn rerun_trigger_paths_lists_each_changed_file_once() {
+        use std::fs;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = dunce::canonicalize(temp_dir.path()).unwrap();
+
+        let lib = root.join("src/lib/lib.mbt");
+        let main = root.join("src/main/main.mbt");
+        for file in [&lib, &main] {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, "stuff").unwrap();
+        }
+        let ignored = root.join("_build/wasm-gc/lib.mbt");
+        fs::create_dir_all(ignored.parent().unwrap()).unwrap();
+        fs::write(&ignored, "stuff").unwrap();
+
+        let events = [
+            build_event(&lib),
+            build_event(&ignored),
+            build_event(&main),
+            build_event(&lib),
+        ];
+        let paths = rerun_trigger_paths(
+            &root,
+            &root.join("_build"),
+            &events,
+            &AdditionalWatchPaths::default(),
+        );
+
+        assert_eq!(paths, vec![lib, main]);
+    }
+
     #[test]
     fn rerun_ignored_for_ignored_paths() {
         use std::fs;
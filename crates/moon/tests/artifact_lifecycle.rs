@@ -31,6 +31,7 @@ fn virtual_contract_uses_lifecycle_interface_dependencies() {
         .args(["build", "src/virtual", "--target", "wasm-gc", "--dry-run"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .success()
@@ -44,6 +45,7 @@ moonc build-interface ./src/virtual/pkg.mbti -o ./_build/wasm-gc/debug/build/vir
         .args(["check", "src/virtual", "--target", "wasm-gc", "--dry-run"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .success()
@@ -82,6 +82,7 @@ pub fn moon_cmd(dir: &impl AsRef<Path>) -> snapbox::cmd::Command {
         .env("MOON_TOOLCHAIN_ROOT", toolchain_root_for_tests())
         .env("MOONRUN_OVERRIDE", moonrun_bin())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(dir)
 }
 
@@ -90,6 +91,7 @@ pub fn moon_process_cmd(dir: &impl AsRef<Path>) -> std::process::Command {
     cmd.env("MOON_TOOLCHAIN_ROOT", toolchain_root_for_tests())
         .env("MOONRUN_OVERRIDE", moonrun_bin())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(dir);
     cmd
 }
@@ -254,6 +256,22 @@ pub fn get_err_stderr(
     replace_dir(&s, dir)
 }
 
+#[track_caller]
+pub fn get_err_stdout_with_envs(
+    dir: &impl AsRef<std::path::Path>,
+    args: impl IntoIterator<Item = impl AsRef<std::ffi::OsStr>>,
+    envs: impl IntoIterator<Item = (impl AsRef<std::ffi::OsStr>, impl AsRef<std::ffi::OsStr>)>,
+) -> String {
+    let s = get_output_without_replace(
+        dir,
+        args,
+        envs,
+        ExpectedStatus::Failure,
+        OutputStream::Stdout,
+    );
+    replace_dir(&s, dir)
+}
+
 #[track_caller]
 pub fn get_err_stderr_with_envs(
     dir: &impl AsRef<std::path::Path>,
@@ -30,6 +30,7 @@ fn moon_check(dir: &TestDir) -> snapbox::cmd::Command {
         .args(["check", "--target", "wasm-gc"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(dir)
 }
 
@@ -38,6 +39,7 @@ fn moon_test(dir: &TestDir) -> snapbox::cmd::Command {
         .args(["test", "--target", "wasm-gc"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(dir)
 }
 
@@ -59,6 +61,7 @@ fn multi_backend_check_shares_the_package_prebuild_provider() {
         .args(["check", "--target", "all"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .success();
@@ -78,6 +81,7 @@ fn builtin_embed_handles_moon_path_with_spaces() {
         .args(["check", "--target", "wasm-gc"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .success();
@@ -243,6 +247,7 @@ fn bin_dep_uses_existing_legacy_virtual_contract() {
         .arg(dir.join("bin"))
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .success();
@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn test_build_cache_restores_package_outputs_after_clean() {
+    let dir = TestDir::new("hello");
+    let cache = tempfile::TempDir::new().unwrap();
+    let build = || {
+        let output = moon_cmd(&dir)
+            .env("MOON_BUILD_CACHE", cache.path())
+            .args(["build", "--target", "wasm-gc"])
+            .assert()
+            .success()
+            .get_output()
+            .stderr
+            .to_owned();
+        String::from_utf8_lossy(&output).into_owned()
+    };
+
+    build();
+    let records = cache.path().join("v1/actions");
+    assert!(
+        records.read_dir().unwrap().next().is_some(),
+        "the first build should publish package outputs"
+    );
+
+    moon_cmd(&dir).arg("clean").assert().success();
+    check(
+        build(),
+        expect![[r#"
+            Finished. moon: ran 1 task, now up to date
+        "#]],
+    );
+}
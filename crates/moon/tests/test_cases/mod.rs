@@ -39,6 +39,7 @@ mod backend;
 mod backend_config;
 mod bench2;
 mod blackbox;
+mod build_cache;
 mod build_package_dep_core;
 mod build_workflow;
 mod check_fmt;
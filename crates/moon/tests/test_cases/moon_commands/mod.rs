@@ -498,23 +498,17 @@ fn test_run_with_explicit_target_exits_with_guest_exit_code() {
 #[test]
 fn test_test_with_explicit_target_fails_on_test_executable_exit_code() {
     let dir = TestDir::new("moon_run_with_cli_args.in");
-    let stderr = get_err_stderr_with_envs(
+    let stdout = get_err_stdout_with_envs(
         &dir,
         ["test", "main", "--target", "wasm", "--filter", "exit-7"],
         [("MOONRUN_OVERRIDE", moonrun_bin())],
     );
     snapbox::assert_data_eq!(
-        stderr,
+        stdout,
         snapbox::str![[r#"
 ...
-Error: failed to run test for target Wasm
-
-Caused by:
-    Failed to run the test: $ROOT/_build/wasm/debug/test/main/main.blackbox_test.wasm
-    The test executable exited with exit [..]: 7
-    Active test at executable exit:
-      - $ROOT/main/exit_wasm_test.mbt:1 "exit-7"
-
+[..]"exit-7"[..] failed: the test executable exited with exit [..]: 7
+Total tests: 1, passed: 0, failed: 1.
 "#]],
     );
 }
@@ -478,6 +478,35 @@ fn test_moon_test_with_local_dep() {
     );
 }
 
+#[test]
+fn test_moon_test_locked_rejects_stale_lock_file() {
+    let dir = TestDir::new("moon_test/with_local_deps");
+    let stale = r#"{
+  "version": 1,
+  "modules": [
+    {
+      "name": "alice/lib",
+      "version": "0.1.0",
+      "source": "registry",
+      "checksum": "sha256:0000"
+    }
+  ]
+}
+"#;
+    std::fs::write(dir.join("moon.lock"), stale).unwrap();
+
+    let stderr = get_err_stderr(&dir, ["test", "--target", "wasm-gc", "--locked"]);
+    assert!(
+        stderr.contains("needs to be updated but `--locked` was passed"),
+        "unexpected stderr:\n{stderr}"
+    );
+    assert!(
+        stderr.contains("removed `alice/lib@0.1.0`"),
+        "unexpected stderr:\n{stderr}"
+    );
+    assert_eq!(read(dir.join("moon.lock")), stale);
+}
+
 #[test]
 fn test_pkg_source_in() {
     let dir = TestDir::new("moon_test/with_local_deps");
@@ -691,6 +720,58 @@ fn test_max_concurrent_tests() {
     );
 }
 
+#[test]
+fn test_test_timeout() {
+    let dir = TestDir::new("moon_test");
+
+    // `test-timeout` in moon.pkg.json kills the hanging test, and the tests
+    // after it still run in a relaunched executable.
+    let stdout = get_err_stdout(&dir, ["-C", "test_timeout", "test"]);
+    assert!(
+        stdout
+            .contains("(\"hangs\") failed: the test executable timed out after 1s and was killed"),
+        "expected the hanging test to fail:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Total tests: 3, passed: 2, failed: 1."),
+        "expected the remaining tests to run:\n{stdout}"
+    );
+
+    // `--timeout` overrides the package setting
+    let stdout = get_err_stdout(&dir, ["-C", "test_timeout", "test", "--timeout", "2"]);
+    assert!(
+        stdout.contains("the test executable timed out after 2s and was killed"),
+        "expected --timeout to override the package timeout:\n{stdout}"
+    );
+}
+
+#[test]
+fn test_rerun_failed() {
+    let dir = TestDir::new("moon_test");
+
+    let stdout = get_err_stdout(&dir, ["-C", "rerun_failed", "test"]);
+    assert!(
+        stdout.contains("Total tests: 3, passed: 2, failed: 1."),
+        "unexpected first run:\n{stdout}"
+    );
+
+    let stdout = get_err_stdout(&dir, ["-C", "rerun_failed", "test", "--rerun-failed"]);
+    assert!(
+        stdout.contains("(\"fails\") failed"),
+        "expected the failed test to rerun:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Total tests: 1, passed: 0, failed: 1."),
+        "expected only the failed test to rerun:\n{stdout}"
+    );
+
+    let stdout = get_err_stdout(&dir, ["-C", "rerun_failed", "test", "--failed-first"]);
+    assert!(
+        stdout.contains("Total tests: 3, passed: 2, failed: 1."),
+        "expected --failed-first to run every test:\n{stdout}"
+    );
+}
+
 #[test]
 fn test_doctest_without_bbtest_file() {
     let dir = TestDir::new("moon_test/doctest_without_bbtest");
@@ -113,11 +113,14 @@ fn moon_test_with_failure_json() {
     let dir = TestDir::new("test_with_failure_json");
 
     let output = get_err_stdout(&dir, ["test", "--test-failure-json"]);
+    let output = regex::Regex::new(r#""duration_ms":[0-9.]+"#)
+        .unwrap()
+        .replace_all(&output, r#""duration_ms":"[DURATION]""#);
     check(
         &output,
         // should keep in this format, it's used in ide test explorer
         expect![[r#"
-            {"package":"username/hello/lib1","filename":"hello.mbt","index":"0","test_name":"test_1","message":"src/lib1/hello.mbt:7:3-7:24@username/hello FAILED: test_1 failed"}
+            {"package":"username/hello/lib1","filename":"hello.mbt","index":"0","test_name":"test_1","message":"src/lib1/hello.mbt:7:3-7:24@username/hello FAILED: test_1 failed","duration_ms":"[DURATION]"}
             Total tests: 2, passed: 1, failed: 1.
         "#]],
     );
@@ -128,6 +128,7 @@ fn multi_target_tests_wait_for_every_backend_to_build() {
         .args(["test", "--target", "wasm,js", "--serial"])
         .env("MOON_TOOLCHAIN_ROOT", moonutil::toolchain::toolchain_root())
         .env("MOON_DEP_CACHE", "off")
+        .env("MOON_BUILD_CACHE", "off")
         .current_dir(&dir)
         .assert()
         .code(1);
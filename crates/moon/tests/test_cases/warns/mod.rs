@@ -460,3 +460,36 @@ fn test_deny_warn() {
         expect![""],
     );
 }
+
+#[test]
+fn test_check_baseline_on_warm_builds() {
+    let dir = TestDir::new("warns/deny_warn");
+    let baseline = "baseline.json";
+
+    // Build once, so that the baseline is written from an up-to-date project.
+    get_stderr(&dir, ["check"]);
+    get_stderr(&dir, ["check", "--write-baseline", baseline]);
+    let recorded = read(dir.join(baseline));
+    assert!(
+        recorded.contains("Unused variable 'a'"),
+        "warm builds must record every diagnostic:\n{recorded}"
+    );
+
+    // Nothing changed, so both the cold and the warm check pass.
+    get_stderr(&dir, ["check", "--baseline", baseline, "-d"]);
+    get_stderr(&dir, ["check", "--baseline", baseline, "-d"]);
+
+    let main = dir.join("main/main.mbt");
+    std::fs::write(
+        &main,
+        "fn main {\n  let a = 0\n  let fresh = 1\n  @lib.hello()\n}\n",
+    )
+    .unwrap();
+    for _ in 0..2 {
+        let stderr = get_err_stderr(&dir, ["check", "--baseline", baseline, "-d"]);
+        assert!(
+            stderr.contains("Unused variable 'fresh'"),
+            "new warnings must be reported on every run:\n{stderr}"
+        );
+    }
+}
@@ -341,6 +341,7 @@ mod tests {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }
@@ -135,6 +135,7 @@ pub fn build_synth_single_file_package(
         implement: None,
         overrides: None,
         max_concurrent_tests: None,
+        test_timeout: None,
         regex_backend: None,
         local_rules: None,
     };
@@ -250,7 +250,10 @@ impl ResolveConfig {
         workspace_env: WorkspaceEnv,
     ) -> Self {
         Self {
-            sync_flags: AutoSyncFlags { frozen },
+            sync_flags: AutoSyncFlags {
+                frozen,
+                locked: false,
+            },
             sync_output: SyncOutputOptions::default(),
             dependency_source_cache: CacheRoot::Disabled,
             no_std,
@@ -313,6 +313,23 @@ impl TargetLayout {
         dir
     }
 
+    /// Outcomes of the last test run for each backend/opt_level/run_mode,
+    /// used by `moon test --rerun-failed`.
+    pub fn last_test_results_of_build_target(&self, backend: TargetBackend) -> PathBuf {
+        let mut dir = self.run_mode_dir(backend);
+        dir.push(moonutil::constants::LAST_TEST_RESULTS_JSON);
+        dir
+    }
+
+    /// A named set of benchmark results saved by `moon bench --save-baseline`,
+    /// one per backend/opt_level.
+    pub fn bench_baseline_of_build_target(&self, backend: TargetBackend, name: &str) -> PathBuf {
+        let mut dir = self.run_mode_dir(backend);
+        dir.push(moonutil::constants::BENCH_BASELINES_DIR);
+        dir.push(format!("{name}.json"));
+        dir
+    }
+
     pub fn linked_core_of_build_target(
         &self,
         pkg_list: &DiscoverResult,
@@ -1251,6 +1268,7 @@ mod tests {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }
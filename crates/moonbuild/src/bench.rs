@@ -129,6 +129,7 @@ pub fn f() -> Unit {{
                 overrides: None,
                 sub_package: None,
                 max_concurrent_tests: None,
+                test_timeout: None,
                 regex_backend: None,
             };
             write_package_json_to_file(&pkg, &moon_pkg).unwrap();
@@ -232,6 +233,7 @@ pub fn write(config: &Config, base_dir: &Path) {
         overrides: None,
         sub_package: None,
         max_concurrent_tests: None,
+        test_timeout: None,
         regex_backend: None,
     };
 
@@ -20,7 +20,7 @@ use colored::Colorize;
 
 pub const BATCHBENCH: &str = "@BATCH_BENCH ";
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct BenchSummary {
     pub name: Option<String>,
     pub min: f64,
@@ -43,7 +43,51 @@ pub struct BatchBenchSummaries {
     pub summaries: Vec<BenchSummary>,
 }
 
-fn auto_select_unit(us: f64) -> String {
+/// How the timing of a benchmark changed from a baseline run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchChange {
+    /// Change of the mean, in percent of the baseline mean.
+    pub mean_change_pct: f64,
+    /// Whether the change is unlikely to be run-to-run noise.
+    pub significant: bool,
+}
+
+/// The t statistic above which a difference of means is taken as real,
+/// roughly a 95% confidence level for the usual number of runs.
+const SIGNIFICANT_T: f64 = 2.0;
+
+impl BenchSummary {
+    /// Compare this run against `baseline`.
+    ///
+    /// A change is significant when Welch's t statistic of the two means
+    /// exceeds [`SIGNIFICANT_T`] and the medians are further apart than the
+    /// larger median absolute deviation, so that a few outlier runs cannot
+    /// produce a change on their own.
+    pub fn change_from(&self, baseline: &BenchSummary) -> BenchChange {
+        let diff = self.mean - baseline.mean;
+        let std_err = (baseline.variance / baseline.runs.max(1) as f64
+            + self.variance / self.runs.max(1) as f64)
+            .sqrt();
+        // Zero variance on both sides makes any difference significant, and
+        // no difference at all gives NaN, which is not.
+        let t = diff / std_err;
+        let median_shift = (self.median - baseline.median).abs();
+        BenchChange {
+            mean_change_pct: diff / baseline.mean * 100.0,
+            significant: t.abs() > SIGNIFICANT_T
+                && median_shift > baseline.median_abs_dev.max(self.median_abs_dev),
+        }
+    }
+}
+
+/// Parse the summaries carried by a passed benchmark's message, or `None` if
+/// the message is not a batch benchmark result.
+pub fn parse_batch_bench_summaries(msg: &str) -> Option<BatchBenchSummaries> {
+    let msg = msg.strip_prefix(BATCHBENCH)?;
+    serde_json_lenient::from_str(msg).ok()
+}
+
+pub fn auto_select_unit(us: f64) -> String {
     if us < 1.0 {
         format!("{:>6.2} ns", us * 1e3)
     } else if us < 1e3 {
@@ -105,6 +149,56 @@ pub fn render_batch_bench_summary(msg: &str) {
 mod tests {
     use super::*;
 
+    fn summary(mean: f64, std_dev: f64, median: f64, median_abs_dev: f64) -> BenchSummary {
+        BenchSummary {
+            name: None,
+            min: mean - std_dev,
+            max: mean + std_dev,
+            mean,
+            median,
+            variance: std_dev * std_dev,
+            std_dev,
+            std_dev_pct: std_dev / mean * 100.0,
+            median_abs_dev,
+            median_abs_dev_pct: median_abs_dev / median * 100.0,
+            quartiles: (median - median_abs_dev, median, median + median_abs_dev),
+            iqr: 2.0 * median_abs_dev,
+            batch_size: 1000,
+            runs: 10,
+        }
+    }
+
+    #[test]
+    fn bench_change_significance() {
+        let baseline = summary(100.0, 2.0, 100.0, 1.0);
+
+        let slower = summary(120.0, 2.0, 120.0, 1.0).change_from(&baseline);
+        assert_eq!(slower.mean_change_pct, 20.0);
+        assert!(slower.significant);
+
+        let faster = summary(80.0, 2.0, 80.0, 1.0).change_from(&baseline);
+        assert_eq!(faster.mean_change_pct, -20.0);
+        assert!(faster.significant);
+
+        // Within the noise of either run.
+        let noisy = summary(110.0, 30.0, 110.0, 20.0).change_from(&baseline);
+        assert!(!noisy.significant);
+
+        // The means moved, but the medians did not: a few outliers.
+        let outliers = summary(110.0, 2.0, 100.5, 1.0).change_from(&baseline);
+        assert!(!outliers.significant);
+
+        assert!(!baseline.change_from(&baseline).significant);
+    }
+
+    #[test]
+    fn parse_batch_bench_summaries_requires_prefix() {
+        let msg = r#"{"summaries": []}"#;
+        assert!(parse_batch_bench_summaries(msg).is_none());
+        let parsed = parse_batch_bench_summaries(&format!("{BATCHBENCH}{msg}")).unwrap();
+        assert!(parsed.summaries.is_empty());
+    }
+
     #[test]
     fn auto_select_unit_nanoseconds() {
         assert_eq!(auto_select_unit(0.5), "500.00 ns");
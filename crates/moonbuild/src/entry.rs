@@ -250,6 +250,26 @@ impl<'a> CompactTestFormatter<'a> {
         write!(w, " {}", "ok".green().bold())
     }
 
+    /// A test that failed before passing on attempt `attempts`.
+    pub fn write_flaky<W: Write>(&self, w: &mut W, attempts: u32) -> std::io::Result<()> {
+        self.write_common_prefix(false, w)?;
+        write!(
+            w,
+            " {} (passed on attempt {attempts})",
+            "flaky".yellow().bold()
+        )
+    }
+
+    /// A line of the `--report-slowest` summary.
+    pub fn write_duration<W: Write>(
+        &self,
+        w: &mut W,
+        duration: std::time::Duration,
+    ) -> std::io::Result<()> {
+        self.write_common_prefix(false, w)?;
+        write!(w, " {:.3}s", duration.as_secs_f64())
+    }
+
     pub fn write_failure<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
         self.write_common_prefix(false, w)?;
         write!(w, " {}", "failed".red().bold())
@@ -38,6 +38,10 @@ pub struct TestStatistics {
     pub index: String,
     pub test_name: String,
     pub message: String,
+    /// Wall-clock time between the test's start and result events, when both
+    /// were observed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
 }
 
 impl std::fmt::Display for TestStatistics {
@@ -16,6 +16,8 @@
 //
 // For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
 
+use std::time::Instant;
+
 use tokio::io::{AsyncBufRead, AsyncWriteExt};
 
 pub struct SectionCapture<'a> {
@@ -24,6 +26,8 @@ pub struct SectionCapture<'a> {
     capture_buffer: String,
     include_delimiters: bool,
     state: SectionCaptureState,
+    /// Arrival time of each line in `capture_buffer`, when enabled.
+    line_times: Option<Vec<Instant>>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -47,6 +51,20 @@ impl<'a> SectionCapture<'a> {
             capture_buffer: String::new(),
             include_delimiters,
             state: SectionCaptureState::Outside,
+            line_times: None,
+        }
+    }
+
+    /// Also record when each captured line arrived. See [`Self::finish_timed`].
+    pub fn timed(mut self) -> Self {
+        self.line_times = Some(Vec::new());
+        self
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.capture_buffer.push_str(line);
+        if let Some(times) = &mut self.line_times {
+            times.push(Instant::now());
         }
     }
 
@@ -55,7 +73,7 @@ impl<'a> SectionCapture<'a> {
         if line.trim_end().ends_with(self.begin_delimiter) {
             self.state = SectionCaptureState::Capturing;
             if self.include_delimiters {
-                self.capture_buffer.push_str(line);
+                self.push_line(line);
             }
             let end_index = line.trim_end().len() - self.begin_delimiter.len();
             return Some(LineCaptured::Suffix(end_index));
@@ -65,7 +83,7 @@ impl<'a> SectionCapture<'a> {
         {
             self.state = SectionCaptureState::Complete;
             if self.include_delimiters {
-                self.capture_buffer.push_str(line);
+                self.push_line(line);
             }
             let start_index = self.end_delimiter.len();
             if line.trim().len() == self.end_delimiter.len() {
@@ -76,7 +94,7 @@ impl<'a> SectionCapture<'a> {
             }
         }
         if matches!(self.state, SectionCaptureState::Capturing) {
-            self.capture_buffer.push_str(line);
+            self.push_line(line);
             return Some(LineCaptured::All);
         }
         None
@@ -90,6 +108,27 @@ impl<'a> SectionCapture<'a> {
             None
         }
     }
+
+    /// Like [`Self::finish`], but also returns the arrival time of each line
+    /// of the captured section. Requires [`Self::timed`].
+    pub fn finish_timed(self) -> Option<(String, Vec<Instant>)> {
+        if matches!(self.state, SectionCaptureState::Complete) {
+            Some((self.capture_buffer, self.line_times.unwrap_or_default()))
+        } else {
+            None
+        }
+    }
+
+    /// Like [`Self::finish_timed`], but also returns a section whose end
+    /// delimiter was never seen, e.g. because the process was killed.
+    pub fn finish_timed_partial(self) -> Option<(String, Vec<Instant>)> {
+        match self.state {
+            SectionCaptureState::Outside => None,
+            SectionCaptureState::Capturing | SectionCaptureState::Complete => {
+                Some((self.capture_buffer, self.line_times.unwrap_or_default()))
+            }
+        }
+    }
 }
 
 /// Pipes the child stdout to stdout, with the ability to capture sections of the output.
@@ -275,3 +314,46 @@ fghij";
     assert_eq!(buf, out);
     assert!(capture.finish().is_none());
 }
+
+#[test]
+fn test_timed_capture_records_one_time_per_line() {
+    let out = "abcde
+---begin---
+one
+two
+---end---
+fghij";
+
+    let mut capture = SectionCapture::new("---begin---", "---end---", false).timed();
+    let mut captures = [&mut capture];
+    handle_stdout(
+        &mut std::io::BufReader::new(out.as_bytes()),
+        &mut captures,
+        |_| {},
+    )
+    .unwrap();
+    let (captured, times) = capture.finish_timed().unwrap();
+    assert_eq!(captured, "one\ntwo\n");
+    assert_eq!(times.len(), 2);
+    assert!(times[0] <= times[1]);
+}
+
+#[test]
+fn test_partial_capture_keeps_an_unterminated_section() {
+    let out = "---begin---
+one
+two
+";
+
+    let mut capture = SectionCapture::new("---begin---", "---end---", false).timed();
+    let mut captures = [&mut capture];
+    handle_stdout(
+        &mut std::io::BufReader::new(out.as_bytes()),
+        &mut captures,
+        |_| {},
+    )
+    .unwrap();
+    let (captured, times) = capture.finish_timed_partial().unwrap();
+    assert_eq!(captured, "one\ntwo\n");
+    assert_eq!(times.len(), 2);
+}
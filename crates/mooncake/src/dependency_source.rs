@@ -20,6 +20,7 @@
 
 mod global;
 mod project;
+mod vendor;
 
 use std::path::PathBuf;
 
@@ -30,9 +31,12 @@ use moonutil::{
     user_log::UserLog,
 };
 
-use crate::registry::RegistrySource;
+use crate::{registry::RegistrySource, vendor::Vendor};
 
-use self::{global::ImmutableDependencySource, project::ProjectDependencySource};
+use self::{
+    global::ImmutableDependencySource, project::ProjectDependencySource,
+    vendor::VendoredDependencySource,
+};
 
 pub(crate) trait DependencySource {
     /// Ensure that every resolved dependency has a source directory and return
@@ -71,6 +75,12 @@ pub(crate) fn select<'a>(
     )))
 }
 
+/// The dependency source for a project with vendored dependencies. It takes
+/// precedence over [`select`] and never contacts the registry.
+pub(crate) fn vendored(vendor: &Vendor) -> Box<dyn DependencySource + '_> {
+    Box::new(VendoredDependencySource::new(vendor))
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -0,0 +1,93 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Dependency sources vendored into the project by `moon vendor`.
+
+use anyhow::{Context, bail};
+use moonutil::{
+    resolution::{DirSyncResult, ModuleSourceKind, ResolvedEnv},
+    toolchain,
+    user_log::UserLog,
+};
+
+use crate::{
+    registry::RegistrySource,
+    vendor::{VENDORED_CHECKSUM_FILE, Vendor},
+};
+
+use super::DependencySource;
+
+/// Reads registry modules in place from the project's vendor directory.
+///
+/// This source never acquires anything: a module missing from the vendor
+/// directory, or one whose contents no longer carry the recorded checksum, is
+/// an error that asks for `moon vendor` to be run again.
+pub(super) struct VendoredDependencySource<'a> {
+    vendor: &'a Vendor,
+}
+
+impl<'a> VendoredDependencySource<'a> {
+    pub(super) fn new(vendor: &'a Vendor) -> Self {
+        Self { vendor }
+    }
+}
+
+impl DependencySource for VendoredDependencySource<'_> {
+    fn ensure(
+        &self,
+        _registry: &dyn RegistrySource,
+        resolved: &ResolvedEnv,
+        _frozen: bool,
+        _user_log: &UserLog,
+    ) -> anyhow::Result<DirSyncResult> {
+        let mut result = DirSyncResult::default();
+
+        for (id, module) in resolved.all_modules_and_id() {
+            let directory = match module.source() {
+                ModuleSourceKind::Registry if module.is_core() => toolchain::core(),
+                ModuleSourceKind::Registry => {
+                    let (directory, checksum) = self.vendor.source_dir(module)?;
+                    let marker = directory.join(VENDORED_CHECKSUM_FILE);
+                    let recorded = std::fs::read_to_string(&marker).with_context(|| {
+                        format!(
+                            "vendored source of `{module}` is missing at `{}`; run `moon vendor` to restore it",
+                            directory.display()
+                        )
+                    })?;
+                    if recorded != checksum {
+                        bail!(
+                            "vendored source of `{module}` at `{}` does not match `{}`; run `moon vendor` to restore it",
+                            directory.display(),
+                            self.vendor.dir().display()
+                        );
+                    }
+                    directory
+                }
+                ModuleSourceKind::Local(path)
+                | ModuleSourceKind::Stdlib(path)
+                | ModuleSourceKind::SingleFile(path) => path.clone(),
+                ModuleSourceKind::Git(url) => {
+                    bail!("Git dependencies are not supported: {url}")
+                }
+            };
+            result.insert(id, directory);
+        }
+
+        Ok(result)
+    }
+}
@@ -19,8 +19,10 @@
 #![warn(clippy::clone_on_ref_ptr)]
 
 pub(crate) mod dependency_source;
+pub(crate) mod lock_file;
 pub mod pkg;
 pub mod registry;
 pub(crate) mod resolver;
 mod update;
+pub(crate) mod vendor;
 pub(crate) mod zip_util;
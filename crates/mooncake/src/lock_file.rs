@@ -0,0 +1,417 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! The `moon.lock` record of a resolved module graph.
+//!
+//! Resolution is recomputed from the registry index on every invocation. The
+//! lock file records what it chose so that a moved index, a removed version, or
+//! a republished archive becomes visible instead of silently changing a build.
+//! Only modules fetched from outside the project are recorded: registry modules
+//! with their source archive checksum and git modules with their repository.
+//! Local path dependencies and the standard library are part of the checkout or
+//! the toolchain and are not locked.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::ErrorKind,
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+use moonutil::resolution::{ModuleId, ModuleSource, ModuleSourceKind, ResolvedEnv};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::registry::RegistrySource;
+
+const LOCK_FILE_VERSION: u32 = 1;
+
+/// How an invocation treats the project's lock file.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LockFileOptions<'a> {
+    pub(crate) path: &'a Path,
+    /// Fail instead of updating a lock file that differs from the resolution.
+    pub(crate) locked: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct LockFile {
+    version: u32,
+    #[serde(default)]
+    modules: Vec<LockedModule>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct LockedModule {
+    name: String,
+    version: Version,
+    #[serde(flatten)]
+    source: LockedSource,
+    /// Locked modules this module depends on, as `name@version`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "kebab-case")]
+enum LockedSource {
+    Registry {
+        /// SHA-256 of the published source archive, as recorded in the index.
+        checksum: String,
+    },
+    Git {
+        url: String,
+        /// Git dependencies are not resolved to a commit yet; this is filled
+        /// in once they are.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+    },
+}
+
+impl LockFile {
+    /// Record the externally sourced modules of a resolution.
+    pub(crate) fn from_resolved(
+        resolved: &ResolvedEnv,
+        registry: &dyn RegistrySource,
+    ) -> anyhow::Result<Self> {
+        let locked_ids = resolved
+            .all_modules_and_id()
+            .filter(|(_, module)| is_locked(module))
+            .map(|(id, _)| id)
+            .collect::<BTreeSet<ModuleId>>();
+
+        let mut modules = Vec::with_capacity(locked_ids.len());
+        for &id in &locked_ids {
+            let module = resolved.module_source(id);
+            let source = match module.source() {
+                ModuleSourceKind::Registry => LockedSource::Registry {
+                    checksum: registry
+                        .source_archive_checksum(module.name(), module.version())
+                        .with_context(|| format!("failed to find the checksum of `{module}`"))?,
+                },
+                ModuleSourceKind::Git(url) => LockedSource::Git {
+                    url: url.clone(),
+                    commit: None,
+                },
+                _ => unreachable!("only registry and git modules are locked"),
+            };
+            let dependencies = resolved
+                .deps(id)
+                .filter(|dep| locked_ids.contains(dep))
+                .map(|dep| {
+                    let dep = resolved.module_source(dep);
+                    format!("{}@{}", dep.name(), dep.version())
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            modules.push(LockedModule {
+                name: module.name().to_string(),
+                version: module.version().clone(),
+                source,
+                dependencies,
+            });
+        }
+        modules.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        Ok(Self {
+            version: LOCK_FILE_VERSION,
+            modules,
+        })
+    }
+
+    fn render(&self) -> String {
+        let mut rendered =
+            serde_json::to_string_pretty(self).expect("lock files are always serializable");
+        rendered.push('\n');
+        rendered
+    }
+
+    /// Write this lock file if it differs from the one on disk, or with
+    /// `locked`, fail with a summary of the differences.
+    ///
+    /// A project without external dependencies does not get a new lock file.
+    pub(crate) fn update_or_verify(&self, options: LockFileOptions) -> anyhow::Result<()> {
+        let path = options.path;
+        let existing = match std::fs::read_to_string(path) {
+            Ok(existing) => Some(existing),
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to read `{}`", path.display()));
+            }
+        };
+        let rendered = self.render();
+        match &existing {
+            Some(existing) if *existing == rendered => return Ok(()),
+            None if self.modules.is_empty() => return Ok(()),
+            _ => {}
+        }
+
+        if options.locked {
+            let Some(existing) = existing else {
+                bail!(
+                    "`{}` does not exist but `--locked` was passed; run the command without `--locked` to create it",
+                    path.display()
+                );
+            };
+            let changes = match serde_json_lenient::from_str::<LockFile>(&existing) {
+                Ok(existing) => describe_changes(&existing, self),
+                Err(error) => format!("  the existing lock file cannot be parsed: {error}"),
+            };
+            bail!(
+                "`{}` needs to be updated but `--locked` was passed:\n{changes}",
+                path.display()
+            );
+        }
+
+        std::fs::write(path, rendered)
+            .with_context(|| format!("failed to write `{}`", path.display()))
+    }
+}
+
+fn is_locked(module: &ModuleSource) -> bool {
+    match module.source() {
+        ModuleSourceKind::Registry => !module.is_core(),
+        ModuleSourceKind::Git(_) => true,
+        ModuleSourceKind::Local(_)
+        | ModuleSourceKind::Stdlib(_)
+        | ModuleSourceKind::SingleFile(_) => false,
+    }
+}
+
+/// One line per module that was added, removed, or changed.
+fn describe_changes(old: &LockFile, new: &LockFile) -> String {
+    fn by_name(lock: &LockFile) -> BTreeMap<&str, Vec<&LockedModule>> {
+        let mut modules = BTreeMap::<_, Vec<_>>::new();
+        for module in &lock.modules {
+            modules
+                .entry(module.name.as_str())
+                .or_default()
+                .push(module);
+        }
+        modules
+    }
+
+    let old = by_name(old);
+    let new = by_name(new);
+    let names = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    let mut lines = Vec::new();
+    for name in names {
+        let before = old.get(name).map(Vec::as_slice).unwrap_or_default();
+        let after = new.get(name).map(Vec::as_slice).unwrap_or_default();
+        if before == after {
+            continue;
+        }
+        let versions = |modules: &[&LockedModule]| {
+            modules
+                .iter()
+                .map(|module| module.version.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let line = match (before.is_empty(), after.is_empty()) {
+            (true, _) => format!("  added `{name}@{}`", versions(after)),
+            (_, true) => format!("  removed `{name}@{}`", versions(before)),
+            _ if versions(before) != versions(after) => {
+                format!(
+                    "  changed `{name}` from {} to {}",
+                    versions(before),
+                    versions(after)
+                )
+            }
+            _ if before
+                .iter()
+                .zip(after)
+                .any(|(before, after)| before.source != after.source) =>
+            {
+                format!(
+                    "  changed the source or checksum of `{name}@{}`",
+                    versions(after)
+                )
+            }
+            _ => format!("  changed the dependencies of `{name}@{}`", versions(after)),
+        };
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use moonutil::{
+        manifest::MoonMod,
+        resolution::{DependencyEdge, ModuleName},
+        user_log::UserLog,
+    };
+
+    use super::*;
+
+    struct ChecksumRegistry(&'static str);
+
+    impl RegistrySource for ChecksumRegistry {
+        fn acquire_source_to(
+            &self,
+            _name: &ModuleName,
+            _version: &Version,
+            _expected_checksum: &str,
+            _to: &Path,
+            _user_log: &UserLog,
+        ) -> anyhow::Result<()> {
+            unreachable!("lock files never acquire sources")
+        }
+
+        fn source_archive_checksum(
+            &self,
+            name: &ModuleName,
+            version: &Version,
+        ) -> anyhow::Result<String> {
+            Ok(format!("{}:{name}@{version}", self.0))
+        }
+    }
+
+    fn resolved(lib_version: &str) -> ResolvedEnv {
+        let module = |name: &str| {
+            Arc::new(MoonMod {
+                name: name.to_string(),
+                ..Default::default()
+            })
+        };
+        let mut env = ResolvedEnv::new();
+        let root = env.add_module(
+            ModuleSource::local_path("me/app".into(), "/work/app".into(), Version::new(0, 1, 0)),
+            module("me/app"),
+        );
+        let lib = env.add_module(
+            ModuleSource::from_version("alice/lib".into(), lib_version.parse().unwrap()),
+            module("alice/lib"),
+        );
+        let util = env.add_module(
+            ModuleSource::from_version("bob/util".into(), Version::new(1, 0, 0)),
+            module("bob/util"),
+        );
+        let core = env.add_module(
+            ModuleSource::new_full(
+                "moonbitlang/core".into(),
+                Version::new(0, 1, 0),
+                ModuleSourceKind::Stdlib("/toolchain/core".into()),
+            ),
+            module("moonbitlang/core"),
+        );
+        env.register_stdlib(core);
+        env.add_dependency(root, lib, &"alice/lib".parse::<DependencyEdge>().unwrap());
+        env.add_dependency(lib, util, &"bob/util".parse::<DependencyEdge>().unwrap());
+        env
+    }
+
+    #[test]
+    fn records_external_modules_deterministically() {
+        let lock = LockFile::from_resolved(&resolved("0.2.0"), &ChecksumRegistry("sha")).unwrap();
+        expect_test::expect![[r#"
+            {
+              "version": 1,
+              "modules": [
+                {
+                  "name": "alice/lib",
+                  "version": "0.2.0",
+                  "source": "registry",
+                  "checksum": "sha:alice/lib@0.2.0",
+                  "dependencies": [
+                    "bob/util@1.0.0"
+                  ]
+                },
+                {
+                  "name": "bob/util",
+                  "version": "1.0.0",
+                  "source": "registry",
+                  "checksum": "sha:bob/util@1.0.0"
+                }
+              ]
+            }
+        "#]]
+        .assert_eq(&lock.render());
+        assert_eq!(
+            serde_json::from_str::<LockFile>(&lock.render()).unwrap(),
+            lock
+        );
+    }
+
+    #[test]
+    fn locked_mode_reports_changes_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("moon.lock");
+        let options = LockFileOptions {
+            path: &path,
+            locked: true,
+        };
+
+        let old = LockFile::from_resolved(&resolved("0.2.0"), &ChecksumRegistry("sha")).unwrap();
+        let error = old.update_or_verify(options).unwrap_err();
+        assert!(error.to_string().contains("does not exist"));
+
+        old.update_or_verify(LockFileOptions {
+            locked: false,
+            ..options
+        })
+        .unwrap();
+        old.update_or_verify(options).unwrap();
+
+        let moved = LockFile::from_resolved(&resolved("0.2.1"), &ChecksumRegistry("sha")).unwrap();
+        let error = moved.update_or_verify(options).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("changed `alice/lib` from 0.2.0 to 0.2.1"),
+            "{error}"
+        );
+
+        let republished =
+            LockFile::from_resolved(&resolved("0.2.0"), &ChecksumRegistry("other")).unwrap();
+        let error = republished.update_or_verify(options).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("changed the source or checksum of `alice/lib@0.2.0`"),
+            "{error}"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), old.render());
+    }
+
+    #[test]
+    fn projects_without_external_dependencies_get_no_lock_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("moon.lock");
+        let (env, _) = ResolvedEnv::only_one_module(
+            ModuleSource::local_path("me/app".into(), "/work/app".into(), Version::new(0, 1, 0)),
+            MoonMod {
+                name: "me/app".to_string(),
+                ..Default::default()
+            },
+        );
+        let lock = LockFile::from_resolved(&env, &ChecksumRegistry("sha")).unwrap();
+
+        lock.update_or_verify(LockFileOptions {
+            path: &path,
+            locked: true,
+        })
+        .unwrap();
+        assert!(!path.exists());
+    }
+}
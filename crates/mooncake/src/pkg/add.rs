@@ -18,7 +18,7 @@
 
 use anyhow::bail;
 use indexmap::IndexMap;
-use moonutil::constants::{MOON_MOD, MOONBITLANG_CORE};
+use moonutil::constants::{MOON_LOCK_FILE, MOON_MOD, MOONBITLANG_CORE};
 use moonutil::dependency::{BinaryDependencyInfo, SourceDependencyInfo};
 use moonutil::manifest::{
     convert_module_to_mod_json, read_module_desc_file_in_dir, write_module_json_to_file,
@@ -31,6 +31,7 @@ use semver::Version;
 use std::path::Path;
 use std::sync::Arc;
 
+use crate::lock_file::LockFileOptions;
 use crate::pkg::{install::install_impl, roots_for_selected_module, sync::SyncOutputOptions};
 use crate::registry::Registry;
 
@@ -188,6 +189,10 @@ pub fn add(
         false,
         true,
         &moonutil::cache::CacheRoot::Disabled,
+        Some(LockFileOptions {
+            path: &dirs.source_dir.join(MOON_LOCK_FILE),
+            locked: false,
+        }),
     )?;
 
     if module_dir.join(MOON_MOD).exists() {
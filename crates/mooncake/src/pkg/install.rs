@@ -17,8 +17,11 @@
 // For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
 
 use crate::{
-    dependency_source, registry,
+    dependency_source,
+    lock_file::{LockFile, LockFileOptions},
+    registry::{self, Registry, RegistrySource},
     resolver::{ResolveConfig, resolve_with_default_env_and_resolver},
+    vendor::Vendor,
 };
 
 use super::sync::SyncOutputOptions;
@@ -89,6 +92,7 @@ pub struct InstallSubcommand {
     pub tag: Option<String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn install_impl(
     dirs: &PackageDirs,
     roots: ResolvedRootModules,
@@ -97,6 +101,7 @@ pub(crate) fn install_impl(
     dont_sync: bool,
     no_std: bool,
     source_cache: &CacheRoot,
+    lock_file: Option<LockFileOptions>,
 ) -> anyhow::Result<(ResolvedEnv, DirSyncResult)> {
     for (_, module) in roots.iter() {
         let module = module.module_info();
@@ -119,26 +124,52 @@ pub(crate) fn install_impl(
         anyhow::bail!("workspaces that include `moonbitlang/core` are not supported yet");
     }
 
-    let registry = registry::default_registry();
+    // A vendored project resolves against, and reads sources from, its vendor
+    // directory alone.
+    let vendor = Vendor::load(&dirs.source_dir)?;
+    let client;
+    let (registry, sources): (&dyn Registry, &dyn RegistrySource) = match &vendor {
+        Some(vendor) => (vendor, vendor),
+        None => {
+            client = registry::default_registry();
+            (&client, &client)
+        }
+    };
     let resolve_config = ResolveConfig {
-        registry: &registry,
+        registry,
         inject_std: !includes_core && !no_std,
     };
 
-    let res = resolve_with_default_env_and_resolver(&resolve_config, roots, user_log)?;
+    let res =
+        resolve_with_default_env_and_resolver(&resolve_config, roots, user_log).map_err(|e| {
+            let e = anyhow::Error::from(e);
+            match &vendor {
+                Some(vendor) => e.context(format!(
+                    "failed to resolve dependencies from `{}`; run `moon vendor` after changing dependencies",
+                    vendor.dir().display()
+                )),
+                None => e,
+            }
+        })?;
+    if let Some(options) = lock_file {
+        LockFile::from_resolved(&res, sources)?.update_or_verify(options)?;
+    }
     let dependency_user_log = if output_options.quiet {
         user_log.with_level(log::LevelFilter::Error)
     } else {
         user_log.clone()
     };
-    let dependency_source = dependency_source::select(
-        &dirs.mooncakes_dir,
-        source_cache,
-        &res,
-        output_options.child_output,
-    )?;
+    let dependency_source = match &vendor {
+        Some(vendor) => dependency_source::vendored(vendor),
+        None => dependency_source::select(
+            &dirs.mooncakes_dir,
+            source_cache,
+            &res,
+            output_options.child_output,
+        )?,
+    };
     let dependency_paths =
-        dependency_source.ensure(&registry, &res, dont_sync, &dependency_user_log)?;
+        dependency_source.ensure(sources, &res, dont_sync, &dependency_user_log)?;
 
     let managed_child = ManagedChildRunner::new(output_options.child_output, &dependency_user_log);
     install_bin_deps(
@@ -0,0 +1,841 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Audit the licenses of the resolved dependency graph and export it as an
+//! SBOM.
+//!
+//! Registry modules only carry their dependencies in the index, so their
+//! metadata is read from the synchronized sources.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use moonutil::{
+    cli_support::AutoSyncFlags,
+    manifest::{MoonMod, read_module_desc_file_in_dir},
+    project::{PackageDirs, WorkspaceEnv},
+    resolution::{DirSyncResult, ModuleId, ModuleSourceKind, ResolvedEnv},
+    user_log::UserLog,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::pkg::sync::{SyncOutputOptions, auto_sync};
+
+/// Report the license of every dependency and generate an SBOM
+#[derive(Debug, clap::Parser)]
+pub struct LicensesSubcommand {
+    /// License policy file listing allowed and denied SPDX license
+    /// identifiers. Dependencies that violate it make the command fail
+    ///
+    /// The policy is a JSON object with the keys `allow` and `deny` (lists of
+    /// SPDX identifiers), `allow-missing` (accept modules without a license)
+    /// and `exceptions` (module names exempt from the policy).
+    #[clap(long, value_name = "FILE")]
+    pub policy: Option<PathBuf>,
+
+    /// Emit a software bill of materials in the given format instead of the
+    /// license report
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    pub sbom: Option<SbomFormat>,
+
+    /// Write the report or SBOM to a file instead of stdout
+    #[clap(long, short, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Format of the license report
+    #[clap(long, value_enum, default_value_t)]
+    pub format: LicensesFormat,
+
+    #[clap(flatten)]
+    pub auto_sync_flags: AutoSyncFlags,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LicensesFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    Spdx,
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+}
+
+/// Allowed and denied licenses, read from a JSON policy file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LicensePolicy {
+    /// SPDX identifiers that may be used. An empty list allows every license
+    /// that is not denied.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// SPDX identifiers that must not be used.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Accept modules that do not declare a license.
+    #[serde(default)]
+    pub allow_missing: bool,
+    /// Modules that are exempt from the policy.
+    #[serde(default)]
+    pub exceptions: Vec<String>,
+}
+
+impl LicensePolicy {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read license policy `{}`", path.display()))?;
+        serde_json_lenient::from_str(&content)
+            .with_context(|| format!("failed to parse license policy `{}`", path.display()))
+    }
+
+    fn permits(&self, id: &str) -> bool {
+        !self.deny.iter().any(|denied| denied == id)
+            && (self.allow.is_empty() || self.allow.iter().any(|allowed| allowed == id))
+    }
+
+    /// Check one module against the policy, returning the reason it violates
+    /// it, if any.
+    fn check(&self, entry: &LicenseEntry) -> Option<String> {
+        if self.exceptions.contains(&entry.module) {
+            return None;
+        }
+        let Some(license) = &entry.license else {
+            return (!self.allow_missing).then(|| "no license is declared".to_string());
+        };
+        match LicenseExpr::parse(license) {
+            Ok(expr) if expr.satisfies(&|id| self.permits(id)) => None,
+            Ok(_) => Some(format!(
+                "license `{license}` is not permitted by the policy"
+            )),
+            Err(e) => Some(format!("{e:#}")),
+        }
+    }
+}
+
+/// A parsed SPDX license expression. `WITH` exceptions are kept as part of
+/// the license identifier they apply to.
+#[derive(Debug, PartialEq, Eq)]
+enum LicenseExpr {
+    License(String),
+    And(Vec<LicenseExpr>),
+    Or(Vec<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    fn parse(input: &str) -> anyhow::Result<Self> {
+        let spaced = input.replace('(', " ( ").replace(')', " ) ");
+        let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+        let mut pos = 0;
+        let expr = Self::parse_or(&tokens, &mut pos)
+            .with_context(|| format!("invalid license expression `{input}`"))?;
+        if pos != tokens.len() {
+            bail!(
+                "invalid license expression `{input}`: unexpected `{}`",
+                tokens[pos]
+            );
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(tokens: &[&str], pos: &mut usize) -> anyhow::Result<Self> {
+        let mut terms = vec![Self::parse_and(tokens, pos)?];
+        while tokens
+            .get(*pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case("OR"))
+        {
+            *pos += 1;
+            terms.push(Self::parse_and(tokens, pos)?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            LicenseExpr::Or(terms)
+        })
+    }
+
+    fn parse_and(tokens: &[&str], pos: &mut usize) -> anyhow::Result<Self> {
+        let mut terms = vec![Self::parse_atom(tokens, pos)?];
+        while tokens
+            .get(*pos)
+            .is_some_and(|t| t.eq_ignore_ascii_case("AND"))
+        {
+            *pos += 1;
+            terms.push(Self::parse_atom(tokens, pos)?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            LicenseExpr::And(terms)
+        })
+    }
+
+    fn parse_atom(tokens: &[&str], pos: &mut usize) -> anyhow::Result<Self> {
+        match tokens.get(*pos).copied() {
+            Some("(") => {
+                *pos += 1;
+                let expr = Self::parse_or(tokens, pos)?;
+                if tokens.get(*pos).copied() != Some(")") {
+                    bail!("missing `)`");
+                }
+                *pos += 1;
+                Ok(expr)
+            }
+            Some(token)
+                if token != ")"
+                    && !["AND", "OR", "WITH"]
+                        .iter()
+                        .any(|op| token.eq_ignore_ascii_case(op)) =>
+            {
+                *pos += 1;
+                let mut id = token.to_string();
+                if tokens
+                    .get(*pos)
+                    .is_some_and(|t| t.eq_ignore_ascii_case("WITH"))
+                {
+                    let exception = tokens.get(*pos + 1).context("missing license exception")?;
+                    id = format!("{id} WITH {exception}");
+                    *pos += 2;
+                }
+                Ok(LicenseExpr::License(id))
+            }
+            Some(token) => bail!("unexpected `{token}`"),
+            None => bail!("expected a license identifier"),
+        }
+    }
+
+    fn satisfies(&self, permits: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            LicenseExpr::License(id) => permits(id),
+            LicenseExpr::And(terms) => terms.iter().all(|term| term.satisfies(permits)),
+            LicenseExpr::Or(terms) => terms.iter().any(|term| term.satisfies(permits)),
+        }
+    }
+}
+
+/// The license metadata of one resolved module.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct LicenseEntry {
+    #[serde(skip)]
+    id: ModuleId,
+    module: String,
+    version: Version,
+    source: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    workspace_member: bool,
+    license: Option<String>,
+    repository: Option<String>,
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    violation: Option<String>,
+}
+
+/// Collect the metadata of every resolved module. Registry modules are read
+/// from their synchronized source directory, since the resolver only knows
+/// their dependencies.
+fn collect_entries(
+    resolved: &ResolvedEnv,
+    paths: &DirSyncResult,
+) -> anyhow::Result<Vec<LicenseEntry>> {
+    let mut entries = Vec::new();
+    for (id, source) in resolved.all_modules_and_id() {
+        let loaded;
+        let module: &MoonMod = match (source.source(), paths.get(id)) {
+            (ModuleSourceKind::Registry, Some(path)) => {
+                loaded = read_module_desc_file_in_dir(path)
+                    .with_context(|| format!("failed to read the manifest of `{source}`"))?;
+                &loaded
+            }
+            _ => resolved.module_info(id),
+        };
+        entries.push(LicenseEntry {
+            id,
+            module: source.name().to_string(),
+            version: source.version().clone(),
+            source: source.source().to_string(),
+            workspace_member: resolved.input_module_ids().contains(&id),
+            license: module.license.clone().filter(|l| !l.trim().is_empty()),
+            repository: module.repository.clone().filter(|r| !r.trim().is_empty()),
+            description: module.description.clone().filter(|d| !d.trim().is_empty()),
+            violation: None,
+        });
+    }
+    entries.sort_by(|lhs, rhs| {
+        (!lhs.workspace_member, &lhs.module, &lhs.version).cmp(&(
+            !rhs.workspace_member,
+            &rhs.module,
+            &rhs.version,
+        ))
+    });
+    Ok(entries)
+}
+
+/// Record policy violations on the entries and return how many there are.
+/// Workspace members are the subject of the audit, not dependencies, and are
+/// not checked.
+fn apply_policy(entries: &mut [LicenseEntry], policy: &LicensePolicy) -> usize {
+    let mut violations = 0;
+    for entry in entries.iter_mut().filter(|entry| !entry.workspace_member) {
+        entry.violation = policy.check(entry);
+        violations += usize::from(entry.violation.is_some());
+    }
+    violations
+}
+
+fn render_table(entries: &[LicenseEntry]) -> String {
+    let header = ["Module", "Version", "License", "Repository"];
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.module.clone(),
+                entry.version.to_string(),
+                entry.license.clone().unwrap_or_else(|| "-".to_string()),
+                entry.repository.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    };
+    push_row(&header);
+    for row in &rows {
+        push_row(&row.each_ref().map(String::as_str));
+    }
+    out
+}
+
+/// Stable SPDX element IDs for the modules, in the order of `entries`. IDs
+/// may only contain letters, digits, `.` and `-`, so modules whose names only
+/// differ in other characters get a numeric suffix to stay unique.
+fn spdx_ids(entries: &[LicenseEntry]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .map(|entry| {
+            let sanitized = format!("{}-{}", entry.module, entry.version)
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                        c
+                    } else {
+                        '-'
+                    }
+                })
+                .collect::<String>();
+            let base = format!("SPDXRef-Package-{sanitized}");
+            let mut id = base.clone();
+            let mut n = 1;
+            while !seen.insert(id.clone()) {
+                n += 1;
+                id = format!("{base}-{n}");
+            }
+            id
+        })
+        .collect()
+}
+
+fn bom_ref(entry: &LicenseEntry) -> String {
+    format!("{}@{}", entry.module, entry.version)
+}
+
+/// The dependencies of every module, as indices into `entries`.
+fn dependency_edges(resolved: &ResolvedEnv, entries: &[LicenseEntry]) -> Vec<Vec<usize>> {
+    let index = entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| (entry.id, idx))
+        .collect::<BTreeMap<_, _>>();
+    entries
+        .iter()
+        .map(|entry| {
+            let mut deps = resolved
+                .deps(entry.id)
+                .filter_map(|dep| index.get(&dep).copied())
+                .collect::<Vec<_>>();
+            deps.sort_unstable();
+            deps.dedup();
+            deps
+        })
+        .collect()
+}
+
+fn document_name(entries: &[LicenseEntry]) -> String {
+    entries
+        .iter()
+        .filter(|entry| entry.workspace_member)
+        .map(bom_ref)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn spdx_document(
+    resolved: &ResolvedEnv,
+    entries: &[LicenseEntry],
+    created: &str,
+) -> serde_json::Value {
+    let name = document_name(entries);
+    let mut hasher = Sha256::new();
+    hasher.update(created);
+    for entry in entries {
+        hasher.update(bom_ref(entry));
+    }
+    let digest = format!("{:x}", hasher.finalize());
+    let ids = spdx_ids(entries);
+
+    let packages = entries
+        .iter()
+        .zip(&ids)
+        .map(|(entry, id)| {
+            let mut package = json!({
+                "name": entry.module,
+                "SPDXID": id,
+                "versionInfo": entry.version.to_string(),
+                "downloadLocation": entry.repository.as_deref().unwrap_or("NOASSERTION"),
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": entry.license.as_deref().unwrap_or("NOASSERTION"),
+                "copyrightText": "NOASSERTION",
+            });
+            if let Some(description) = &entry.description {
+                package["summary"] = json!(description);
+            }
+            package
+        })
+        .collect::<Vec<_>>();
+
+    let mut relationships = entries
+        .iter()
+        .zip(&ids)
+        .filter(|(entry, _)| entry.workspace_member)
+        .map(|(_, id)| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": id,
+            })
+        })
+        .collect::<Vec<_>>();
+    for (id, deps) in ids.iter().zip(dependency_edges(resolved, entries)) {
+        for dep in deps {
+            relationships.push(json!({
+                "spdxElementId": id,
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": ids[dep],
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://mooncakes.io/spdxdocs/{}", &digest[..32]),
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: moon-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+fn cyclonedx_component(entry: &LicenseEntry, kind: &str) -> serde_json::Value {
+    let (group, name) = entry
+        .module
+        .split_once('/')
+        .unwrap_or(("", entry.module.as_str()));
+    let mut component = json!({
+        "type": kind,
+        "bom-ref": bom_ref(entry),
+        "group": group,
+        "name": name,
+        "version": entry.version.to_string(),
+    });
+    if let Some(description) = &entry.description {
+        component["description"] = json!(description);
+    }
+    if let Some(license) = &entry.license {
+        component["licenses"] = match LicenseExpr::parse(license) {
+            Ok(LicenseExpr::License(id)) if !id.contains(' ') => {
+                json!([{ "license": { "id": id } }])
+            }
+            Ok(_) => json!([{ "expression": license }]),
+            Err(_) => json!([{ "license": { "name": license } }]),
+        };
+    }
+    if let Some(repository) = &entry.repository {
+        component["externalReferences"] = json!([{ "type": "vcs", "url": repository }]);
+    }
+    component
+}
+
+fn cyclonedx_document(
+    resolved: &ResolvedEnv,
+    entries: &[LicenseEntry],
+    created: &str,
+) -> serde_json::Value {
+    let mut members = entries.iter().filter(|entry| entry.workspace_member);
+    let mut metadata = json!({
+        "timestamp": created,
+        "tools": {
+            "components": [{
+                "type": "application",
+                "name": "moon",
+                "version": env!("CARGO_PKG_VERSION"),
+            }],
+        },
+    });
+    // CycloneDX describes a single subject; other workspace members are
+    // listed as components.
+    let subject = members.next();
+    if let Some(subject) = subject {
+        metadata["component"] = cyclonedx_component(subject, "application");
+    }
+
+    let components = entries
+        .iter()
+        .filter(|entry| subject.is_none_or(|subject| subject.id != entry.id))
+        .map(|entry| cyclonedx_component(entry, "library"))
+        .collect::<Vec<_>>();
+    let dependencies = entries
+        .iter()
+        .zip(dependency_edges(resolved, entries))
+        .map(|(entry, deps)| {
+            json!({
+                "ref": bom_ref(entry),
+                "dependsOn": deps.into_iter().map(|dep| bom_ref(&entries[dep])).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": metadata,
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+pub fn licenses(
+    dirs: &PackageDirs,
+    cmd: &LicensesSubcommand,
+    workspace_env: WorkspaceEnv,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let policy = cmd.policy.as_deref().map(LicensePolicy::load).transpose()?;
+    let (resolved, paths, _) = auto_sync(
+        dirs,
+        &cmd.auto_sync_flags,
+        SyncOutputOptions {
+            quiet: true,
+            ..Default::default()
+        },
+        user_log,
+        false,
+        workspace_env,
+        false,
+    )?;
+    let mut entries = collect_entries(&resolved, &paths)?;
+    let violations = match &policy {
+        Some(policy) => apply_policy(&mut entries, policy),
+        None => 0,
+    };
+
+    let output = match cmd.sbom {
+        Some(format) => {
+            let created = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+            let document = match format {
+                SbomFormat::Spdx => spdx_document(&resolved, &entries, &created),
+                SbomFormat::Cyclonedx => cyclonedx_document(&resolved, &entries, &created),
+            };
+            serde_json::to_string_pretty(&document)? + "\n"
+        }
+        None => match cmd.format {
+            LicensesFormat::Table => render_table(&entries),
+            LicensesFormat::Json => serde_json::to_string_pretty(&entries)? + "\n",
+        },
+    };
+    match &cmd.output {
+        Some(path) => std::fs::write(path, output)
+            .with_context(|| format!("failed to write `{}`", path.display()))?,
+        None => print!("{output}"),
+    }
+
+    if violations > 0 {
+        for entry in &entries {
+            if let Some(violation) = &entry.violation {
+                user_log.error(format!("{}: {violation}", bom_ref(entry)));
+            }
+        }
+        bail!(
+            "{violations} {} the license policy",
+            if violations == 1 {
+                "dependency violates"
+            } else {
+                "dependencies violate"
+            }
+        );
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use expect_test::expect;
+    use moonutil::resolution::{
+        DependencyEdge, DependencyKind, ModuleSource, ResolvedModule, ResolvedRootModules,
+    };
+
+    use super::*;
+
+    fn entry(module: &str, license: Option<&str>) -> LicenseEntry {
+        LicenseEntry {
+            id: ModuleId::default(),
+            module: module.to_string(),
+            version: Version::new(0, 1, 0),
+            source: ModuleSourceKind::Registry.to_string(),
+            workspace_member: false,
+            license: license.map(str::to_string),
+            repository: None,
+            description: None,
+            violation: None,
+        }
+    }
+
+    #[test]
+    fn license_expressions_are_checked_against_the_policy() {
+        let policy = LicensePolicy {
+            allow: vec![
+                "MIT".into(),
+                "Apache-2.0".into(),
+                "Apache-2.0 WITH LLVM-exception".into(),
+            ],
+            deny: vec!["GPL-3.0-only".into()],
+            allow_missing: false,
+            exceptions: vec!["carol/vendored".into()],
+        };
+        let check = |module: &str, license: Option<&str>| policy.check(&entry(module, license));
+
+        assert_eq!(check("a/b", Some("MIT")), None);
+        assert_eq!(check("a/b", Some("MIT OR GPL-3.0-only")), None);
+        assert_eq!(
+            check("a/b", Some("(MIT AND Apache-2.0) OR BSD-3-Clause")),
+            None
+        );
+        assert_eq!(check("a/b", Some("Apache-2.0 WITH LLVM-exception")), None);
+        assert_eq!(check("carol/vendored", Some("GPL-3.0-only")), None);
+        assert_eq!(
+            check("a/b", Some("MIT AND GPL-3.0-only")).as_deref(),
+            Some("license `MIT AND GPL-3.0-only` is not permitted by the policy")
+        );
+        assert_eq!(
+            check("a/b", Some("BSD-3-Clause")).as_deref(),
+            Some("license `BSD-3-Clause` is not permitted by the policy")
+        );
+        assert_eq!(
+            check("a/b", None).as_deref(),
+            Some("no license is declared")
+        );
+        assert_eq!(
+            check("a/b", Some("(MIT OR")).as_deref(),
+            Some("invalid license expression `(MIT OR`: expected a license identifier")
+        );
+    }
+
+    fn module(name: &str, license: Option<&str>) -> Arc<MoonMod> {
+        Arc::new(MoonMod {
+            name: name.to_string(),
+            version: Some(Version::new(0, 1, 0)),
+            license: license.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    /// A workspace member depending on one registry module whose manifest is
+    /// only available from its synchronized sources.
+    fn resolved_graph(sources: &Path) -> (ResolvedEnv, DirSyncResult) {
+        let app = module("me/app", Some("Apache-2.0"));
+        let mut roots = ResolvedRootModules::with_key();
+        roots.insert(ResolvedModule::new(
+            ModuleSource::from_local_module(&app, Path::new("/work/app")),
+            app,
+        ));
+        let mut resolved = ResolvedEnv::from_root_modules(roots);
+        let root = resolved.input_module_ids()[0];
+        let lib = resolved.add_module(
+            ModuleSource::new_full(
+                "alice/lib".into(),
+                Version::new(0, 1, 0),
+                ModuleSourceKind::Registry,
+            ),
+            module("alice/lib", None),
+        );
+        resolved.add_dependency(
+            root,
+            lib,
+            &DependencyEdge {
+                name: "alice/lib".into(),
+                kind: DependencyKind::Regular,
+            },
+        );
+
+        std::fs::write(
+            sources.join("moon.mod.json"),
+            r#"{"name": "alice/lib", "version": "0.1.0", "license": "MIT OR Apache-2.0", "repository": "https://github.com/alice/lib"}"#,
+        )
+        .unwrap();
+        let mut paths = DirSyncResult::new();
+        paths.insert(lib, sources.to_path_buf());
+        (resolved, paths)
+    }
+
+    #[test]
+    fn spdx_ids_stay_unique_after_sanitizing() {
+        let entries = [
+            entry("alice/foo_bar", None),
+            entry("alice/foo-bar", None),
+            entry("alice/foo.bar", None),
+            entry("alice/foo+bar", None),
+        ];
+        assert_eq!(
+            spdx_ids(&entries),
+            [
+                "SPDXRef-Package-alice-foo-bar-0.1.0",
+                "SPDXRef-Package-alice-foo-bar-0.1.0-2",
+                "SPDXRef-Package-alice-foo.bar-0.1.0",
+                "SPDXRef-Package-alice-foo-bar-0.1.0-3",
+            ]
+        );
+    }
+
+    #[test]
+    fn registry_metadata_is_read_from_synchronized_sources() {
+        let sources = tempfile::TempDir::new().unwrap();
+        let (resolved, paths) = resolved_graph(sources.path());
+        let mut entries = collect_entries(&resolved, &paths).unwrap();
+        let policy = LicensePolicy {
+            allow: vec!["Apache-2.0".into()],
+            ..Default::default()
+        };
+        assert_eq!(apply_policy(&mut entries, &policy), 0);
+
+        expect![[r#"
+            Module     Version  License            Repository
+            me/app     0.1.0    Apache-2.0         -
+            alice/lib  0.1.0    MIT OR Apache-2.0  https://github.com/alice/lib
+        "#]]
+        .assert_eq(&render_table(&entries));
+    }
+
+    #[test]
+    fn sbom_documents_list_modules_and_dependencies() {
+        let sources = tempfile::TempDir::new().unwrap();
+        let (resolved, paths) = resolved_graph(sources.path());
+        let entries = collect_entries(&resolved, &paths).unwrap();
+        let created = "2026-01-01T00:00:00Z";
+
+        let spdx = spdx_document(&resolved, &entries, created);
+        assert_eq!(spdx["name"], "me/app@0.1.0");
+        assert_eq!(
+            spdx["packages"][1],
+            json!({
+                "name": "alice/lib",
+                "SPDXID": "SPDXRef-Package-alice-lib-0.1.0",
+                "versionInfo": "0.1.0",
+                "downloadLocation": "https://github.com/alice/lib",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "MIT OR Apache-2.0",
+                "copyrightText": "NOASSERTION",
+            })
+        );
+        assert_eq!(
+            spdx["relationships"],
+            json!([
+                {
+                    "spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES",
+                    "relatedSpdxElement": "SPDXRef-Package-me-app-0.1.0",
+                },
+                {
+                    "spdxElementId": "SPDXRef-Package-me-app-0.1.0",
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": "SPDXRef-Package-alice-lib-0.1.0",
+                },
+            ])
+        );
+
+        let cyclonedx = cyclonedx_document(&resolved, &entries, created);
+        assert_eq!(
+            cyclonedx["metadata"]["component"]["bom-ref"],
+            "me/app@0.1.0"
+        );
+        assert_eq!(
+            cyclonedx["metadata"]["component"]["licenses"],
+            json!([{ "license": { "id": "Apache-2.0" } }])
+        );
+        assert_eq!(
+            cyclonedx["components"],
+            json!([{
+                "type": "library",
+                "bom-ref": "alice/lib@0.1.0",
+                "group": "alice",
+                "name": "lib",
+                "version": "0.1.0",
+                "licenses": [{ "expression": "MIT OR Apache-2.0" }],
+                "externalReferences": [{ "type": "vcs", "url": "https://github.com/alice/lib" }],
+            }])
+        );
+        assert_eq!(
+            cyclonedx["dependencies"],
+            json!([
+                { "ref": "me/app@0.1.0", "dependsOn": ["alice/lib@0.1.0"] },
+                { "ref": "alice/lib@0.1.0", "dependsOn": [] },
+            ])
+        );
+    }
+}
@@ -27,9 +27,13 @@ use moonutil::{
 pub mod add;
 pub mod install;
 pub mod legacy_postadd;
+pub mod licenses;
+pub mod outdated;
 pub mod remove;
 pub mod sync;
 pub mod tree;
+pub mod upgrade_deps;
+pub mod vendor;
 mod work;
 
 pub use work::{init_workspace, sync_workspace, use_workspace};
@@ -0,0 +1,316 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Report resolved registry dependencies that have newer published versions.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use moonutil::manifest::read_module_desc_file_in_dir;
+use moonutil::project::ProjectManifest;
+use moonutil::resolution::{ModuleSourceKind, ResolvedEnv};
+use moonutil::user_log::UserLog;
+use semver::Version;
+use serde::Serialize;
+
+use crate::pkg::roots_for_selected_module;
+use crate::registry::Registry;
+use crate::resolver::mvs::same_mvs_compatibility_set;
+use crate::resolver::{ResolveConfig, resolve_with_default_env_and_resolver};
+
+/// List dependencies with newer versions in the registry
+#[derive(Debug, clap::Parser)]
+pub struct OutdatedSubcommand {
+    /// Output format
+    #[clap(long, value_enum, default_value_t)]
+    pub format: OutdatedFormat,
+
+    /// Do not update the registry index before checking
+    #[clap(long)]
+    pub no_update: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutdatedFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum DependencyDepth {
+    Direct,
+    Transitive,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct OutdatedEntry {
+    module: String,
+    depth: DependencyDepth,
+    current: Version,
+    /// The newest version MVS would consider compatible with `current`.
+    compatible: Version,
+    latest: Version,
+}
+
+impl OutdatedEntry {
+    fn is_outdated(&self) -> bool {
+        self.latest > self.current
+    }
+}
+
+/// Compare every resolved registry module with the versions in the registry.
+///
+/// Pre-releases are only offered to modules that already use one.
+fn collect_outdated(
+    resolved: &ResolvedEnv,
+    registry: &dyn Registry,
+) -> anyhow::Result<Vec<OutdatedEntry>> {
+    let roots = resolved
+        .input_module_ids()
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+    let direct = roots
+        .iter()
+        .flat_map(|&root| resolved.deps(root))
+        .collect::<HashSet<_>>();
+
+    let mut entries = Vec::new();
+    for (id, module) in resolved.all_modules_and_id() {
+        if roots.contains(&id)
+            || module.is_core()
+            || !matches!(module.source(), ModuleSourceKind::Registry)
+        {
+            continue;
+        }
+        let current = module.version();
+        let versions = registry
+            .all_versions_of(module.name())
+            .with_context(|| format!("failed to look up `{}` in the registry", module.name()))?;
+        let candidates = versions
+            .keys()
+            .filter(|version| version.pre.is_empty() || !current.pre.is_empty())
+            .chain(std::iter::once(current));
+        let compatible = candidates
+            .clone()
+            .filter(|version| same_mvs_compatibility_set(current, version))
+            .max()
+            .unwrap_or(current);
+        let latest = candidates.max().unwrap_or(current);
+        entries.push(OutdatedEntry {
+            module: module.name().to_string(),
+            depth: if direct.contains(&id) {
+                DependencyDepth::Direct
+            } else {
+                DependencyDepth::Transitive
+            },
+            current: current.clone(),
+            compatible: compatible.clone(),
+            latest: latest.clone(),
+        });
+    }
+    entries.sort_by(|lhs, rhs| {
+        (lhs.depth, &lhs.module, &lhs.current).cmp(&(rhs.depth, &rhs.module, &rhs.current))
+    });
+    Ok(entries)
+}
+
+fn render_table(entries: &[OutdatedEntry]) -> String {
+    if entries.is_empty() {
+        return "No registry dependencies.\n".to_string();
+    }
+
+    let header = ["Module", "Current", "Compatible", "Latest", "Dependency"];
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let marker = |version: &Version| {
+                if *version > entry.current {
+                    version.to_string()
+                } else {
+                    "-".to_string()
+                }
+            };
+            [
+                entry.module.clone(),
+                entry.current.to_string(),
+                marker(&entry.compatible),
+                marker(&entry.latest),
+                match entry.depth {
+                    DependencyDepth::Direct => "direct".to_string(),
+                    DependencyDepth::Transitive => "transitive".to_string(),
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    let mut push_row = |cells: &[&str]| {
+        let line = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    };
+    push_row(&header);
+    for row in &rows {
+        push_row(&row.each_ref().map(String::as_str));
+    }
+
+    let outdated = entries.iter().filter(|entry| entry.is_outdated()).count();
+    if outdated == 0 {
+        out.push_str("\nAll dependencies are up to date.\n");
+    }
+    out
+}
+
+pub fn outdated(
+    registry: &dyn Registry,
+    module_dir: &Path,
+    project_manifest: &ProjectManifest,
+    format: OutdatedFormat,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let module = Arc::new(read_module_desc_file_in_dir(module_dir)?);
+    let roots = roots_for_selected_module(module_dir, module, project_manifest)?;
+    let resolve_cfg = ResolveConfig {
+        registry,
+        inject_std: false,
+    };
+    let resolved = resolve_with_default_env_and_resolver(&resolve_cfg, roots, user_log)?;
+    let entries = collect_outdated(&resolved, registry)?;
+
+    match format {
+        OutdatedFormat::Table => print!("{}", render_table(&entries)),
+        OutdatedFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::mock::MockRegistry;
+    use expect_test::expect;
+    use moonutil::manifest::MoonMod;
+    use moonutil::resolution::{ModuleSource, ResolvedModule};
+
+    fn resolve(registry: &MockRegistry, deps: &[(&str, &str)]) -> ResolvedEnv {
+        let module = Arc::new(crate::registry::mock::create_mock_module(
+            "me/app",
+            "0.1.0",
+            deps.iter().copied(),
+        ));
+        let (roots, _) = ResolvedModule::only_one_module(
+            ModuleSource::from_local_module(&module, Path::new("/work/app")),
+            Arc::clone(&module) as Arc<MoonMod>,
+        );
+        let config = ResolveConfig {
+            registry,
+            inject_std: false,
+        };
+        resolve_with_default_env_and_resolver(
+            &config,
+            roots,
+            &UserLog::new(log::LevelFilter::Error),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reports_compatible_and_latest_versions() {
+        let mut registry = MockRegistry::new();
+        registry
+            .add_module_full("alice/lib", "0.2.0", [("bob/util", "1.0.0")])
+            .add_module_full("alice/lib", "0.3.1", [("bob/util", "1.0.0")])
+            .add_module_full("alice/lib", "2.0.0", [("bob/util", "1.0.0")])
+            .add_module_full("alice/lib", "3.0.0-beta.1", [])
+            .add_module_full("bob/util", "1.0.0", [])
+            .add_module_full("bob/util", "1.4.0", [])
+            .add_module_full("carol/done", "0.1.0", []);
+        let resolved = resolve(
+            &registry,
+            &[("alice/lib", "0.2.0"), ("carol/done", "0.1.0")],
+        );
+
+        let entries = collect_outdated(&resolved, &registry).unwrap();
+        expect![[r#"
+            Module      Current  Compatible  Latest  Dependency
+            alice/lib   0.2.0    0.3.1       2.0.0   direct
+            carol/done  0.1.0    -           -       direct
+            bob/util    1.0.0    1.4.0       1.4.0   transitive
+        "#]]
+        .assert_eq(&render_table(&entries));
+        expect![[r#"
+            [
+              {
+                "module": "alice/lib",
+                "depth": "direct",
+                "current": "0.2.0",
+                "compatible": "0.3.1",
+                "latest": "2.0.0"
+              },
+              {
+                "module": "carol/done",
+                "depth": "direct",
+                "current": "0.1.0",
+                "compatible": "0.1.0",
+                "latest": "0.1.0"
+              },
+              {
+                "module": "bob/util",
+                "depth": "transitive",
+                "current": "1.0.0",
+                "compatible": "1.4.0",
+                "latest": "1.4.0"
+              }
+            ]"#]]
+        .assert_eq(&serde_json::to_string_pretty(&entries).unwrap());
+    }
+
+    #[test]
+    fn up_to_date_dependencies_are_reported_as_such() {
+        let mut registry = MockRegistry::new();
+        registry.add_module_full("alice/lib", "0.2.0", []);
+        let resolved = resolve(&registry, &[("alice/lib", "0.2.0")]);
+
+        expect![[r#"
+            Module     Current  Compatible  Latest  Dependency
+            alice/lib  0.2.0    -           -       direct
+
+            All dependencies are up to date.
+        "#]]
+        .assert_eq(&render_table(
+            &collect_outdated(&resolved, &registry).unwrap(),
+        ));
+    }
+}
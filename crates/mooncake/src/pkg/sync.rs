@@ -26,6 +26,7 @@ use moonutil::{
     cache::CacheRoot,
     child_process::ChildOutputMode,
     cli_support::AutoSyncFlags,
+    constants::MOON_LOCK_FILE,
     front_matter::MbtMdHeader,
     manifest::{MoonMod, read_module_desc_file_in_dir},
     project::{MoonWork, PackageDirs, ProjectManifest, WorkspaceEnv, WorkspaceLayout},
@@ -34,6 +35,8 @@ use moonutil::{
 };
 use semver::Version;
 
+use crate::lock_file::LockFileOptions;
+
 /// User-visible output policy while synchronizing dependencies.
 ///
 /// Dependency progress and Moon-managed setup children are independent: a
@@ -90,6 +93,10 @@ pub fn auto_sync(
         cli.dont_sync(),
         no_std,
         &CacheRoot::Disabled,
+        Some(LockFileOptions {
+            path: &dirs.source_dir.join(MOON_LOCK_FILE),
+            locked: cli.locked,
+        }),
     )?;
     log::debug!("Dir sync result: {:?}", sync_result);
     Ok((resolved_env, sync_result, None))
@@ -123,6 +130,10 @@ fn resolve_workspace_sync(
         cli.dont_sync(),
         no_std,
         &CacheRoot::Disabled,
+        Some(LockFileOptions {
+            path: &dirs.source_dir.join(MOON_LOCK_FILE),
+            locked: cli.locked,
+        }),
     )?;
     log::debug!("Dir sync result: {:?}", sync_result);
     Ok((
@@ -178,6 +189,7 @@ pub fn auto_sync_for_single_mbt_md(
         dont_sync,
         false,
         &CacheRoot::Disabled,
+        None,
     )?;
     log::debug!("Dir sync result: {:?}", dir_sync_result);
     Ok((resolved_env, dir_sync_result, m))
@@ -215,6 +227,7 @@ pub fn auto_sync_for_single_file_rr(
         sync_flags.dont_sync(),
         false,
         source_cache,
+        None,
     )?;
 
     log::debug!("Dir sync result: {:?}", dir_sync_result);
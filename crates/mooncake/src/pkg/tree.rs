@@ -16,101 +16,263 @@
 //
 // For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
 
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::Path;
 use std::sync::Arc;
 
-use anyhow::Context;
+use anyhow::{Context, bail};
 use moonutil::manifest::read_module_desc_file_in_dir;
 use moonutil::project::ProjectManifest;
-use moonutil::resolution::{ModuleId, ModuleName, ResolvedEnv};
+use moonutil::resolution::{
+    DependencyEdge, DependencyKind, ModuleId, ModuleName, ModuleSource, ModuleSourceKind,
+    ResolvedEnv,
+};
 use moonutil::user_log::UserLog;
+use semver::Version;
+use serde::Serialize;
 
 use crate::pkg::roots_for_selected_module;
 use crate::registry;
-use crate::resolver::{ResolveConfig, resolve_with_default_env_and_resolver};
+use crate::resolver::explain::{Explanation, RequirementTrace, render_explanation};
+use crate::resolver::mvs::same_mvs_compatibility_set;
+use crate::resolver::{ResolveConfig, ResolverErrors, resolve_with_trace};
 
 /// Display the dependency tree
-#[derive(Debug, clap::Parser)]
-pub struct TreeSubcommand {}
+#[derive(Debug, Default, clap::Parser)]
+pub struct TreeSubcommand {
+    /// Maximum depth of dependencies to display
+    #[clap(long, value_name = "N")]
+    pub depth: Option<usize>,
 
-fn render_tree(resolved: &ResolvedEnv, root: ModuleId) -> String {
-    let workspace_members = if resolved.input_module_ids().len() > 1 {
-        resolved
-            .input_module_ids()
-            .iter()
-            .copied()
-            .collect::<HashSet<_>>()
-    } else {
-        HashSet::new()
-    };
+    /// Show the modules that pull in the given module instead of the
+    /// dependencies of the selected module
+    #[clap(long, value_name = "MODULE", conflicts_with = "duplicates")]
+    pub invert: Option<String>,
 
-    let mut out = String::new();
-    out.push_str(&format_module_label(resolved, root, &workspace_members));
-    out.push_str(":\n");
+    /// Explain why each version of the given module was selected, listing
+    /// every requirement on it and the chain of modules that declared it
+    #[clap(long, value_name = "MODULE", conflicts_with_all = ["invert", "duplicates"])]
+    pub why: Option<String>,
+
+    /// If dependency resolution fails, explain the requirements behind each
+    /// module that could not be resolved or was selected at conflicting
+    /// versions
+    #[clap(long)]
+    pub explain: bool,
+
+    /// List modules that are requested at more than one version, and the
+    /// version that was selected for them
+    #[clap(long)]
+    pub duplicates: bool,
+
+    /// Kinds of dependency edges to display. `bin` also includes the
+    /// `bin-deps` of the root modules
+    #[clap(long, value_enum, default_value_t)]
+    pub edges: TreeEdges,
+
+    /// Output format
+    #[clap(long, value_enum, default_value_t)]
+    pub format: TreeFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TreeEdges {
+    #[default]
+    Normal,
+    Bin,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum TreeFormat {
+    #[default]
+    Text,
+    Json,
+}
 
-    let mut stack = HashSet::new();
-    stack.insert(root);
-    let direct_dep_count =
-        render_tree_edges(resolved, root, "", &workspace_members, &mut stack, &mut out);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Dependencies,
+    Dependents,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct TreeOptions {
+    depth: Option<usize>,
+    include_bin: bool,
+}
 
-    if direct_dep_count == 0 {
-        out.push_str("  (no dependencies)\n");
+impl TreeOptions {
+    fn includes(&self, edge: &DependencyEdge) -> bool {
+        self.include_bin || edge.kind != DependencyKind::Binary
     }
+}
 
-    out
+/// A module in the rendered tree, along with the edge that led to it.
+#[derive(Debug, Serialize)]
+struct TreeNode {
+    name: String,
+    version: Version,
+    source: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    workspace_member: bool,
+    /// The key of the dependency edge that led to this node.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependency: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    binary: bool,
+    /// Set when this node was already on the path from the root; its
+    /// children are not expanded again.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    cycle: bool,
+    children: Vec<TreeNode>,
+
+    #[serde(skip)]
+    label: String,
+    #[serde(skip)]
+    edge: Option<DependencyEdge>,
 }
 
-fn render_tree_edges(
-    resolved: &ResolvedEnv,
-    source: ModuleId,
-    indent: &str,
-    workspace_members: &HashSet<ModuleId>,
-    stack: &mut HashSet<ModuleId>,
-    out: &mut String,
-) -> usize {
-    let mut deps = resolved.deps_keyed(source).collect::<Vec<_>>();
-    deps.sort_by(|(lhs_id, lhs_edge), (rhs_id, rhs_edge)| {
-        lhs_edge.name.cmp(&rhs_edge.name).then_with(|| {
-            resolved
-                .module_source(*lhs_id)
-                .cmp(resolved.module_source(*rhs_id))
-        })
-    });
+struct TreeBuilder<'a> {
+    resolved: &'a ResolvedEnv,
+    workspace_members: HashSet<ModuleId>,
+    options: TreeOptions,
+    direction: Direction,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn new(resolved: &'a ResolvedEnv, options: TreeOptions, direction: Direction) -> Self {
+        let workspace_members = if resolved.input_module_ids().len() > 1 {
+            resolved.input_module_ids().iter().copied().collect()
+        } else {
+            HashSet::new()
+        };
+        Self {
+            resolved,
+            workspace_members,
+            options,
+            direction,
+        }
+    }
+
+    fn build(&self, root: ModuleId) -> TreeNode {
+        let mut stack = HashSet::new();
+        self.build_node(root, None, 0, &mut stack)
+    }
+
+    fn build_node(
+        &self,
+        id: ModuleId,
+        edge: Option<&DependencyEdge>,
+        depth: usize,
+        stack: &mut HashSet<ModuleId>,
+    ) -> TreeNode {
+        let source = self.resolved.module_source(id);
+        let mut node = TreeNode {
+            name: source.name().to_string(),
+            version: source.version().clone(),
+            source: source.source().to_string(),
+            workspace_member: self.workspace_members.contains(&id),
+            dependency: edge.map(|e| e.name.to_string()),
+            binary: edge.is_some_and(|e| e.kind == DependencyKind::Binary),
+            cycle: false,
+            children: Vec::new(),
+            label: format_module_label(self.resolved, id, &self.workspace_members),
+            edge: edge.cloned(),
+        };
+
+        if stack.contains(&id) {
+            node.cycle = true;
+            return node;
+        }
+        if self.options.depth.is_some_and(|max| depth >= max) {
+            return node;
+        }
+
+        stack.insert(id);
+        for (next, next_edge) in self.neighbors(id) {
+            let child = self.build_node(next, Some(&next_edge), depth + 1, stack);
+            node.children.push(child);
+        }
+        stack.remove(&id);
+        node
+    }
+
+    fn neighbors(&self, id: ModuleId) -> Vec<(ModuleId, DependencyEdge)> {
+        let direction = match self.direction {
+            Direction::Dependencies => petgraph::Direction::Outgoing,
+            Direction::Dependents => petgraph::Direction::Incoming,
+        };
+        let mut neighbors = self
+            .resolved
+            .graph()
+            .edges_directed(id, direction)
+            .filter(|(_, _, edge)| self.options.includes(edge))
+            .map(|(source, target, edge)| {
+                let next = if source == id { target } else { source };
+                (next, edge.clone())
+            })
+            .collect::<Vec<_>>();
+        neighbors.sort_by(
+            |(lhs_id, lhs_edge), (rhs_id, rhs_edge)| match self.direction {
+                Direction::Dependencies => lhs_edge.name.cmp(&rhs_edge.name).then_with(|| {
+                    self.resolved
+                        .module_source(*lhs_id)
+                        .cmp(self.resolved.module_source(*rhs_id))
+                }),
+                Direction::Dependents => self
+                    .resolved
+                    .module_source(*lhs_id)
+                    .cmp(self.resolved.module_source(*rhs_id))
+                    .then_with(|| lhs_edge.cmp(rhs_edge)),
+            },
+        );
+        neighbors
+    }
+}
+
+fn render_text(root: &TreeNode, direction: Direction) -> String {
+    let mut out = String::new();
+    out.push_str(&root.label);
+    out.push_str(":\n");
+    render_text_children(root, direction, "", &mut out);
 
-    for (idx, (dep_id, dep_edge)) in deps.iter().enumerate() {
-        let is_last = idx + 1 == deps.len();
+    if root.children.is_empty() {
+        match direction {
+            Direction::Dependencies => out.push_str("  (no dependencies)\n"),
+            Direction::Dependents => out.push_str("  (no dependents)\n"),
+        }
+    }
+    out
+}
+
+fn render_text_children(node: &TreeNode, direction: Direction, indent: &str, out: &mut String) {
+    for (idx, child) in node.children.iter().enumerate() {
+        let is_last = idx + 1 == node.children.len();
         let branch = if is_last { "└─" } else { "├─" };
         out.push_str(indent);
         out.push_str(branch);
         out.push(' ');
-        out.push_str(&format!(
-            "{} -> {}",
-            dep_edge.name,
-            format_module_label(resolved, *dep_id, workspace_members)
-        ));
+        match (direction, &child.edge) {
+            (Direction::Dependencies, Some(edge)) => {
+                out.push_str(&format!("{} -> {}", edge, child.label));
+            }
+            _ => {
+                out.push_str(&child.label);
+                if child.binary {
+                    out.push_str(" (binary)");
+                }
+            }
+        }
         out.push('\n');
 
         let next_indent = format!("{indent}{}", if is_last { "   " } else { "│  " });
-        if stack.contains(dep_id) {
+        if child.cycle {
             out.push_str(&next_indent);
             out.push_str("└─ (cycle)\n");
             continue;
         }
-
-        stack.insert(*dep_id);
-        render_tree_edges(
-            resolved,
-            *dep_id,
-            &next_indent,
-            workspace_members,
-            stack,
-            out,
-        );
-        stack.remove(dep_id);
+        render_text_children(child, direction, &next_indent, out);
     }
-
-    deps.len()
 }
 
 fn format_module_label(
@@ -125,9 +287,244 @@ fn format_module_label(
     label
 }
 
+/// Parse a `name` or `name@version` module query.
+fn parse_module_query(query: &str) -> anyhow::Result<(ModuleName, Option<Version>)> {
+    match query.split_once('@') {
+        Some((name, version)) => {
+            let version = version
+                .parse::<Version>()
+                .with_context(|| format!("invalid version in `{query}`"))?;
+            Ok((name.into(), Some(version)))
+        }
+        None => Ok((query.into(), None)),
+    }
+}
+
+/// Find the modules matching `query`, which is either a module name or
+/// `name@version`.
+fn find_modules(resolved: &ResolvedEnv, query: &str) -> anyhow::Result<Vec<ModuleId>> {
+    let (name, version) = parse_module_query(query)?;
+
+    let mut ids = resolved
+        .all_modules_and_id()
+        .filter(|(_, source)| {
+            source.name() == &name && version.as_ref().is_none_or(|v| source.version() == v)
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    ids.sort_by(|lhs, rhs| {
+        resolved
+            .module_source(*lhs)
+            .cmp(resolved.module_source(*rhs))
+    });
+
+    if ids.is_empty() {
+        bail!("module `{query}` is not in the dependency graph");
+    }
+    Ok(ids)
+}
+
+/// One version requirement on a module, as written in a dependant's manifest.
+#[derive(Debug, Serialize)]
+struct VersionRequest {
+    by: String,
+    version: Version,
+    /// The version that satisfies this requirement after resolution.
+    selected: Version,
+}
+
+#[derive(Debug, Serialize)]
+struct SelectedVersion {
+    version: Version,
+    source: String,
+    reason: String,
+}
+
+/// A module that is requested at more than one version.
+#[derive(Debug, Serialize)]
+struct DuplicateEntry {
+    module: String,
+    requests: Vec<VersionRequest>,
+    selected: Vec<SelectedVersion>,
+}
+
+fn collect_duplicates(resolved: &ResolvedEnv, options: TreeOptions) -> Vec<DuplicateEntry> {
+    let roots = resolved
+        .input_module_ids()
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+
+    let mut requests = BTreeMap::<ModuleName, Vec<(VersionRequest, ModuleId)>>::new();
+    for (id, source) in resolved.all_modules_and_id() {
+        let module = resolved.module_info(id);
+        let regular = module
+            .deps
+            .iter()
+            .map(|(name, req)| (name, req, DependencyKind::Regular));
+        let binary = (options.include_bin && roots.contains(&id)).then(|| {
+            module
+                .bin_deps
+                .iter()
+                .flatten()
+                .map(|(name, req)| (name, &req.common, DependencyKind::Binary))
+        });
+
+        for (name, req, kind) in regular.chain(binary.into_iter().flatten()) {
+            let Some(version) = req.version() else {
+                continue;
+            };
+            let edge = DependencyEdge {
+                name: name.as_str().into(),
+                kind,
+            };
+            let Some(selected) = resolved.dep_with_key(id, &edge) else {
+                continue;
+            };
+            requests.entry(edge.name).or_default().push((
+                VersionRequest {
+                    by: source.to_string(),
+                    version: version.clone(),
+                    selected: resolved.module_source(selected).version().clone(),
+                },
+                selected,
+            ));
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (name, mut requests) in requests {
+        let distinct = requests
+            .iter()
+            .map(|(req, _)| &req.version)
+            .collect::<BTreeSet<_>>();
+        if distinct.len() < 2 {
+            continue;
+        }
+        requests.sort_by(|(lhs, _), (rhs, _)| {
+            lhs.version
+                .cmp(&rhs.version)
+                .then_with(|| lhs.by.cmp(&rhs.by))
+        });
+
+        let selected_ids = requests
+            .iter()
+            .map(|(_, id)| *id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|id| resolved.module_source(id))
+            .collect::<BTreeSet<_>>();
+        let selected = selected_ids
+            .into_iter()
+            .map(|source| SelectedVersion {
+                version: source.version().clone(),
+                source: source.source().to_string(),
+                reason: selection_reason(resolved, source, &requests),
+            })
+            .collect();
+
+        entries.push(DuplicateEntry {
+            module: name.to_string(),
+            requests: requests.into_iter().map(|(req, _)| req).collect(),
+            selected,
+        });
+    }
+    entries
+}
+
+fn selection_reason(
+    resolved: &ResolvedEnv,
+    selected: &ModuleSource,
+    requests: &[(VersionRequest, ModuleId)],
+) -> String {
+    let is_workspace_member = resolved.input_module_ids().len() > 1
+        && resolved
+            .input_module_ids()
+            .iter()
+            .any(|id| resolved.module_source(*id) == selected);
+    match selected.source() {
+        _ if is_workspace_member => {
+            "workspace members take precedence over registry versions".to_string()
+        }
+        ModuleSourceKind::Local(_) => "pinned by a local path dependency".to_string(),
+        ModuleSourceKind::Git(_) => "pinned by a git dependency".to_string(),
+        _ => {
+            let highest = requests
+                .iter()
+                .map(|(req, _)| &req.version)
+                .filter(|v| same_mvs_compatibility_set(v, selected.version()))
+                .max();
+            if highest == Some(selected.version()) {
+                "highest version required within its compatibility set".to_string()
+            } else {
+                "lowest available version satisfying every requirement in its compatibility set"
+                    .to_string()
+            }
+        }
+    }
+}
+
+fn render_duplicates(entries: &[DuplicateEntry]) -> String {
+    if entries.is_empty() {
+        return "No modules are requested at more than one version.\n".to_string();
+    }
+
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&entry.module);
+        out.push_str(":\n");
+        for (idx, req) in entry.requests.iter().enumerate() {
+            let branch = if idx + 1 == entry.requests.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            out.push_str(&format!(
+                "{branch} {} requires {} (uses {})\n",
+                req.by, req.version, req.selected
+            ));
+        }
+        for selected in &entry.selected {
+            out.push_str(&format!("  selected {}", selected.version));
+            if selected.source != ModuleSourceKind::Registry.to_string() {
+                out.push_str(&format!(" ({})", selected.source));
+            }
+            out.push_str(&format!(": {}\n", selected.reason));
+        }
+    }
+    out
+}
+
+/// Explain every module named by a resolution error.
+fn explain_errors(trace: &RequirementTrace, errors: &ResolverErrors) -> Vec<Explanation> {
+    let mut seen = HashSet::new();
+    errors
+        .0
+        .iter()
+        .filter_map(|error| error.module())
+        .filter(|module| seen.insert(*module))
+        .filter_map(|module| trace.explain(module, None))
+        .collect()
+}
+
+fn print_explanations(explanations: &[Explanation], format: TreeFormat) -> anyhow::Result<()> {
+    match format {
+        TreeFormat::Text => {
+            let rendered = explanations
+                .iter()
+                .map(render_explanation)
+                .collect::<Vec<_>>();
+            print!("{}", rendered.join("\n"));
+        }
+        TreeFormat::Json => println!("{}", serde_json::to_string_pretty(explanations)?),
+    }
+    Ok(())
+}
+
 pub fn tree(
     module_dir: &Path,
     project_manifest: &ProjectManifest,
+    cmd: &TreeSubcommand,
     user_log: &UserLog,
 ) -> anyhow::Result<i32> {
     let module = Arc::new(read_module_desc_file_in_dir(module_dir)?);
@@ -137,18 +534,73 @@ pub fn tree(
         registry: &registry,
         inject_std: false,
     };
-    let resolved = resolve_with_default_env_and_resolver(&resolve_cfg, roots, user_log)?;
+    let (resolved, trace) = resolve_with_trace(&resolve_cfg, roots, user_log);
+    let resolved = match resolved {
+        Ok(resolved) => resolved,
+        Err(errors) => {
+            if cmd.explain || cmd.why.is_some() {
+                print_explanations(&explain_errors(&trace, &errors), cmd.format)?;
+            }
+            return Err(errors.into());
+        }
+    };
 
-    let module_name: ModuleName = module.name.as_str().into();
-    let selected_root = resolved
-        .input_module_ids()
-        .iter()
-        .copied()
-        .find(|id| resolved.module_source(*id).name() == &module_name)
-        .or_else(|| resolved.input_module_ids().first().copied())
-        .context("resolved dependency graph has no root modules")?;
+    if let Some(query) = &cmd.why {
+        let (name, version) = parse_module_query(query)?;
+        let explanation = trace
+            .explain(&name, version.as_ref())
+            .filter(|explanation| {
+                !explanation.selected.is_empty() || !explanation.unresolved.is_empty()
+            })
+            .with_context(|| format!("module `{query}` is not required by any module"))?;
+        print_explanations(&[explanation], cmd.format)?;
+        return Ok(0);
+    }
 
-    print!("{}", render_tree(&resolved, selected_root));
+    let options = TreeOptions {
+        depth: cmd.depth,
+        include_bin: cmd.edges == TreeEdges::Bin,
+    };
+
+    if cmd.duplicates {
+        let entries = collect_duplicates(&resolved, options);
+        match cmd.format {
+            TreeFormat::Text => print!("{}", render_duplicates(&entries)),
+            TreeFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        }
+        return Ok(0);
+    }
+
+    let (roots, direction) = match &cmd.invert {
+        Some(query) => (find_modules(&resolved, query)?, Direction::Dependents),
+        None => {
+            let module_name: ModuleName = module.name.as_str().into();
+            let selected_root = resolved
+                .input_module_ids()
+                .iter()
+                .copied()
+                .find(|id| resolved.module_source(*id).name() == &module_name)
+                .or_else(|| resolved.input_module_ids().first().copied())
+                .context("resolved dependency graph has no root modules")?;
+            (vec![selected_root], Direction::Dependencies)
+        }
+    };
+
+    let builder = TreeBuilder::new(&resolved, options, direction);
+    let nodes = roots
+        .into_iter()
+        .map(|root| builder.build(root))
+        .collect::<Vec<_>>();
+    match cmd.format {
+        TreeFormat::Text => {
+            let rendered = nodes
+                .iter()
+                .map(|node| render_text(node, direction))
+                .collect::<Vec<_>>();
+            print!("{}", rendered.join("\n"));
+        }
+        TreeFormat::Json => println!("{}", serde_json::to_string_pretty(&nodes)?),
+    }
     Ok(0)
 }
 
@@ -177,6 +629,11 @@ mod tests {
         })
     }
 
+    fn render_tree(resolved: &ResolvedEnv, root: ModuleId, options: TreeOptions) -> String {
+        let node = TreeBuilder::new(resolved, options, Direction::Dependencies).build(root);
+        render_text(&node, Direction::Dependencies)
+    }
+
     fn regular_dep(name: &str) -> DependencyEdge {
         DependencyEdge {
             name: name.parse().unwrap(),
@@ -209,7 +666,7 @@ mod tests {
         env.add_dependency(root_id, dep_b, &regular_dep("alice/b"));
         env.add_dependency(dep_a, dep_c, &regular_dep("alice/c"));
 
-        let rendered = render_tree(&env, root_id);
+        let rendered = render_tree(&env, root_id, TreeOptions::default());
         expect![[r#"
             alice/root@0.1.0 (local /workspace/root):
             ├─ alice/a -> alice/a@0.1.0 (local /workspace/a)
@@ -232,7 +689,7 @@ mod tests {
         );
         env.add_dependency(root_id, dep_id, &regular_dep("just/hello004"));
 
-        let rendered = render_tree(&env, root_id);
+        let rendered = render_tree(&env, root_id, TreeOptions::default());
         expect![[r#"
             username/hello@0.1.0 (local /workspace/hello):
             └─ just/hello004 -> just/hello004@0.1.0 (local /workspace/hello/deps/hello004)
@@ -254,11 +711,155 @@ mod tests {
         let mut env = ResolvedEnv::from_root_modules(roots);
         env.add_dependency(app, liba, &regular_dep("alice/liba"));
 
-        let rendered = render_tree(&env, app);
+        let rendered = render_tree(&env, app, TreeOptions::default());
         expect![[r#"
             alice/app@0.1.0 (local /workspace/app) [workspace member]:
             └─ alice/liba -> alice/liba@0.1.1 (local /workspace/liba) [workspace member]
         "#]]
         .assert_eq(&rendered);
     }
+
+    fn registry_source(name: &str, version: &str) -> ModuleSource {
+        ModuleSource::from_version(name.into(), version.parse().unwrap())
+    }
+
+    fn module_with_deps(name: &str, version: &str, deps: &[(&str, &str)]) -> Arc<MoonMod> {
+        Arc::new(MoonMod {
+            name: name.to_string(),
+            version: Some(version.parse().unwrap()),
+            deps: deps
+                .iter()
+                .map(|(name, version)| {
+                    (
+                        name.to_string(),
+                        moonutil::dependency::SourceDependencyInfo::Simple(
+                            version.parse().unwrap(),
+                        ),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    /// root -> a -> c@0.2.0, root -> b -> c@0.2.0 (b asks for 0.1.0), and
+    /// root has a binary dependency on tool.
+    fn diamond_env() -> (ResolvedEnv, ModuleId) {
+        let (roots, root_id) = ResolvedModule::only_one_module(
+            local_source("alice/root", "0.1.0", "/workspace/root"),
+            module_with_deps(
+                "alice/root",
+                "0.1.0",
+                &[("alice/a", "0.1.0"), ("alice/b", "0.1.0")],
+            ),
+        );
+        let mut env = ResolvedEnv::from_root_modules(roots);
+        let a = env.add_module(
+            registry_source("alice/a", "0.1.0"),
+            module_with_deps("alice/a", "0.1.0", &[("alice/c", "0.2.0")]),
+        );
+        let b = env.add_module(
+            registry_source("alice/b", "0.1.0"),
+            module_with_deps("alice/b", "0.1.0", &[("alice/c", "0.1.0")]),
+        );
+        let c = env.add_module(
+            registry_source("alice/c", "0.2.0"),
+            module_with_deps("alice/c", "0.2.0", &[]),
+        );
+        let tool = env.add_module(
+            registry_source("alice/tool", "1.0.0"),
+            module_with_deps("alice/tool", "1.0.0", &[]),
+        );
+        env.add_dependency(root_id, a, &regular_dep("alice/a"));
+        env.add_dependency(root_id, b, &regular_dep("alice/b"));
+        env.add_dependency(a, c, &regular_dep("alice/c"));
+        env.add_dependency(b, c, &regular_dep("alice/c"));
+        env.add_dependency(
+            root_id,
+            tool,
+            &DependencyEdge {
+                name: "alice/tool".parse().unwrap(),
+                kind: DependencyKind::Binary,
+            },
+        );
+        (env, root_id)
+    }
+
+    #[test]
+    fn tree_render_limits_depth_and_includes_bin_edges_on_request() {
+        let (env, root_id) = diamond_env();
+
+        let rendered = render_tree(
+            &env,
+            root_id,
+            TreeOptions {
+                depth: Some(1),
+                include_bin: false,
+            },
+        );
+        expect![[r#"
+            alice/root@0.1.0 (local /workspace/root):
+            ├─ alice/a -> alice/a@0.1.0
+            └─ alice/b -> alice/b@0.1.0
+        "#]]
+        .assert_eq(&rendered);
+
+        let rendered = render_tree(
+            &env,
+            root_id,
+            TreeOptions {
+                depth: None,
+                include_bin: true,
+            },
+        );
+        expect![[r#"
+            alice/root@0.1.0 (local /workspace/root):
+            ├─ alice/a -> alice/a@0.1.0
+            │  └─ alice/c -> alice/c@0.2.0
+            ├─ alice/b -> alice/b@0.1.0
+            │  └─ alice/c -> alice/c@0.2.0
+            └─ alice/tool (binary) -> alice/tool@1.0.0
+        "#]]
+        .assert_eq(&rendered);
+    }
+
+    #[test]
+    fn tree_invert_lists_dependents() {
+        let (env, _) = diamond_env();
+        let targets = find_modules(&env, "alice/c").unwrap();
+        assert_eq!(targets.len(), 1);
+        assert!(find_modules(&env, "alice/c@0.1.0").is_err());
+
+        let node =
+            TreeBuilder::new(&env, TreeOptions::default(), Direction::Dependents).build(targets[0]);
+        expect![[r#"
+            alice/c@0.2.0:
+            ├─ alice/a@0.1.0
+            │  └─ alice/root@0.1.0 (local /workspace/root)
+            └─ alice/b@0.1.0
+               └─ alice/root@0.1.0 (local /workspace/root)
+        "#]]
+        .assert_eq(&render_text(&node, Direction::Dependents));
+
+        let json = serde_json::to_value(&node).unwrap();
+        assert_eq!(json["children"][0]["name"], "alice/a");
+        assert_eq!(json["children"][0]["dependency"], "alice/c");
+        assert_eq!(
+            json["children"][0]["children"][0]["source"],
+            "local /workspace/root"
+        );
+    }
+
+    #[test]
+    fn tree_duplicates_explain_selected_version() {
+        let (env, _) = diamond_env();
+        let entries = collect_duplicates(&env, TreeOptions::default());
+        expect![[r#"
+            alice/c:
+            ├─ alice/b@0.1.0 requires 0.1.0 (uses 0.2.0)
+            └─ alice/a@0.1.0 requires 0.2.0 (uses 0.2.0)
+              selected 0.2.0: highest version required within its compatibility set
+        "#]]
+        .assert_eq(&render_duplicates(&entries));
+    }
 }
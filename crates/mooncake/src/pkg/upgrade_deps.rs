@@ -0,0 +1,319 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Raise the registry dependency requirements of one or more modules at once.
+//!
+//! With the global `--dry-run` flag the manifest changes are printed as a
+//! unified diff instead of being written.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use indexmap::IndexMap;
+use moonutil::constants::{MOON_MOD, MOON_MOD_JSON};
+use moonutil::manifest::{MoonMod, convert_module_to_mod_json, read_module_desc_file_in_dir};
+use moonutil::moon_mod_patch::{MoonModPatch, patch_module_dsl};
+use moonutil::project::ProjectManifest;
+use moonutil::resolution::ModuleName;
+use moonutil::user_log::UserLog;
+use semver::Version;
+
+use crate::registry::Registry;
+use crate::resolver::mvs::same_mvs_compatibility_set;
+
+/// Upgrade the version requirements of registry dependencies
+#[derive(Debug, clap::Parser)]
+pub struct UpgradeDepsSubcommand {
+    /// Only upgrade these dependencies (default: all registry dependencies)
+    #[clap(value_name = "MODULE")]
+    pub modules: Vec<String>,
+
+    /// Upgrade to the newest version, even if its major version is incompatible
+    #[clap(long)]
+    pub major: bool,
+
+    /// Do not update the registry index before upgrading
+    #[clap(long)]
+    pub no_update: bool,
+}
+
+/// One raised requirement.
+#[derive(Debug, PartialEq, Eq)]
+struct Upgrade {
+    from: Version,
+    to: Version,
+}
+
+/// Choose new requirements for the registry dependencies of `module`.
+///
+/// Without `major`, a requirement only moves within its MVS compatibility set,
+/// so the upgrade never selects a second major version of the same module.
+/// Pre-releases are only chosen for requirements that already are one.
+fn plan_upgrades(
+    module: &MoonMod,
+    registry: &dyn Registry,
+    only: &[String],
+    major: bool,
+    user_log: &UserLog,
+) -> IndexMap<String, Upgrade> {
+    let mut upgrades = IndexMap::new();
+    for (name, dependency) in &module.deps {
+        if !only.is_empty() && !only.contains(name) {
+            continue;
+        }
+        if dependency.path().is_some() || dependency.git().is_some() {
+            continue;
+        }
+        let Some(current) = dependency.version() else {
+            continue;
+        };
+        let versions = match registry.all_versions_of(&ModuleName::from(name.as_str())) {
+            Ok(versions) => versions,
+            Err(e) => {
+                user_log.warn(format!("skipping `{name}` in `{}`: {e}", module.name));
+                continue;
+            }
+        };
+        let newest = versions
+            .keys()
+            .filter(|version| version.pre.is_empty() || !current.pre.is_empty())
+            .filter(|version| major || same_mvs_compatibility_set(current, version))
+            .max();
+        if let Some(newest) = newest
+            && newest > current
+        {
+            upgrades.insert(
+                name.clone(),
+                Upgrade {
+                    from: current.clone(),
+                    to: newest.clone(),
+                },
+            );
+        }
+    }
+    upgrades
+}
+
+/// The manifest of one module with its requirements raised.
+struct PatchedManifest {
+    path: PathBuf,
+    before: String,
+    after: String,
+}
+
+/// Render `upgrades` into the manifest of the module at `module_dir`.
+///
+/// `moon.mod` is patched in place so comments and layout survive. A legacy
+/// `moon.mod.json` is rewritten from the parsed module, as `moon add` does.
+fn patch_manifest(
+    module_dir: &Path,
+    mut module: MoonMod,
+    upgrades: &IndexMap<String, Upgrade>,
+) -> anyhow::Result<PatchedManifest> {
+    let dsl = module_dir.join(MOON_MOD);
+    if dsl.exists() {
+        let before = std::fs::read_to_string(&dsl)
+            .with_context(|| format!("failed to load `{}`", dsl.display()))?;
+        let versions = upgrades
+            .iter()
+            .map(|(name, upgrade)| (name.clone(), upgrade.to.clone()))
+            .collect();
+        let after = patch_module_dsl(before.clone(), MoonModPatch::UpdateImportItems(versions));
+        return Ok(PatchedManifest {
+            path: dsl,
+            before,
+            after,
+        });
+    }
+
+    let json = module_dir.join(MOON_MOD_JSON);
+    let before = std::fs::read_to_string(&json)
+        .with_context(|| format!("failed to load `{}`", json.display()))?;
+    for (name, upgrade) in upgrades {
+        if let Some(dependency) = module.deps.get_mut(name) {
+            dependency.set_version(Some(upgrade.to.clone()));
+        }
+    }
+    let after = serde_json_lenient::to_string_pretty(&convert_module_to_mod_json(module))?;
+    Ok(PatchedManifest {
+        path: json,
+        before,
+        after,
+    })
+}
+
+fn unified_diff(manifest: &PatchedManifest) -> String {
+    let path = manifest.path.display().to_string();
+    similar::TextDiff::from_lines(&manifest.before, &manifest.after)
+        .unified_diff()
+        .header(&path, &path)
+        .to_string()
+}
+
+pub fn upgrade_deps(
+    registry: &dyn Registry,
+    module_dir: &Path,
+    project_manifest: &ProjectManifest,
+    cmd: &UpgradeDepsSubcommand,
+    dry_run: bool,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let module_dirs = match project_manifest {
+        ProjectManifest::Workspace(workspace) => workspace.members().to_vec(),
+        _ => vec![module_dir.to_path_buf()],
+    };
+
+    let mut modules = Vec::with_capacity(module_dirs.len());
+    for module_dir in &module_dirs {
+        modules.push((module_dir, read_module_desc_file_in_dir(module_dir)?));
+    }
+    let declared = modules
+        .iter()
+        .flat_map(|(_, module)| module.deps.keys())
+        .collect::<HashSet<_>>();
+    for name in &cmd.modules {
+        if !declared.contains(name) {
+            bail!("`{name}` is not a dependency of any selected module");
+        }
+    }
+
+    let mut upgraded = 0;
+    for (module_dir, module) in modules {
+        let upgrades = plan_upgrades(&module, registry, &cmd.modules, cmd.major, user_log);
+        if upgrades.is_empty() {
+            continue;
+        }
+        upgraded += upgrades.len();
+        let module_name = module.name.clone();
+        let manifest = patch_manifest(module_dir, module, &upgrades)?;
+
+        if dry_run {
+            print!("{}", unified_diff(&manifest));
+            continue;
+        }
+        std::fs::write(&manifest.path, &manifest.after)
+            .with_context(|| format!("failed to write `{}`", manifest.path.display()))?;
+        for (name, upgrade) in &upgrades {
+            println!(
+                "{module_name}: upgraded `{name}` from {} to {}",
+                upgrade.from, upgrade.to
+            );
+        }
+    }
+
+    if upgraded == 0 {
+        println!("All registry dependencies are already at their newest versions.");
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::mock::MockRegistry;
+    use expect_test::expect;
+
+    fn registry() -> MockRegistry {
+        let mut registry = MockRegistry::new();
+        registry
+            .add_module_full("alice/lib", "0.2.0", [])
+            .add_module_full("alice/lib", "0.3.1", [])
+            .add_module_full("alice/lib", "2.0.0", [])
+            .add_module_full("bob/util", "1.0.0", [])
+            .add_module_full("bob/util", "1.4.0", [])
+            .add_module_full("bob/util", "2.0.0-rc.1", []);
+        registry
+    }
+
+    fn quiet() -> UserLog {
+        UserLog::new(log::LevelFilter::Error)
+    }
+
+    fn upgrades_of(dir: &Path, only: &[String], major: bool) -> IndexMap<String, Upgrade> {
+        let module = read_module_desc_file_in_dir(dir).unwrap();
+        plan_upgrades(&module, &registry(), only, major, &quiet())
+    }
+
+    #[test]
+    fn upgrades_stay_compatible_unless_major_is_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(MOON_MOD_JSON),
+            r#"{
+  "name": "me/app",
+  "deps": {
+    "alice/lib": "0.2.0",
+    "bob/util": "1.0.0",
+    "me/local": { "path": "../local" }
+  }
+}"#,
+        )
+        .unwrap();
+
+        let compatible = upgrades_of(dir.path(), &[], false);
+        assert_eq!(
+            compatible.get("alice/lib"),
+            Some(&Upgrade {
+                from: Version::new(0, 2, 0),
+                to: Version::new(0, 3, 1),
+            })
+        );
+        assert_eq!(compatible["bob/util"].to, Version::new(1, 4, 0));
+        assert!(!compatible.contains_key("me/local"));
+
+        let major = upgrades_of(dir.path(), &["alice/lib".to_string()], true);
+        assert_eq!(major.len(), 1);
+        assert_eq!(major["alice/lib"].to, Version::new(2, 0, 0));
+    }
+
+    #[test]
+    fn dry_run_diff_preserves_moon_mod_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join(MOON_MOD),
+            r#"name = "me/app"
+
+import {
+  // pinned for the parser
+  "alice/lib@0.2.0",
+  "bob/util@1.4.0", // already newest
+}
+"#,
+        )
+        .unwrap();
+        let module = read_module_desc_file_in_dir(dir.path()).unwrap();
+        let upgrades = plan_upgrades(&module, &registry(), &[], false, &quiet());
+        let mut manifest = patch_manifest(dir.path(), module, &upgrades).unwrap();
+        manifest.path = PathBuf::from(MOON_MOD);
+
+        expect![[r#"
+            --- moon.mod
+            +++ moon.mod
+            @@ -2,6 +2,6 @@
+             
+             import {
+               // pinned for the parser
+            -  "alice/lib@0.2.0",
+            +  "alice/lib@0.3.1",
+               "bob/util@1.4.0", // already newest
+             }
+        "#]]
+        .assert_eq(&unified_diff(&manifest));
+    }
+}
@@ -0,0 +1,88 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Copy the resolved registry dependencies of a project into its `vendor`
+//! directory.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use moonutil::constants::{MOON_LOCK_FILE, MOON_VENDOR_DIR};
+use moonutil::manifest::read_module_desc_file_in_dir;
+use moonutil::project::ProjectManifest;
+use moonutil::resolution::{ModuleSource, ResolvedModule, ResolvedRootModules};
+use moonutil::user_log::UserLog;
+
+use crate::lock_file::{LockFile, LockFileOptions};
+use crate::registry::RegistryClient;
+use crate::resolver::{ResolveConfig, resolve_with_default_env_and_resolver};
+use crate::vendor::vendor_resolved;
+
+/// Copy all registry dependencies into `vendor/` so that builds work offline
+#[derive(Debug, clap::Parser)]
+pub struct VendorSubcommand {
+    /// Fail instead of updating `moon.lock` if it does not match the resolution
+    #[clap(long)]
+    pub locked: bool,
+}
+
+pub fn vendor(
+    registry: &RegistryClient,
+    project_dir: &Path,
+    project_manifest: &ProjectManifest,
+    cmd: &VendorSubcommand,
+    user_log: &UserLog,
+) -> anyhow::Result<i32> {
+    let mut roots = ResolvedRootModules::with_key();
+    let module_dirs = match project_manifest {
+        ProjectManifest::Workspace(workspace) => workspace.members().to_vec(),
+        _ => vec![project_dir.to_path_buf()],
+    };
+    for module_dir in &module_dirs {
+        let module = Arc::new(read_module_desc_file_in_dir(module_dir)?);
+        let source = ModuleSource::from_local_module(&module, module_dir);
+        roots.insert(ResolvedModule::new(source, module));
+    }
+
+    let resolve_cfg = ResolveConfig {
+        registry,
+        inject_std: false,
+    };
+    let resolved = resolve_with_default_env_and_resolver(&resolve_cfg, roots, user_log)?;
+    LockFile::from_resolved(&resolved, registry)?.update_or_verify(LockFileOptions {
+        path: &project_dir.join(MOON_LOCK_FILE),
+        locked: cmd.locked,
+    })?;
+
+    let summary = vendor_resolved(registry, &resolved, project_dir, user_log)?;
+    for module in &summary.added {
+        println!("Vendored {module}");
+    }
+    for module in &summary.removed {
+        println!("Removed {module}");
+    }
+    println!(
+        "{} modules in `{}` ({} added, {} removed, {} unchanged)",
+        summary.added.len() + summary.unchanged,
+        MOON_VENDOR_DIR,
+        summary.added.len(),
+        summary.removed.len(),
+        summary.unchanged
+    );
+    Ok(0)
+}
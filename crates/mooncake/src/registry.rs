@@ -17,6 +17,7 @@
 // For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
 
 mod client;
+mod local;
 #[cfg(test)]
 pub(crate) mod mock;
 pub mod path;
@@ -25,6 +26,7 @@ use std::{collections::BTreeMap, path::Path, sync::Arc};
 
 pub use client::RegistryClient;
 use indexmap::IndexMap;
+pub use local::LocalRegistry;
 use moonutil::dependency::SourceDependencyInfo;
 use moonutil::resolution::ModuleName;
 use moonutil::user_log::UserLog;
@@ -39,6 +39,7 @@ use reqwest::{StatusCode, header::USER_AGENT};
 use semver::Version;
 use serde::Deserialize;
 
+use super::local::{LocalRegistry, local_registry_root};
 use crate::{
     registry::RegistryVersionInfo,
     update::{RegistryIndexRecloneReason, RegistryIndexUpdate, UpdateOutcome},
@@ -72,11 +73,18 @@ impl RegistryEndpoints {
         }
     }
 
+    /// Endpoints of a registry stored in a local directory. Package archives
+    /// are read through [`LocalRegistry`]; only the assets base is used.
+    fn local(root: &Path) -> Self {
+        Self {
+            packages: format!("file://{}", root.join("user").display()),
+            assets: format!("file://{}", root.join("assets").display()),
+        }
+    }
+
+    #[cfg(test)]
     fn package_archive(&self, name: &ModuleName, version: &Version) -> String {
-        let path = form_urlencoded::Serializer::new(String::new())
-            .append_key_only(&format!("{}/{}/{}", name.username, name.unqual, version))
-            .finish();
-        format!("{}/{}.zip", self.packages, path)
+        package_archive_url(&self.packages, name, version)
     }
 
     fn wasm_asset(&self, name: &ModuleName, version: &Version, package_path: &str) -> String {
@@ -92,6 +100,77 @@ impl RegistryEndpoints {
     }
 }
 
+fn package_archive_url(packages: &str, name: &ModuleName, version: &Version) -> String {
+    let path = form_urlencoded::Serializer::new(String::new())
+        .append_key_only(&format!("{}/{}/{}", name.username, name.unqual, version))
+        .finish();
+    format!("{packages}/{path}.zip")
+}
+
+/// A location that serves package archives.
+enum ArchiveSource {
+    /// The package endpoint of a hosted registry or mirror.
+    Http(String),
+    Local(LocalRegistry),
+}
+
+impl ArchiveSource {
+    fn mirror(location: &str) -> Option<Self> {
+        if let Some(root) = local_registry_root(location) {
+            Some(Self::Local(LocalRegistry::new(root)))
+        } else if location.starts_with("http://") || location.starts_with("https://") {
+            Some(Self::Http(format!(
+                "{}/user",
+                location.trim_end_matches('/')
+            )))
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for ArchiveSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveSource::Http(url) => write!(f, "{url}"),
+            ArchiveSource::Local(registry) => write!(f, "{}", registry.root().display()),
+        }
+    }
+}
+
+/// A location that serves the registry index, tried when syncing with the
+/// registry itself fails.
+enum IndexMirror {
+    /// The Git index of a hosted mirror, laid out like the registry itself.
+    Git(String),
+    /// The index of a local registry directory, copied into the index.
+    Local(LocalRegistry),
+}
+
+impl IndexMirror {
+    fn mirror(location: &str) -> Option<Self> {
+        if let Some(root) = local_registry_root(location) {
+            Some(Self::Local(LocalRegistry::new(root)))
+        } else if location.starts_with("http://") || location.starts_with("https://") {
+            Some(Self::Git(format!(
+                "{}/git/index",
+                location.trim_end_matches('/')
+            )))
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for IndexMirror {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexMirror::Git(url) => write!(f, "{url}"),
+            IndexMirror::Local(registry) => write!(f, "{}", registry.root().display()),
+        }
+    }
+}
+
 /// Access to a configured Mooncakes registry and its local state.
 ///
 /// This client owns synchronization of the Git index and symbols archive as
@@ -102,6 +181,15 @@ pub struct RegistryClient {
     config: RegistryConfig,
     home: MoonHomeLayout,
     endpoints: RegistryEndpoints,
+    /// Set when the configured registry is a local directory, which then also
+    /// serves as the index.
+    local: Option<LocalRegistry>,
+    /// The configured mirrors followed by the registry itself, in the order
+    /// package downloads are attempted.
+    archive_sources: Vec<ArchiveSource>,
+    /// The configured mirrors, in the order they are tried when syncing the
+    /// index with the registry fails.
+    index_mirrors: Vec<IndexMirror>,
     cache: RefCell<HashMap<ModuleName, Arc<BTreeMap<Version, RegistryVersionInfo>>>>,
 }
 
@@ -116,24 +204,119 @@ impl RegistryClient {
     }
 
     fn with_home(config: RegistryConfig, home: MoonHomeLayout) -> Self {
+        let local = local_registry_root(&config.registry).map(LocalRegistry::new);
+        let endpoints = match &local {
+            Some(local) => RegistryEndpoints::local(local.root()),
+            None => RegistryEndpoints::from_config(&config),
+        };
+        let mut archive_sources = Vec::with_capacity(config.mirrors.len() + 1);
+        let mut index_mirrors = Vec::with_capacity(config.mirrors.len());
+        for mirror in &config.mirrors {
+            match ArchiveSource::mirror(mirror).zip(IndexMirror::mirror(mirror)) {
+                Some((archive_source, index_mirror)) => {
+                    archive_sources.push(archive_source);
+                    index_mirrors.push(index_mirror);
+                }
+                None => log::warn!(
+                    "Ignoring registry mirror `{mirror}`: expected an http(s) URL, a file:// URL, or an absolute path"
+                ),
+            }
+        }
+        archive_sources.push(match &local {
+            Some(local) => ArchiveSource::Local(LocalRegistry::new(local.root())),
+            None => ArchiveSource::Http(endpoints.packages.clone()),
+        });
+
         Self {
-            endpoints: RegistryEndpoints::from_config(&config),
+            endpoints,
             config,
             home,
+            local,
+            archive_sources,
+            index_mirrors,
             cache: RefCell::new(HashMap::new()),
         }
     }
 
+    fn index_file(&self, name: &ModuleName) -> std::path::PathBuf {
+        if let Some(local) = &self.local {
+            return local.index_file(name);
+        }
+        self.home.registry_index_file(name)
+    }
+
     /// Return whether a local registry index is available for offline fallback.
     pub fn has_cached_index(&self) -> bool {
-        self.home.registry_index_dir().exists()
+        match &self.local {
+            Some(local) => local.index_dir().exists(),
+            None => self.home.registry_index_dir().exists(),
+        }
     }
 
     /// Synchronize the Git index and symbols archive with the configured registry.
+    ///
+    /// A local registry directory is read in place and has nothing to sync.
+    /// If the registry cannot be reached, the index of the first mirror that
+    /// serves one is used instead.
     pub fn sync(&self, user_log: &UserLog) -> anyhow::Result<()> {
-        let outcome = crate::update::sync(&self.home, &self.config, user_log)?;
-        self.cache.borrow_mut().clear();
-        log_sync_outcome(outcome, user_log);
+        if let Some(local) = &self.local {
+            user_log.info(format!(
+                "Using local registry at {}",
+                local.root().display()
+            ));
+            return Ok(());
+        }
+        let error = match crate::update::sync(&self.home, &self.config, user_log) {
+            Ok(outcome) => {
+                self.cache.borrow_mut().clear();
+                log_sync_outcome(outcome, user_log);
+                return Ok(());
+            }
+            Err(error) => error,
+        };
+        if self.index_mirrors.is_empty() {
+            return Err(error);
+        }
+        user_log.warn(format!(
+            "failed to sync the registry index, trying mirrors: {error:#}"
+        ));
+        for mirror in &self.index_mirrors {
+            match self.sync_from_mirror(mirror, user_log) {
+                Ok(()) => {
+                    self.cache.borrow_mut().clear();
+                    return Ok(());
+                }
+                Err(e) => user_log.warn(format!(
+                    "failed to sync the registry index from mirror {mirror}: {e:#}"
+                )),
+            }
+        }
+        Err(error)
+    }
+
+    fn sync_from_mirror(&self, mirror: &IndexMirror, user_log: &UserLog) -> anyhow::Result<()> {
+        match mirror {
+            IndexMirror::Git(index) => {
+                let config = RegistryConfig {
+                    registry: self.config.registry.clone(),
+                    index: index.clone(),
+                    symbols: self.config.symbols.clone(),
+                    mirrors: Vec::new(),
+                };
+                let outcome = crate::update::sync(&self.home, &config, user_log)?;
+                log_sync_outcome(outcome, user_log);
+            }
+            IndexMirror::Local(local) => {
+                if !local.index_dir().is_dir() {
+                    anyhow::bail!("`{}` does not exist", local.index_dir().display());
+                }
+                crate::update::sync_from_local_index(&self.home, &local.index_dir(), user_log)?;
+                user_log.info(format!(
+                    "Registry index copied from mirror at {}",
+                    local.root().display()
+                ));
+            }
+        }
         Ok(())
     }
 
@@ -150,7 +333,11 @@ impl RegistryClient {
             .build()
             .context("failed to create registry asset HTTP client")?;
         self.acquire_wasm_asset_with(name, version, package_path, user_log, |url| {
-            download_registry_asset(&http, url, user_log)
+            match url.strip_prefix("file://") {
+                Some(path) => std::fs::read(path)
+                    .with_context(|| format!("failed to read registry asset `{path}`")),
+                None => download_registry_asset(&http, url, user_log),
+            }
         })
     }
 
@@ -320,7 +507,7 @@ fn parse_sha256_checksum(bytes: &[u8]) -> anyhow::Result<String> {
     Ok(checksum.to_ascii_lowercase())
 }
 
-fn sha256_hex(bytes: &[u8]) -> String {
+pub(super) fn sha256_hex(bytes: &[u8]) -> String {
     use sha2::{Digest, Sha256};
 
     format!("{:x}", Sha256::digest(bytes))
@@ -336,31 +523,7 @@ impl super::Registry for RegistryClient {
             return Ok(Arc::clone(v));
         }
 
-        let index_file = self.home.registry_index_file(name);
-        log::debug!("Reading versions of {} from {}", name, index_file.display());
-        let file = std::fs::File::open(index_file)?;
-        let reader = std::io::BufReader::new(file);
-
-        let lines = reader.lines();
-        let mut res = BTreeMap::new();
-        for line in lines {
-            let line = line?;
-            let entry = match serde_json_lenient::from_str::<RegistryIndexEntry>(&line) {
-                Ok(m) => m,
-                Err(e) => {
-                    log::warn!("Error when reading index file of {}: {}", name, e);
-                    continue;
-                }
-            };
-            if let Some(v) = entry.version.as_deref() {
-                res.insert(
-                    Version::parse(v)?,
-                    RegistryVersionInfo {
-                        deps: entry.deps.unwrap_or_default(),
-                    },
-                );
-            }
-        }
+        let res = read_index_versions(&self.index_file(name), name)?;
 
         // put in cache
         let res = Arc::new(res);
@@ -393,6 +556,97 @@ impl super::RegistrySource for RegistryClient {
     }
 }
 
+/// Download one package archive from `source` into the verified archive cache.
+fn fetch_archive(
+    source: &ArchiveSource,
+    cache_file: &Path,
+    name: &ModuleName,
+    version: &Version,
+    expected_checksum: &str,
+) -> anyhow::Result<File> {
+    match source {
+        ArchiveSource::Http(packages) => {
+            let url = package_archive_url(packages, name, version);
+            let client = reqwest::blocking::Client::new();
+            let mut response = client
+                .get(url)
+                .header(
+                    USER_AGENT,
+                    format!("mooncake/{}", env!("CARGO_PKG_VERSION")),
+                )
+                .send()?
+                .error_for_status()?;
+            persist_verified_archive(&mut response, cache_file, name, version, expected_checksum)
+        }
+        ArchiveSource::Local(registry) => {
+            let path = registry.archive_path(name, version);
+            let mut archive = File::open(&path)
+                .with_context(|| format!("failed to open `{}`", path.display()))?;
+            persist_verified_archive(&mut archive, cache_file, name, version, expected_checksum)
+        }
+    }
+}
+
+/// Read every version of `name` from a registry index file.
+pub(super) fn read_index_versions(
+    index_file: &Path,
+    name: &ModuleName,
+) -> anyhow::Result<BTreeMap<Version, RegistryVersionInfo>> {
+    log::debug!("Reading versions of {} from {}", name, index_file.display());
+    let file = std::fs::File::open(index_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let lines = reader.lines();
+    let mut res = BTreeMap::new();
+    for line in lines {
+        let line = line?;
+        let entry = match serde_json_lenient::from_str::<RegistryIndexEntry>(&line) {
+            Ok(m) => m,
+            Err(e) => {
+                log::warn!("Error when reading index file of {}: {}", name, e);
+                continue;
+            }
+        };
+        if let Some(v) = entry.version.as_deref() {
+            res.insert(
+                Version::parse(v)?,
+                RegistryVersionInfo {
+                    deps: entry.deps.unwrap_or_default(),
+                },
+            );
+        }
+    }
+    Ok(res)
+}
+
+/// Read the source archive checksum of one version from a registry index file.
+pub(super) fn read_index_checksum(p: &Path, version: &Version) -> anyhow::Result<String> {
+    let file = std::fs::File::open(p)?;
+    let reader = std::io::BufReader::new(file);
+
+    let lines = reader.lines().collect::<std::io::Result<Vec<String>>>()?;
+    let version_str = version.to_string();
+    for line in lines.iter().rev() {
+        let entry = serde_json_lenient::from_str::<RegistryIndexEntry>(line)?;
+        if entry.version.as_deref() == Some(version_str.as_str()) {
+            if let Some(checksum) = entry.checksum {
+                return Ok(checksum);
+            } else {
+                bail!(
+                    "No checksum found for version {} in index file {:?}",
+                    version,
+                    p
+                );
+            }
+        }
+    }
+    bail!(
+        "No description found for version {} in index file {:?}",
+        version,
+        p,
+    );
+}
+
 fn calc_sha2(reader: &mut impl Read) -> std::io::Result<String> {
     use sha2::{Digest, Sha256};
 
@@ -413,7 +667,10 @@ fn calc_sha2(reader: &mut impl Read) -> std::io::Result<String> {
 
 /// Keep the file that was hashed open so a concurrent cache-path replacement
 /// cannot change the bytes later consumed by extraction.
-fn open_verified_archive(path: &Path, expected_checksum: &str) -> std::io::Result<Option<File>> {
+pub(super) fn open_verified_archive(
+    path: &Path,
+    expected_checksum: &str,
+) -> std::io::Result<Option<File>> {
     let mut archive = File::open(path)?;
     if calc_sha2(&mut archive)? != expected_checksum {
         return Ok(None);
@@ -490,31 +747,7 @@ impl RegistryClient {
         name: &ModuleName,
         version: &Version,
     ) -> anyhow::Result<String> {
-        let p = self.home.registry_index_file(name);
-        let file = std::fs::File::open(&p)?;
-        let reader = std::io::BufReader::new(file);
-
-        let lines = reader.lines().collect::<std::io::Result<Vec<String>>>()?;
-        let version_str = version.to_string();
-        for line in lines.iter().rev() {
-            let entry = serde_json_lenient::from_str::<RegistryIndexEntry>(line)?;
-            if entry.version.as_deref() == Some(version_str.as_str()) {
-                if let Some(checksum) = entry.checksum {
-                    return Ok(checksum);
-                } else {
-                    bail!(
-                        "No checksum found for version {} in index file {:?}",
-                        version,
-                        p
-                    );
-                }
-            }
-        }
-        bail!(
-            "No description found for version {} in index file {:?}",
-            version,
-            p,
-        );
+        read_index_checksum(&self.index_file(name), version)
     }
 
     fn download_or_use_cache(
@@ -524,7 +757,7 @@ impl RegistryClient {
         expected_checksum: &str,
         user_log: &UserLog,
     ) -> anyhow::Result<File> {
-        let pkg_index = self.home.registry_index_file(name);
+        let pkg_index = self.index_file(name);
         if !pkg_index.exists() {
             anyhow::bail!("Module {}@{} not found", name, version);
         }
@@ -549,18 +782,19 @@ impl RegistryClient {
             return Ok(archive);
         }
         user_log.status(format!("Downloading {name}@{version}"));
-        let url = self.endpoints.package_archive(name, version);
-        let client = reqwest::blocking::Client::new();
-        let mut response = client
-            .get(url)
-            .header(
-                USER_AGENT,
-                format!("mooncake/{}", env!("CARGO_PKG_VERSION")),
-            )
-            .send()?
-            .error_for_status()?;
-
-        persist_verified_archive(&mut response, &cache_file, name, version, expected_checksum)
+        let (last, mirrors) = self
+            .archive_sources
+            .split_last()
+            .expect("the registry itself is always an archive source");
+        for source in mirrors {
+            match fetch_archive(source, &cache_file, name, version, expected_checksum) {
+                Ok(archive) => return Ok(archive),
+                Err(error) => user_log.warn(format!(
+                    "failed to download {name}@{version} from mirror {source}, trying the next source: {error:#}"
+                )),
+            }
+        }
+        fetch_archive(last, &cache_file, name, version, expected_checksum)
     }
 
     /// Return the registry index's SHA-256 checksum for a published source archive.
@@ -715,6 +949,7 @@ mod tests {
             registry: "https://mooncakes.io/".to_owned(),
             index: String::new(),
             symbols: None,
+            mirrors: Vec::new(),
         });
         assert_eq!(
             endpoints.package_archive(&"test/pkg".into(), &Version::new(1, 2, 3)),
@@ -728,6 +963,7 @@ mod tests {
             registry: "https://registry.example.com/".to_owned(),
             index: String::new(),
             symbols: None,
+            mirrors: Vec::new(),
         });
         assert_eq!(
             endpoints.package_archive(&"test/pkg".into(), &Version::new(1, 2, 3)),
@@ -741,6 +977,7 @@ mod tests {
                 registry: "https://mooncakes.io".to_owned(),
                 index: String::new(),
                 symbols: None,
+                mirrors: Vec::new(),
             },
             MoonHomeLayout::new(sandbox.path().to_path_buf()),
         )
@@ -856,6 +1093,7 @@ mod tests {
                         registry: "https://mooncakes.io".to_owned(),
                         index: String::new(),
                         symbols: None,
+                        mirrors: Vec::new(),
                     },
                     home.clone(),
                 );
@@ -1064,6 +1302,7 @@ mod tests {
                     registry: String::new(),
                     index: String::new(),
                     symbols: None,
+                    mirrors: Vec::new(),
                 },
                 home,
             ),
@@ -1205,6 +1444,7 @@ mod tests {
                             registry: url_base,
                             index: String::new(),
                             symbols: None,
+                            mirrors: Vec::new(),
                         },
                         home,
                     );
@@ -1237,6 +1477,101 @@ mod tests {
         assert_eq!(request_count.load(Ordering::Relaxed), 1);
     }
 
+    #[test]
+    fn mirrors_are_tried_in_order_before_the_registry() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let name: ModuleName = "test/module".into();
+        let version = Version::new(1, 2, 3);
+        let archive = test_archive();
+        let checksum = calc_sha2(&mut Cursor::new(&archive)).unwrap();
+
+        let empty_mirror = sandbox.path().join("empty-mirror");
+        let mirror = LocalRegistry::new(sandbox.path().join("mirror"));
+        let mirror_archive = mirror.archive_path(&name, &version);
+        std::fs::create_dir_all(mirror_archive.parent().unwrap()).unwrap();
+        std::fs::write(mirror_archive, &archive).unwrap();
+
+        let home = MoonHomeLayout::new(sandbox.path().join("home"));
+        let index_file = home.registry_index_file(&name);
+        std::fs::create_dir_all(index_file.parent().unwrap()).unwrap();
+        std::fs::write(&index_file, "registry entry exists").unwrap();
+        let registry = RegistryClient::with_home(
+            RegistryConfig {
+                // Unreachable: the second mirror must serve the archive.
+                registry: "http://127.0.0.1:9".into(),
+                index: String::new(),
+                symbols: None,
+                mirrors: vec![
+                    format!("file://{}", empty_mirror.display()),
+                    mirror.root().display().to_string(),
+                ],
+            },
+            home,
+        );
+        let destination = sandbox.path().join("source");
+
+        registry
+            .acquire_source_to(&name, &version, &checksum, &destination, &quiet_user_log())
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(destination.join("moon.mod")).unwrap(),
+            "name = \"test/module\"\nversion = \"1.2.3\"\n"
+        );
+    }
+
+    #[test]
+    fn index_falls_back_to_mirrors_when_the_registry_is_unreachable() {
+        let sandbox = tempfile::TempDir::new().unwrap();
+        let name: ModuleName = "test/module".into();
+
+        let empty_mirror = sandbox.path().join("empty-mirror");
+        let mirror = LocalRegistry::new(sandbox.path().join("mirror"));
+        let index_file = mirror.index_file(&name);
+        std::fs::create_dir_all(index_file.parent().unwrap()).unwrap();
+        std::fs::write(
+            &index_file,
+            r#"{"name":"test/module","version":"1.2.3","checksum":"abc123"}
+"#,
+        )
+        .unwrap();
+
+        let registry = RegistryClient::with_home(
+            RegistryConfig {
+                registry: "http://127.0.0.1:9".into(),
+                // Not a Git repository, so cloning the index fails.
+                index: sandbox.path().join("missing-index").display().to_string(),
+                symbols: None,
+                mirrors: vec![
+                    format!("file://{}", empty_mirror.display()),
+                    mirror.root().display().to_string(),
+                ],
+            },
+            MoonHomeLayout::new(sandbox.path().join("home")),
+        );
+
+        registry.sync(&quiet_user_log()).unwrap();
+        let versions = registry.all_versions_of(&name).unwrap();
+        assert!(versions.contains_key(&Version::new(1, 2, 3)));
+
+        // Later invocations read the index without syncing again.
+        let later = RegistryClient::with_home(
+            RegistryConfig::default(),
+            MoonHomeLayout::new(sandbox.path().join("home")),
+        );
+        let versions = later.all_versions_of(&name).unwrap();
+        assert!(versions.contains_key(&Version::new(1, 2, 3)));
+    }
+
+    #[test]
+    fn registry_config_fields_default_when_omitted() {
+        let config: RegistryConfig =
+            serde_json_lenient::from_str(r#"{"mirrors": ["/srv/mooncakes"]}"#).unwrap();
+        assert_eq!(config.registry, "https://mooncakes.io");
+        assert_eq!(config.mirrors, ["/srv/mooncakes"]);
+        assert!(config.symbols.is_none());
+    }
+
     #[test]
     fn downloaded_archive_checksum_mismatch_is_rejected() {
         let name: ModuleName = "test/module".into();
@@ -1336,6 +1671,7 @@ mod tests {
                 registry: String::new(),
                 index: String::new(),
                 symbols: None,
+                mirrors: Vec::new(),
             },
             home.clone(),
         );
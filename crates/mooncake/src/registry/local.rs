@@ -0,0 +1,291 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! A registry served from a plain directory.
+//!
+//! The directory uses the same layout as a hosted registry, so it can be
+//! produced by copying one or by publishing into it:
+//!
+//! ```text
+//! <root>/index/user/<username>/<module>.index   one JSON line per version
+//! <root>/user/<username>/<module>/<version>.zip  published source archives
+//! ```
+//!
+//! A local registry can be configured as the primary registry, as one of the
+//! mirrors, or used directly wherever a [`Registry`] is needed.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use moonutil::{resolution::ModuleName, user_log::UserLog};
+use semver::Version;
+
+use super::{Registry, RegistrySource, RegistryVersionInfo, client};
+use crate::zip_util::extract_zip_to_dir;
+
+pub struct LocalRegistry {
+    root: PathBuf,
+    cache: RefCell<HashMap<ModuleName, Arc<BTreeMap<Version, RegistryVersionInfo>>>>,
+}
+
+impl LocalRegistry {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub(crate) fn index_dir(&self) -> PathBuf {
+        self.root.join("index")
+    }
+
+    pub(crate) fn index_file(&self, name: &ModuleName) -> PathBuf {
+        self.index_dir()
+            .join("user")
+            .join(name.username.as_str())
+            .join(format!("{}.index", name.unqual))
+    }
+
+    pub(crate) fn archive_path(&self, name: &ModuleName, version: &Version) -> PathBuf {
+        self.root
+            .join("user")
+            .join(name.username.as_str())
+            .join(name.unqual.as_str())
+            .join(format!("{version}.zip"))
+    }
+}
+
+impl Registry for LocalRegistry {
+    fn all_versions_of(
+        &self,
+        name: &ModuleName,
+    ) -> anyhow::Result<Arc<BTreeMap<Version, RegistryVersionInfo>>> {
+        if let Some(versions) = self.cache.borrow().get(name) {
+            return Ok(Arc::clone(versions));
+        }
+        let versions = Arc::new(client::read_index_versions(&self.index_file(name), name)?);
+        self.cache
+            .borrow_mut()
+            .insert(name.clone(), Arc::clone(&versions));
+        Ok(versions)
+    }
+}
+
+impl RegistrySource for LocalRegistry {
+    fn acquire_source_to(
+        &self,
+        name: &ModuleName,
+        version: &Version,
+        expected_checksum: &str,
+        to: &Path,
+        _user_log: &UserLog,
+    ) -> anyhow::Result<()> {
+        let path = self.archive_path(name, version);
+        let archive = client::open_verified_archive(&path, expected_checksum)
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        let Some(archive) = archive else {
+            bail!(
+                "Checksum mismatch for {name}@{version}: `{}` does not match the index",
+                path.display()
+            );
+        };
+        if to.exists() {
+            std::fs::remove_dir_all(to)?;
+        }
+        std::fs::create_dir_all(to)?;
+        extract_zip_to_dir(to, archive)
+    }
+
+    fn source_archive_checksum(
+        &self,
+        name: &ModuleName,
+        version: &Version,
+    ) -> anyhow::Result<String> {
+        client::read_index_checksum(&self.index_file(name), version)
+    }
+}
+
+/// Interpret a configured registry location as a local directory, if it is a
+/// `file://` URL or an absolute path.
+pub(crate) fn local_registry_root(location: &str) -> Option<PathBuf> {
+    let path = match location.strip_prefix("file://") {
+        // `file:///C:/registry` names `C:/registry` on Windows.
+        #[cfg(windows)]
+        Some(path) => path
+            .strip_prefix('/')
+            .filter(|path| path.as_bytes().get(1) == Some(&b':'))
+            .unwrap_or(path),
+        #[cfg(not(windows))]
+        Some(path) => path,
+        None => location,
+    };
+    let path = Path::new(path);
+    path.is_absolute().then(|| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, sync::Arc};
+
+    use moonutil::{
+        manifest::MoonMod,
+        resolution::{ModuleSource, ResolvedModule},
+    };
+
+    use super::*;
+    use crate::resolver::{ResolveConfig, resolve_with_default_env_and_resolver};
+
+    fn user_log() -> UserLog {
+        UserLog::new(log::LevelFilter::Error)
+    }
+
+    fn archive(name: &str, version: &str) -> Vec<u8> {
+        let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        archive
+            .start_file("moon.mod", zip::write::FileOptions::default())
+            .unwrap();
+        write!(archive, "name = \"{name}\"\nversion = \"{version}\"\n").unwrap();
+        archive.finish().unwrap().into_inner()
+    }
+
+    /// Publish `name@version` into the registry at `root`.
+    fn publish(root: &Path, name: &str, version: &str, deps: &str) {
+        let registry = LocalRegistry::new(root);
+        let module: ModuleName = name.into();
+        let archive = archive(name, version);
+        let checksum = client::sha256_hex(&archive);
+
+        let archive_path = registry.archive_path(&module, &version.parse().unwrap());
+        std::fs::create_dir_all(archive_path.parent().unwrap()).unwrap();
+        std::fs::write(archive_path, archive).unwrap();
+
+        let index_file = registry.index_file(&module);
+        std::fs::create_dir_all(index_file.parent().unwrap()).unwrap();
+        let mut index = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(index_file)
+            .unwrap();
+        writeln!(
+            index,
+            r#"{{"name":"{name}","version":"{version}","deps":{deps},"checksum":"{checksum}"}}"#
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn local_registry_resolves_and_extracts_published_modules() {
+        let root = tempfile::TempDir::new().unwrap();
+        publish(root.path(), "alice/lib", "0.1.0", r#"{"bob/util":"1.0.0"}"#);
+        publish(root.path(), "bob/util", "1.0.0", "{}");
+        publish(root.path(), "bob/util", "1.1.0", "{}");
+        let registry = LocalRegistry::new(root.path());
+
+        let app = Arc::new(crate::registry::mock::create_mock_module(
+            "me/app",
+            "0.1.0",
+            [("alice/lib", "0.1.0")],
+        ));
+        let (roots, _) = ResolvedModule::only_one_module(
+            ModuleSource::from_local_module(&app, Path::new("/work/app")),
+            Arc::clone(&app) as Arc<MoonMod>,
+        );
+        let config = ResolveConfig {
+            registry: &registry,
+            inject_std: false,
+        };
+        let resolved = resolve_with_default_env_and_resolver(&config, roots, &user_log()).unwrap();
+        let mut modules = resolved
+            .all_modules()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>();
+        modules.sort();
+        assert_eq!(
+            modules,
+            [
+                "alice/lib@0.1.0",
+                "bob/util@1.0.0",
+                "me/app@0.1.0 (local /work/app)"
+            ]
+        );
+
+        let name: ModuleName = "bob/util".into();
+        let version = Version::new(1, 0, 0);
+        let checksum = registry.source_archive_checksum(&name, &version).unwrap();
+        let destination = root.path().join("extracted");
+        registry
+            .acquire_source_to(&name, &version, &checksum, &destination, &user_log())
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(destination.join("moon.mod")).unwrap(),
+            "name = \"bob/util\"\nversion = \"1.0.0\"\n"
+        );
+    }
+
+    #[test]
+    fn local_registry_rejects_an_archive_that_does_not_match_the_index() {
+        let root = tempfile::TempDir::new().unwrap();
+        publish(root.path(), "bob/util", "1.0.0", "{}");
+        let registry = LocalRegistry::new(root.path());
+        let name: ModuleName = "bob/util".into();
+        let version = Version::new(1, 0, 0);
+        let checksum = registry.source_archive_checksum(&name, &version).unwrap();
+        std::fs::write(
+            registry.archive_path(&name, &version),
+            archive("bob/util", "9.9.9"),
+        )
+        .unwrap();
+
+        let error = registry
+            .acquire_source_to(
+                &name,
+                &version,
+                &checksum,
+                &root.path().join("extracted"),
+                &user_log(),
+            )
+            .unwrap_err();
+        assert!(
+            format!("{error:#}").contains("Checksum mismatch"),
+            "{error:#}"
+        );
+    }
+
+    #[test]
+    fn registry_locations_name_local_directories() {
+        let root = tempfile::TempDir::new().unwrap();
+        let url = format!("file://{}", root.path().display());
+        #[cfg(not(windows))]
+        assert_eq!(local_registry_root(&url).as_deref(), Some(root.path()));
+        assert_eq!(
+            local_registry_root(&root.path().display().to_string()).as_deref(),
+            Some(root.path())
+        );
+        assert_eq!(local_registry_root("https://mooncakes.io"), None);
+    }
+}
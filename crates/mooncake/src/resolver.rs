@@ -30,11 +30,13 @@ use thiserror::Error;
 use crate::registry::Registry;
 
 pub(crate) mod env;
+pub(crate) mod explain;
 pub(crate) mod mvs;
 
 pub(crate) use mvs::MvsSolver;
 
 use self::env::ResolverEnv;
+use self::explain::RequirementTrace;
 
 /// Any error that may occur during dependency resolution.
 #[derive(Debug, Error)]
@@ -74,6 +76,19 @@ pub(crate) enum ResolverError {
     Other(#[source] anyhow::Error),
 }
 
+impl ResolverError {
+    /// The module this error is about, if it concerns a single module.
+    pub(crate) fn module(&self) -> Option<&ModuleName> {
+        match self {
+            ResolverError::ModuleMissing { dependency, .. }
+            | ResolverError::NoSatisfiedVersion { dependency, .. }
+            | ResolverError::LocalDepVersionMismatch { dependency, .. } => Some(dependency),
+            ResolverError::ConflictingVersions { module, .. } => Some(module),
+            ResolverError::CannotInjectCore(_) | ResolverError::Other(_) => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct VersionConflict {
     pub selected: ModuleSource,
@@ -243,23 +258,38 @@ pub(crate) fn resolve_with_default_env(
     root: ResolvedRootModules,
     user_log: &UserLog,
 ) -> Result<ResolvedEnv, ResolverErrors> {
+    resolve_with_default_env_traced(config, resolver, root, user_log).0
+}
+
+/// Like [`resolve_with_default_env`], but also returns every requirement the
+/// resolver gathered, whether or not resolution succeeded.
+fn resolve_with_default_env_traced(
+    config: &ResolveConfig,
+    resolver: &mut dyn Resolver,
+    root: ResolvedRootModules,
+    user_log: &UserLog,
+) -> (Result<ResolvedEnv, ResolverErrors>, RequirementTrace) {
     let mut env = env::ResolverEnv::new(config.registry);
     let mut res = ResolvedEnv::from_root_modules(root);
 
-    if config.inject_std {
-        inject_std(&mut res)
-            .map_err(|e| ResolverErrors(vec![ResolverError::CannotInjectCore(e)]))?;
+    if config.inject_std
+        && let Err(e) = inject_std(&mut res)
+    {
+        return (
+            Err(ResolverErrors(vec![ResolverError::CannotInjectCore(e)])),
+            RequirementTrace::default(),
+        );
     }
 
     let status = resolver.resolve(&mut env, &mut res, user_log);
+    let trace = env.take_trace();
     if env.any_errors() {
-        Err(ResolverErrors(env.into_errors()))
+        (Err(ResolverErrors(env.into_errors())), trace)
     } else {
         if !status {
             panic!("The resolver should not return `false` when no errors are found");
         }
-        assert_no_duplicate_module_names(&res)?;
-        Ok(res)
+        (assert_no_duplicate_module_names(&res).map(|()| res), trace)
     }
 }
 
@@ -284,3 +314,13 @@ pub(crate) fn resolve_with_default_env_and_resolver(
     let mut resolver = MvsSolver;
     resolve_with_default_env(config, &mut resolver, root, user_log)
 }
+
+/// Resolve with MVS and keep the requirement trace for explanations.
+pub(crate) fn resolve_with_trace(
+    config: &ResolveConfig,
+    root: ResolvedRootModules,
+    user_log: &UserLog,
+) -> (Result<ResolvedEnv, ResolverErrors>, RequirementTrace) {
+    let mut resolver = MvsSolver;
+    resolve_with_default_env_traced(config, &mut resolver, root, user_log)
+}
@@ -30,13 +30,14 @@ use semver::Version;
 
 use crate::registry::{Registry, RegistryVersionInfo};
 
-use super::ResolverError;
+use super::{ResolverError, explain::RequirementTrace};
 
 pub(crate) struct ResolverEnv<'a> {
     registry: &'a dyn Registry,
     errors: Vec<super::ResolverError>,
     local_module_cache: HashMap<PathBuf, Arc<MoonMod>>,
     stdlib: Option<Arc<MoonMod>>,
+    trace: RequirementTrace,
 }
 
 impl<'a> ResolverEnv<'a> {
@@ -46,6 +47,7 @@ impl<'a> ResolverEnv<'a> {
             errors: Vec::new(),
             local_module_cache: HashMap::new(),
             stdlib: None,
+            trace: RequirementTrace::default(),
         }
     }
 
@@ -53,6 +55,14 @@ impl<'a> ResolverEnv<'a> {
         self.errors
     }
 
+    pub(crate) fn take_trace(&mut self) -> RequirementTrace {
+        std::mem::take(&mut self.trace)
+    }
+
+    pub(crate) fn trace_mut(&mut self) -> &mut RequirementTrace {
+        &mut self.trace
+    }
+
     pub(crate) fn report_error(&mut self, error: super::ResolverError) {
         self.errors.push(error);
     }
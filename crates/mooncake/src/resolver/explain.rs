@@ -0,0 +1,410 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Explanations of why the resolver selected, or failed to select, a version.
+//!
+//! MVS gathers every requirement reachable from the roots, including those of
+//! versions that are later superseded, so the requirement that decides a
+//! selection may come from a module that is absent from the final graph.
+//! [`RequirementTrace`] records all of them while the resolver runs.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use moonutil::resolution::{DependencyKind, ModuleName, ModuleSource, ModuleSourceKind};
+use semver::Version;
+use serde::Serialize;
+
+use super::mvs::same_mvs_compatibility_set;
+
+/// One dependency requirement seen during resolution.
+#[derive(Debug, Clone)]
+pub(crate) struct Requirement {
+    pub(crate) dependant: ModuleSource,
+    pub(crate) dependency: ModuleName,
+    pub(crate) required: Option<Version>,
+    pub(crate) kind: DependencyKind,
+    /// The module the requirement resolved to, or `None` if it could not be
+    /// resolved.
+    pub(crate) resolved: Option<ModuleSource>,
+}
+
+/// Every requirement gathered by a resolver run.
+#[derive(Debug, Default)]
+pub(crate) struct RequirementTrace {
+    roots: Vec<ModuleSource>,
+    requirements: Vec<Requirement>,
+}
+
+/// Why each version of one module was selected.
+#[derive(Debug, Serialize)]
+pub(crate) struct Explanation {
+    pub(crate) module: String,
+    pub(crate) selected: Vec<SelectedVersion>,
+    /// Requirements that could not be resolved to any module.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) unresolved: Vec<RequirementPath>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SelectedVersion {
+    pub(crate) version: Version,
+    pub(crate) source: String,
+    pub(crate) reason: String,
+    pub(crate) requirements: Vec<RequirementPath>,
+}
+
+/// A requirement together with the shortest chain of requirements from a root
+/// module to the module that declares it.
+#[derive(Debug, Serialize)]
+pub(crate) struct RequirementPath {
+    pub(crate) required: Option<Version>,
+    pub(crate) path: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) binary: bool,
+    /// Set when the declaring module was itself replaced by a newer version
+    /// and does not appear in the final graph.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) superseded_by: Option<String>,
+}
+
+impl RequirementTrace {
+    pub(crate) fn add_root(&mut self, root: ModuleSource) {
+        self.roots.push(root);
+    }
+
+    pub(crate) fn add_requirement(&mut self, requirement: Requirement) {
+        self.requirements.push(requirement);
+    }
+
+    /// Explain the selection of `module`, optionally only for the compatible
+    /// set containing `version`. Returns `None` if nothing requires it.
+    pub(crate) fn explain(
+        &self,
+        module: &ModuleName,
+        version: Option<&Version>,
+    ) -> Option<Explanation> {
+        let requirements = self
+            .requirements
+            .iter()
+            .filter(|req| &req.dependency == module)
+            .collect::<Vec<_>>();
+        if requirements.is_empty() {
+            return None;
+        }
+
+        let paths = self.shortest_paths();
+        let describe = |req: &Requirement| RequirementPath {
+            required: req.required.clone(),
+            path: paths
+                .get(&req.dependant)
+                .map(|path| path.iter().map(ToString::to_string).collect())
+                .unwrap_or_else(|| vec![req.dependant.to_string()]),
+            binary: req.kind == DependencyKind::Binary,
+            superseded_by: self.superseded_by(&req.dependant),
+        };
+
+        let selected = self
+            .select(module)
+            .into_iter()
+            .filter(|selected| {
+                version.is_none_or(|v| same_mvs_compatibility_set(v, selected.version()))
+            })
+            .map(|selected| {
+                let workspace_member = self.roots.contains(&selected);
+                let mut requirements = requirements
+                    .iter()
+                    .filter(|req| {
+                        req.resolved.as_ref().is_some_and(|resolved| {
+                            workspace_member
+                                || same_mvs_compatibility_set(
+                                    resolved.version(),
+                                    selected.version(),
+                                )
+                        })
+                    })
+                    .copied()
+                    .collect::<Vec<_>>();
+                requirements.sort_by(|a, b| b.required.cmp(&a.required));
+                let reason = if workspace_member {
+                    "workspace member, which overrides every requirement"
+                } else if requirements.len() > 1 {
+                    "highest version required within its compatibility set"
+                } else {
+                    "only version required within its compatibility set"
+                };
+                SelectedVersion {
+                    version: selected.version().clone(),
+                    source: selected.source().to_string(),
+                    reason: reason.to_string(),
+                    requirements: requirements.into_iter().map(describe).collect(),
+                }
+            })
+            .collect();
+        let unresolved = requirements
+            .iter()
+            .filter(|req| req.resolved.is_none())
+            .filter(|req| {
+                version.is_none_or(|v| {
+                    req.required
+                        .as_ref()
+                        .is_none_or(|r| same_mvs_compatibility_set(v, r))
+                })
+            })
+            .map(|req| describe(req))
+            .collect();
+
+        Some(Explanation {
+            module: module.to_string(),
+            selected,
+            unresolved,
+        })
+    }
+
+    /// The versions of `module` MVS selects from the gathered requirements:
+    /// a workspace member if there is one, otherwise the highest resolved
+    /// version of each compatibility set.
+    fn select(&self, module: &ModuleName) -> Vec<ModuleSource> {
+        if let Some(root) = self.roots.iter().find(|root| root.name() == module) {
+            return vec![root.clone()];
+        }
+
+        let mut resolved = self
+            .requirements
+            .iter()
+            .filter(|req| &req.dependency == module)
+            .filter_map(|req| req.resolved.clone())
+            .collect::<Vec<_>>();
+        resolved.sort_by(|a, b| {
+            a.version()
+                .cmp(b.version())
+                .then_with(|| a.source().cmp(b.source()))
+        });
+        resolved.dedup();
+
+        let mut selected: Vec<ModuleSource> = Vec::new();
+        for source in resolved {
+            match selected.last_mut() {
+                Some(last) if same_mvs_compatibility_set(last.version(), source.version()) => {
+                    *last = source
+                }
+                _ => selected.push(source),
+            }
+        }
+        selected
+    }
+
+    fn superseded_by(&self, source: &ModuleSource) -> Option<String> {
+        if self.roots.contains(source) {
+            return None;
+        }
+        self.select(source.name())
+            .into_iter()
+            .find(|selected| {
+                selected != source
+                    && same_mvs_compatibility_set(selected.version(), source.version())
+            })
+            .map(|selected| selected.to_string())
+    }
+
+    /// The shortest requirement chain from any root to every reachable module.
+    fn shortest_paths(&self) -> HashMap<ModuleSource, Vec<ModuleSource>> {
+        let mut edges = HashMap::<&ModuleSource, Vec<&ModuleSource>>::new();
+        for req in &self.requirements {
+            if let Some(resolved) = &req.resolved {
+                edges.entry(&req.dependant).or_default().push(resolved);
+            }
+        }
+
+        let mut paths = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        for root in &self.roots {
+            if seen.insert(root) {
+                paths.insert(root.clone(), vec![root.clone()]);
+                queue.push_back(root);
+            }
+        }
+        while let Some(current) = queue.pop_front() {
+            let path = paths[current].clone();
+            for &next in edges.get(current).into_iter().flatten() {
+                if seen.insert(next) {
+                    let mut next_path = path.clone();
+                    next_path.push(next.clone());
+                    paths.insert(next.clone(), next_path);
+                    queue.push_back(next);
+                }
+            }
+        }
+        paths
+    }
+}
+
+pub(crate) fn render_explanation(explanation: &Explanation) -> String {
+    let mut out = String::new();
+    if explanation.selected.len() > 1 {
+        out.push_str(&format!(
+            "{} is selected at {} incompatible versions: {}\n",
+            explanation.module,
+            explanation.selected.len(),
+            explanation
+                .selected
+                .iter()
+                .map(|selected| selected.version.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    for selected in &explanation.selected {
+        out.push_str(&format!("{}@{}", explanation.module, selected.version));
+        if selected.source != ModuleSourceKind::Registry.to_string() {
+            out.push_str(&format!(" ({})", selected.source));
+        }
+        out.push_str(&format!(": {}\n", selected.reason));
+        render_requirements(&mut out, &selected.requirements);
+    }
+    if !explanation.unresolved.is_empty() {
+        out.push_str(&format!(
+            "{}: requirements that could not be resolved\n",
+            explanation.module
+        ));
+        render_requirements(&mut out, &explanation.unresolved);
+    }
+    out
+}
+
+fn render_requirements(out: &mut String, requirements: &[RequirementPath]) {
+    for (idx, req) in requirements.iter().enumerate() {
+        let branch = if idx + 1 == requirements.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        let required = req
+            .required
+            .as_ref()
+            .map_or_else(|| "any version".to_string(), ToString::to_string);
+        out.push_str(&format!(
+            "{branch} {required} required by {}",
+            req.path.join(" -> ")
+        ));
+        if req.binary {
+            out.push_str(" (bin-deps)");
+        }
+        if let Some(replacement) = &req.superseded_by {
+            out.push_str(&format!(" (superseded by {replacement})"));
+        }
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Arc};
+
+    use expect_test::expect;
+    use moonutil::{
+        manifest::MoonMod,
+        resolution::{ModuleSource, ResolvedModule},
+        user_log::UserLog,
+    };
+
+    use super::*;
+    use crate::{
+        registry::mock::{MockRegistry, create_mock_module},
+        resolver::{ResolveConfig, resolve_with_trace},
+    };
+
+    fn trace(registry: &MockRegistry, deps: &[(&str, &str)]) -> RequirementTrace {
+        let module = Arc::new(create_mock_module("me/app", "0.1.0", deps.iter().copied()));
+        let (roots, _) = ResolvedModule::only_one_module(
+            ModuleSource::from_local_module(&module, Path::new("/work/app")),
+            Arc::clone(&module) as Arc<MoonMod>,
+        );
+        let config = ResolveConfig {
+            registry,
+            inject_std: false,
+        };
+        resolve_with_trace(&config, roots, &UserLog::new(log::LevelFilter::Error)).1
+    }
+
+    fn render(trace: &RequirementTrace, module: &str) -> String {
+        render_explanation(&trace.explain(&module.into(), None).unwrap())
+    }
+
+    #[test]
+    fn explains_a_requirement_from_a_superseded_module() {
+        let mut registry = MockRegistry::new();
+        registry
+            .add_module_full("alice/lib", "0.1.0", [("bob/util", "1.5.0")])
+            .add_module_full("alice/lib", "0.2.0", [("bob/util", "1.0.0")])
+            .add_module_full("carol/x", "0.1.0", [("alice/lib", "0.2.0")])
+            .add_module_full("bob/util", "1.0.0", [])
+            .add_module_full("bob/util", "1.5.0", []);
+        let trace = trace(&registry, &[("alice/lib", "0.1.0"), ("carol/x", "0.1.0")]);
+
+        expect![[r#"
+            bob/util@1.5.0: highest version required within its compatibility set
+            ├─ 1.5.0 required by me/app@0.1.0 (local /work/app) -> alice/lib@0.1.0 (superseded by alice/lib@0.2.0)
+            └─ 1.0.0 required by me/app@0.1.0 (local /work/app) -> carol/x@0.1.0 -> alice/lib@0.2.0
+        "#]]
+        .assert_eq(&render(&trace, "bob/util"));
+        assert!(trace.explain(&"dave/none".into(), None).is_none());
+    }
+
+    #[test]
+    fn explains_both_paths_of_incompatible_versions() {
+        let mut registry = MockRegistry::new();
+        registry
+            .add_module_full("alice/lib", "0.1.0", [("bob/util", "2.0.0")])
+            .add_module_full("bob/util", "1.0.0", [])
+            .add_module_full("bob/util", "2.0.0", []);
+        let trace = trace(&registry, &[("alice/lib", "0.1.0"), ("bob/util", "1.0.0")]);
+
+        expect![[r#"
+            bob/util is selected at 2 incompatible versions: 1.0.0, 2.0.0
+            bob/util@1.0.0: only version required within its compatibility set
+            └─ 1.0.0 required by me/app@0.1.0 (local /work/app)
+            bob/util@2.0.0: only version required within its compatibility set
+            └─ 2.0.0 required by me/app@0.1.0 (local /work/app) -> alice/lib@0.1.0
+        "#]]
+        .assert_eq(&render(&trace, "bob/util"));
+
+        let only_v2 = trace
+            .explain(&"bob/util".into(), Some(&Version::new(2, 1, 0)))
+            .unwrap();
+        assert_eq!(only_v2.selected.len(), 1);
+        assert_eq!(only_v2.selected[0].version, Version::new(2, 0, 0));
+    }
+
+    #[test]
+    fn explains_unresolved_requirements() {
+        let mut registry = MockRegistry::new();
+        registry
+            .add_module_full("alice/lib", "0.1.0", [("bob/util", "1.9.0")])
+            .add_module_full("bob/util", "1.0.0", []);
+        let trace = trace(&registry, &[("alice/lib", "0.1.0"), ("bob/util", "1.0.0")]);
+
+        expect![[r#"
+            bob/util@1.0.0: only version required within its compatibility set
+            └─ 1.0.0 required by me/app@0.1.0 (local /work/app)
+            bob/util: requirements that could not be resolved
+            └─ 1.9.0 required by me/app@0.1.0 (local /work/app) -> alice/lib@0.1.0
+        "#]]
+        .assert_eq(&render(&trace, "bob/util"));
+    }
+}
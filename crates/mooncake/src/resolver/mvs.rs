@@ -34,7 +34,7 @@ use moonutil::{
 };
 use semver::Version;
 
-use super::{Resolver, ResolverError, env::ResolverEnv};
+use super::{Resolver, ResolverError, env::ResolverEnv, explain::Requirement};
 
 type WorkspaceRoots = HashMap<ModuleName, (ModuleSource, Arc<MoonMod>)>;
 
@@ -65,7 +65,7 @@ fn mvs_requirement_matches(required: &Version, candidate: &Version) -> bool {
     }
 }
 
-fn same_mvs_compatibility_set(lhs: &Version, rhs: &Version) -> bool {
+pub(crate) fn same_mvs_compatibility_set(lhs: &Version, rhs: &Version) -> bool {
     (lhs.major < 2 && rhs.major < 2) || lhs.major == rhs.major
 }
 
@@ -272,6 +272,9 @@ fn mvs_resolve(env: &mut ResolverEnv, res: &mut ResolvedEnv, user_log: &UserLog)
             Arc::clone(res.module_info(id)),
         )
     }));
+    for &id in res.input_module_ids() {
+        env.trace_mut().add_root(res.module_source(id).clone());
+    }
     if log::log_enabled!(log::Level::Debug) {
         for &id in res.input_module_ids() {
             log::debug!("MVS root item: {}", res.module_source(id));
@@ -282,25 +285,36 @@ fn mvs_resolve(env: &mut ResolverEnv, res: &mut ResolvedEnv, user_log: &UserLog)
     // Do a DFS in the graph
     while let Some((source, module)) = working_list.pop() {
         log::debug!("-- Solving for {}", source);
+        let regular_deps = module
+            .deps
+            .iter()
+            .map(|(k, v)| (k, v, DependencyKind::Regular));
         let bin_deps = root_sources.contains(&source).then(|| {
             module
                 .bin_deps
                 .iter()
                 .flat_map(|m| m.iter())
-                .map(|(k, v)| (k, &v.common))
+                .map(|(k, v)| (k, &v.common, DependencyKind::Binary))
         });
-        let all_deps = module.deps.iter().chain(bin_deps.into_iter().flatten());
-        for (name, req) in all_deps {
-            let pkg_name = name.as_str().into();
-
-            let (ms, module) =
-                match resolve_pkg(req, &source, env, &workspace_roots, &pkg_name, user_log) {
-                    Ok(value) => value,
-                    Err(e) => {
-                        env.report_error(e);
-                        continue;
-                    }
-                };
+        let all_deps = regular_deps.chain(bin_deps.into_iter().flatten());
+        for (name, req, kind) in all_deps {
+            let pkg_name: ModuleName = name.as_str().into();
+
+            let resolved = resolve_pkg(req, &source, env, &workspace_roots, &pkg_name, user_log);
+            env.trace_mut().add_requirement(Requirement {
+                dependant: source.clone(),
+                dependency: pkg_name.clone(),
+                required: req.version().cloned(),
+                kind,
+                resolved: resolved.as_ref().ok().map(|(ms, _)| ms.clone()),
+            });
+            let (ms, module) = match resolved {
+                Ok(value) => value,
+                Err(e) => {
+                    env.report_error(e);
+                    continue;
+                }
+            };
 
             // Add module to working list
             if visited.insert(ms.clone()) {
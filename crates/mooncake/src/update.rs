@@ -715,6 +715,68 @@ pub(crate) fn sync(
     )
 }
 
+/// Replace the registry index with a copy of the index of a local registry
+/// directory, for when the registry itself cannot be reached.
+///
+/// The copy is not a Git checkout, so the next successful sync with the
+/// registry clones the index again.
+pub(crate) fn sync_from_local_index(
+    home: &MoonHomeLayout,
+    index_dir: &Path,
+    user_log: &UserLog,
+) -> anyhow::Result<()> {
+    let registry_dir = home.registry_dir();
+    let target_dir = home.registry_index_dir();
+    std::fs::create_dir_all(&registry_dir)
+        .with_context(|| format!("failed to create `{}`", registry_dir.display()))?;
+    let _lock = lock_directory(&registry_dir, user_log)
+        .context("failed to lock registry update directory")?;
+
+    let tmp_dir = unique_sibling_dir(&registry_dir, ".registry-index.tmp")
+        .context("failed to create temp directory for the registry index")?;
+    if let Err(e) = copy_dir(index_dir, &tmp_dir) {
+        let _ = std::fs::remove_dir_all(&tmp_dir);
+        return Err(e.context(format!("failed to copy `{}`", index_dir.display())));
+    }
+
+    if target_dir.exists() {
+        let backup_dir = unique_sibling_dir(&registry_dir, ".registry-index.old")
+            .context("failed to create backup dir")?;
+        std::fs::rename(&target_dir, &backup_dir)
+            .context("failed to move existing registry index to backup")?;
+
+        if let Err(e) = std::fs::rename(&tmp_dir, &target_dir) {
+            let _ = std::fs::rename(&backup_dir, &target_dir);
+            let _ = std::fs::remove_dir_all(&tmp_dir);
+            return Err(anyhow::Error::from(e).context("failed to replace registry index"));
+        }
+
+        if let Err(e) = std::fs::remove_dir_all(&backup_dir) {
+            user_log.warn(format!(
+                "failed to remove old registry index at `{}`: {e}",
+                backup_dir.display()
+            ));
+        }
+    } else {
+        std::fs::rename(&tmp_dir, &target_dir)
+            .context("failed to move registry index into place")?;
+    }
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let dest = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
 fn registry_identity(index_url: &str, symbols_url: &str) -> String {
     let mut identity = Sha256::new();
     identity.update(index_url.as_bytes());
@@ -792,6 +854,7 @@ mod tests {
                 registry: index.clone(),
                 index,
                 symbols: None,
+                mirrors: Vec::new(),
             },
         )
     }
@@ -871,6 +934,7 @@ mod tests {
                 registry: index.clone(),
                 index,
                 symbols: None,
+                mirrors: Vec::new(),
             },
         )
     }
@@ -0,0 +1,573 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Project-local copies of registry dependencies for offline builds.
+//!
+//! `moon vendor` extracts every resolved registry module into
+//! `vendor/<user>/<module>/<version>` and indexes them in
+//! `vendor/moon.vendor.json`, together with the checksum of the archive each
+//! one came from and the dependencies the registry index declares for it.
+//! While that index exists, [`Vendor`] stands in for the registry during
+//! resolution and the build reads sources from the vendor directory, so neither
+//! the registry index nor the network is consulted.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, bail};
+use indexmap::IndexMap;
+use moonutil::{
+    constants::{MOON_VENDOR_DIR, MOON_VENDOR_MANIFEST},
+    dependency::SourceDependencyInfo,
+    manifest::read_module_desc_file_in_dir,
+    resolution::{ModuleName, ModuleSource, ModuleSourceKind, ResolvedEnv},
+    user_log::UserLog,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::registry::{Registry, RegistrySource, RegistryVersionInfo};
+
+const VENDOR_MANIFEST_VERSION: u32 = 1;
+
+/// Written into each vendored module directory; holds the archive checksum
+/// the directory was extracted from.
+pub(crate) const VENDORED_CHECKSUM_FILE: &str = ".moon-vendor-checksum";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VendorManifest {
+    version: u32,
+    #[serde(default)]
+    modules: Vec<VendoredModule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct VendoredModule {
+    name: String,
+    version: Version,
+    /// SHA-256 of the published source archive, as recorded in the index.
+    checksum: String,
+    /// The dependencies the registry index declares for this version.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    deps: IndexMap<String, SourceDependencyInfo>,
+}
+
+/// The vendored modules of a project.
+pub(crate) struct Vendor {
+    dir: PathBuf,
+    modules: HashMap<ModuleName, BTreeMap<Version, VendoredModule>>,
+    versions: HashMap<ModuleName, Arc<BTreeMap<Version, RegistryVersionInfo>>>,
+}
+
+impl Vendor {
+    /// Load the vendor index of the project at `project_dir`, if it has one.
+    pub(crate) fn load(project_dir: &Path) -> anyhow::Result<Option<Self>> {
+        let dir = project_dir.join(MOON_VENDOR_DIR);
+        let Some(manifest) = read_manifest(&dir)? else {
+            return Ok(None);
+        };
+        if manifest.version != VENDOR_MANIFEST_VERSION {
+            bail!(
+                "`{}` has unsupported version {}; run `moon vendor` to regenerate it",
+                dir.join(MOON_VENDOR_MANIFEST).display(),
+                manifest.version
+            );
+        }
+
+        let mut modules = HashMap::<ModuleName, BTreeMap<_, _>>::new();
+        for module in manifest.modules {
+            modules
+                .entry(module.name.as_str().into())
+                .or_default()
+                .insert(module.version.clone(), module);
+        }
+        let versions = modules
+            .iter()
+            .map(|(name, versions)| {
+                let info = versions
+                    .iter()
+                    .map(|(version, module)| {
+                        (
+                            version.clone(),
+                            RegistryVersionInfo {
+                                deps: module.deps.clone(),
+                            },
+                        )
+                    })
+                    .collect();
+                (name.clone(), Arc::new(info))
+            })
+            .collect();
+
+        Ok(Some(Self {
+            dir,
+            modules,
+            versions,
+        }))
+    }
+
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The directory holding the vendored source of `module`, along with the
+    /// checksum it was recorded with.
+    pub(crate) fn source_dir(&self, module: &ModuleSource) -> anyhow::Result<(PathBuf, &str)> {
+        let vendored = self.get(module.name(), module.version())?;
+        Ok((
+            self.dir
+                .join(vendored_path(module.name(), module.version())),
+            &vendored.checksum,
+        ))
+    }
+
+    fn get(&self, name: &ModuleName, version: &Version) -> anyhow::Result<&VendoredModule> {
+        self.modules
+            .get(name)
+            .and_then(|versions| versions.get(version))
+            .with_context(|| {
+                format!(
+                    "`{name}@{version}` is not vendored; run `moon vendor` to update `{}`",
+                    self.dir.display()
+                )
+            })
+    }
+}
+
+impl Registry for Vendor {
+    fn all_versions_of(
+        &self,
+        name: &ModuleName,
+    ) -> anyhow::Result<Arc<BTreeMap<Version, RegistryVersionInfo>>> {
+        self.versions.get(name).map(Arc::clone).with_context(|| {
+            format!(
+                "`{name}` is not vendored; run `moon vendor` to update `{}`",
+                self.dir.display()
+            )
+        })
+    }
+}
+
+impl RegistrySource for Vendor {
+    fn acquire_source_to(
+        &self,
+        name: &ModuleName,
+        version: &Version,
+        expected_checksum: &str,
+        to: &Path,
+        _user_log: &UserLog,
+    ) -> anyhow::Result<()> {
+        let vendored = self.get(name, version)?;
+        if vendored.checksum != expected_checksum {
+            bail!("vendored source of `{name}@{version}` does not match the expected checksum");
+        }
+        copy_dir(&self.dir.join(vendored_path(name, version)), to)
+    }
+
+    fn source_archive_checksum(
+        &self,
+        name: &ModuleName,
+        version: &Version,
+    ) -> anyhow::Result<String> {
+        Ok(self.get(name, version)?.checksum.clone())
+    }
+}
+
+/// What [`vendor_resolved`] changed in the vendor directory.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct VendorSummary {
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) unchanged: usize,
+}
+
+/// Copy every registry module of `resolved` into `<project_dir>/vendor` and
+/// rewrite the vendor index.
+///
+/// Modules that are already vendored with the same archive checksum are kept
+/// as they are. Directories of modules that are no longer resolved are
+/// removed; files in the vendor directory that the index does not know about
+/// are left alone.
+pub(crate) fn vendor_resolved<R: Registry + RegistrySource>(
+    registry: &R,
+    resolved: &ResolvedEnv,
+    project_dir: &Path,
+    user_log: &UserLog,
+) -> anyhow::Result<VendorSummary> {
+    let dir = project_dir.join(MOON_VENDOR_DIR);
+    let previous = read_manifest(&dir)?.unwrap_or_default();
+
+    let mut modules = resolved
+        .all_modules()
+        .filter_map(|module| match module.source() {
+            ModuleSourceKind::Registry if !module.is_core() => Some(Ok(module)),
+            ModuleSourceKind::Git(url) => Some(Err(anyhow::anyhow!(
+                "cannot vendor `{}` from `{url}`: git dependencies are not supported",
+                module.name()
+            ))),
+            _ => None,
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    modules.sort();
+
+    let mut summary = VendorSummary::default();
+    let mut vendored = Vec::with_capacity(modules.len());
+    for module in modules {
+        let (name, version) = (module.name(), module.version());
+        let checksum = registry.source_archive_checksum(name, version)?;
+        let deps = registry
+            .all_versions_of(name)?
+            .get(version)
+            .map(|info| info.deps.clone())
+            .unwrap_or_default();
+        let target = dir.join(vendored_path(name, version));
+
+        let up_to_date =
+            previous.modules.iter().any(|m| {
+                m.name == name.to_string() && &m.version == version && m.checksum == checksum
+            }) && std::fs::read_to_string(target.join(VENDORED_CHECKSUM_FILE))
+                .is_ok_and(|recorded| recorded == checksum);
+        if up_to_date {
+            summary.unchanged += 1;
+        } else {
+            extract_module(registry, module, &checksum, &target, user_log)?;
+            summary.added.push(format!("{name}@{version}"));
+        }
+
+        vendored.push(VendoredModule {
+            name: name.to_string(),
+            version: version.clone(),
+            checksum,
+            deps,
+        });
+    }
+
+    for stale in &previous.modules {
+        let still_vendored = vendored
+            .iter()
+            .any(|m| m.name == stale.name && m.version == stale.version);
+        if still_vendored {
+            continue;
+        }
+        let name: ModuleName = stale.name.as_str().into();
+        let path = dir.join(vendored_path(&name, &stale.version));
+        match std::fs::remove_dir_all(&path) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to remove `{}`", path.display()));
+            }
+        }
+        remove_empty_parents(&dir, &path);
+        summary
+            .removed
+            .push(format!("{}@{}", stale.name, stale.version));
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    let manifest = VendorManifest {
+        version: VENDOR_MANIFEST_VERSION,
+        modules: vendored,
+    };
+    let mut rendered =
+        serde_json::to_string_pretty(&manifest).expect("vendor indexes are always serializable");
+    rendered.push('\n');
+    let manifest_path = dir.join(MOON_VENDOR_MANIFEST);
+    std::fs::write(&manifest_path, rendered)
+        .with_context(|| format!("failed to write `{}`", manifest_path.display()))?;
+
+    Ok(summary)
+}
+
+fn read_manifest(dir: &Path) -> anyhow::Result<Option<VendorManifest>> {
+    let path = dir.join(MOON_VENDOR_MANIFEST);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to read `{}`", path.display()));
+        }
+    };
+    let manifest = serde_json_lenient::from_str(&content)
+        .with_context(|| format!("failed to parse `{}`", path.display()))?;
+    Ok(Some(manifest))
+}
+
+/// The location of a vendored module relative to the vendor directory. Slashes
+/// in the module part of the name are replaced with `+`, as in `.mooncakes`.
+fn vendored_path(name: &ModuleName, version: &Version) -> PathBuf {
+    PathBuf::from(name.username.as_str())
+        .join(name.unqual.replace('/', "+"))
+        .join(version.to_string())
+}
+
+fn extract_module(
+    registry: &dyn RegistrySource,
+    module: &ModuleSource,
+    checksum: &str,
+    target: &Path,
+    user_log: &UserLog,
+) -> anyhow::Result<()> {
+    let parent = target.parent().expect("vendored modules have a parent");
+    std::fs::create_dir_all(parent)?;
+    let staging = tempfile::Builder::new()
+        .prefix(".vendor-")
+        .tempdir_in(parent)
+        .with_context(|| format!("unable to create a staging directory for `{module}`"))?;
+    registry.acquire_source_to(
+        module.name(),
+        module.version(),
+        checksum,
+        staging.path(),
+        user_log,
+    )?;
+
+    let manifest = read_module_desc_file_in_dir(staging.path())?;
+    if manifest.name != module.name().to_string()
+        || manifest.version.as_ref() != Some(module.version())
+    {
+        bail!("registry source for `{module}` contains a mismatched module manifest");
+    }
+    std::fs::write(staging.path().join(VENDORED_CHECKSUM_FILE), checksum)?;
+
+    match std::fs::remove_dir_all(target) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to replace `{}`", target.display()));
+        }
+    }
+    let staging = staging.into_path();
+    if let Err(error) = std::fs::rename(&staging, target) {
+        let _ = std::fs::remove_dir_all(&staging);
+        return Err(error)
+            .with_context(|| format!("failed to move `{module}` into `{}`", target.display()));
+    }
+    Ok(())
+}
+
+fn remove_empty_parents(root: &Path, path: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if dir == root || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(from)?;
+        let dest = to.join(relative);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else if relative != Path::new(VENDORED_CHECKSUM_FILE) {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use moonutil::{
+        manifest::MoonMod,
+        resolution::{ModuleSource, ResolvedModule},
+    };
+
+    use super::*;
+    use crate::{
+        dependency_source,
+        registry::mock::{MockRegistry, create_mock_module},
+        resolver::{ResolveConfig, resolve_with_default_env_and_resolver},
+    };
+
+    const CHECKSUM: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    struct TestRegistry {
+        index: MockRegistry,
+        acquisitions: AtomicUsize,
+    }
+
+    impl Registry for TestRegistry {
+        fn all_versions_of(
+            &self,
+            name: &ModuleName,
+        ) -> anyhow::Result<Arc<BTreeMap<Version, RegistryVersionInfo>>> {
+            self.index.all_versions_of(name)
+        }
+    }
+
+    impl RegistrySource for TestRegistry {
+        fn acquire_source_to(
+            &self,
+            name: &ModuleName,
+            version: &Version,
+            _expected_checksum: &str,
+            to: &Path,
+            _user_log: &UserLog,
+        ) -> anyhow::Result<()> {
+            self.acquisitions.fetch_add(1, Ordering::SeqCst);
+            std::fs::create_dir_all(to)?;
+            std::fs::write(
+                to.join("moon.mod"),
+                format!("name = \"{name}\"\nversion = \"{version}\"\n"),
+            )?;
+            Ok(())
+        }
+
+        fn source_archive_checksum(
+            &self,
+            _name: &ModuleName,
+            _version: &Version,
+        ) -> anyhow::Result<String> {
+            Ok(CHECKSUM.to_string())
+        }
+    }
+
+    fn user_log() -> UserLog {
+        UserLog::new(log::LevelFilter::Error)
+    }
+
+    fn resolve(registry: &dyn Registry, deps: &[(&str, &str)]) -> ResolvedEnv {
+        let module = Arc::new(create_mock_module("me/app", "0.1.0", deps.iter().copied()));
+        let (roots, _) = ResolvedModule::only_one_module(
+            ModuleSource::from_local_module(&module, Path::new("/work/app")),
+            Arc::clone(&module) as Arc<MoonMod>,
+        );
+        let config = ResolveConfig {
+            registry,
+            inject_std: false,
+        };
+        resolve_with_default_env_and_resolver(&config, roots, &user_log()).unwrap()
+    }
+
+    fn test_registry() -> TestRegistry {
+        let mut index = MockRegistry::new();
+        index
+            .add_module_full("alice/lib", "0.1.0", [("bob/deep/util", "1.0.0")])
+            .add_module_full("bob/deep/util", "1.0.0", [])
+            .add_module_full("bob/deep/util", "1.2.0", [])
+            .add_module_full("carol/old", "0.1.0", []);
+        TestRegistry {
+            index,
+            acquisitions: AtomicUsize::new(0),
+        }
+    }
+
+    #[test]
+    fn vendored_project_resolves_and_builds_without_the_registry() {
+        let project = tempfile::TempDir::new().unwrap();
+        let registry = test_registry();
+        let resolved = resolve(&registry, &[("alice/lib", "0.1.0")]);
+
+        let summary = vendor_resolved(&registry, &resolved, project.path(), &user_log()).unwrap();
+        assert_eq!(summary.added, ["alice/lib@0.1.0", "bob/deep/util@1.0.0"]);
+        let vendor_dir = project.path().join(MOON_VENDOR_DIR);
+        assert!(vendor_dir.join("bob/deep+util/1.0.0/moon.mod").exists());
+
+        let vendor = Vendor::load(project.path()).unwrap().unwrap();
+        let offline = resolve(&vendor, &[("alice/lib", "0.1.0")]);
+        let mut modules = offline
+            .all_modules()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>();
+        modules.sort();
+        assert_eq!(
+            modules,
+            [
+                "alice/lib@0.1.0",
+                "bob/deep/util@1.0.0",
+                "me/app@0.1.0 (local /work/app)"
+            ]
+        );
+
+        let paths = dependency_source::vendored(&vendor)
+            .ensure(&vendor, &offline, true, &user_log())
+            .unwrap();
+        let (lib, _) = offline
+            .all_modules_and_id()
+            .find(|(_, m)| m.name().to_string() == "alice/lib")
+            .unwrap();
+        assert_eq!(paths[lib], vendor_dir.join("alice/lib/0.1.0"));
+
+        let error = resolve_error(&vendor, &[("carol/old", "0.1.0")]);
+        assert!(error.contains("`carol/old`"), "{error}");
+    }
+
+    fn resolve_error(registry: &dyn Registry, deps: &[(&str, &str)]) -> String {
+        let module = Arc::new(create_mock_module("me/app", "0.1.0", deps.iter().copied()));
+        let (roots, _) = ResolvedModule::only_one_module(
+            ModuleSource::from_local_module(&module, Path::new("/work/app")),
+            Arc::clone(&module) as Arc<MoonMod>,
+        );
+        let config = ResolveConfig {
+            registry,
+            inject_std: false,
+        };
+        let error = resolve_with_default_env_and_resolver(&config, roots, &user_log()).unwrap_err();
+        format!("{error:#}")
+    }
+
+    #[test]
+    fn revendoring_keeps_unchanged_modules_and_removes_stale_ones() {
+        let project = tempfile::TempDir::new().unwrap();
+        let registry = test_registry();
+        let first = resolve(&registry, &[("alice/lib", "0.1.0"), ("carol/old", "0.1.0")]);
+        vendor_resolved(&registry, &first, project.path(), &user_log()).unwrap();
+        assert_eq!(registry.acquisitions.load(Ordering::SeqCst), 3);
+
+        let second = resolve(&registry, &[("alice/lib", "0.1.0")]);
+        let summary = vendor_resolved(&registry, &second, project.path(), &user_log()).unwrap();
+        assert_eq!(
+            summary,
+            VendorSummary {
+                added: vec![],
+                removed: vec!["carol/old@0.1.0".to_string()],
+                unchanged: 2,
+            }
+        );
+        assert_eq!(registry.acquisitions.load(Ordering::SeqCst), 3);
+        let vendor_dir = project.path().join(MOON_VENDOR_DIR);
+        assert!(!vendor_dir.join("carol").exists());
+
+        // A vendored directory that lost its checksum is rejected by builds.
+        std::fs::remove_file(
+            vendor_dir
+                .join("alice/lib/0.1.0")
+                .join(VENDORED_CHECKSUM_FILE),
+        )
+        .unwrap();
+        let vendor = Vendor::load(project.path()).unwrap().unwrap();
+        let error = dependency_source::vendored(&vendor)
+            .ensure(&vendor, &second, false, &user_log())
+            .unwrap_err();
+        assert!(
+            format!("{error:#}").contains("run `moon vendor`"),
+            "{error:#}"
+        );
+    }
+}
@@ -190,6 +190,50 @@ impl FromStr for TestIndexRange {
     }
 }
 
+/// One of `count` disjoint slices of the selected tests, as given to
+/// `moon test --shard K/N`. `index` is 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestShard {
+    pub index: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, thiserror::Error, Clone)]
+pub enum TestShardParseError {
+    #[error("expected `K/N`, e.g. `1/4`")]
+    MissingSlash,
+    #[error("invalid number `{0}`")]
+    InvalidNumber(String),
+    #[error("shard count must be at least 1")]
+    ZeroCount,
+    #[error("shard index must be between 1 and {0}")]
+    IndexOutOfRange(u32),
+}
+
+impl FromStr for TestShard {
+    type Err = TestShardParseError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (index, count) = input
+            .trim()
+            .split_once('/')
+            .ok_or(TestShardParseError::MissingSlash)?;
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u32>()
+                .map_err(|_| TestShardParseError::InvalidNumber(s.to_string()))
+        };
+        let (index, count) = (parse(index)?, parse(count)?);
+        if count == 0 {
+            return Err(TestShardParseError::ZeroCount);
+        }
+        if index == 0 || index > count {
+            return Err(TestShardParseError::IndexOutOfRange(count));
+        }
+        Ok(Self { index, count })
+    }
+}
+
 fn parse_index_bound(
     s: &str,
     empty_error: TestIndexRangeParseError,
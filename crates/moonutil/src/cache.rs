@@ -22,11 +22,13 @@
 //! may choose their own representations without changing the CLI contract.
 
 use std::{
+    ffi::OsString,
     io::Write,
     path::{Path, PathBuf},
 };
 
-use anyhow::bail;
+use anyhow::{Context, bail};
+use serde::Deserialize;
 
 const OWNERSHIP_MARKER: &str = ".moon-cache";
 
@@ -114,6 +116,135 @@ pub fn resolve_cache_root(kind: CacheKind) -> anyhow::Result<CacheRoot> {
     }
 }
 
+/// Environment variable selecting the remote build-artifact cache.
+///
+/// The value is an `http://`, `https://`, or `file://` URL, or `off`.
+pub const REMOTE_BUILD_CACHE_ENV: &str = "MOON_BUILD_CACHE_REMOTE";
+
+/// Environment variable selecting [`RemoteCacheMode`] for the remote cache.
+pub const REMOTE_BUILD_CACHE_MODE_ENV: &str = "MOON_BUILD_CACHE_REMOTE_MODE";
+
+/// Whether this client may publish to a remote build-artifact cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RemoteCacheMode {
+    #[default]
+    ReadWrite,
+    /// Download results but never upload them, for clients that are not
+    /// trusted to populate the shared cache.
+    ReadOnly,
+}
+
+impl RemoteCacheMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read-write" => Some(Self::ReadWrite),
+            "read-only" => Some(Self::ReadOnly),
+            _ => None,
+        }
+    }
+}
+
+/// Where a remote build-artifact cache lives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteCacheLocation {
+    /// Base URL of an HTTP server.
+    Http(String),
+    /// A directory with the same layout as the HTTP namespace.
+    Directory(PathBuf),
+}
+
+/// A configured remote tier of the build-artifact cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteCacheConfig {
+    pub location: RemoteCacheLocation,
+    pub mode: RemoteCacheMode,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MoonHomeConfig {
+    #[serde(default, rename = "build-cache")]
+    build_cache: Option<BuildCacheConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildCacheConfig {
+    remote: Option<String>,
+    #[serde(default)]
+    mode: Option<RemoteCacheMode>,
+}
+
+/// Resolve the remote build-artifact cache.
+///
+/// `MOON_BUILD_CACHE_REMOTE` and `MOON_BUILD_CACHE_REMOTE_MODE` take precedence
+/// over the `build-cache` object of `$MOON_HOME/config.json`. No remote tier is
+/// used unless one of them names a URL.
+pub fn resolve_remote_build_cache() -> anyhow::Result<Option<RemoteCacheConfig>> {
+    resolve_remote_build_cache_from(
+        std::env::var_os(REMOTE_BUILD_CACHE_ENV),
+        std::env::var_os(REMOTE_BUILD_CACHE_MODE_ENV),
+        &crate::MOON_HOME.config_path(),
+    )
+}
+
+fn resolve_remote_build_cache_from(
+    url: Option<OsString>,
+    mode: Option<OsString>,
+    config_path: &Path,
+) -> anyhow::Result<Option<RemoteCacheConfig>> {
+    let config = match std::fs::read_to_string(config_path) {
+        Ok(contents) => serde_json_lenient::from_str::<MoonHomeConfig>(&contents)
+            .with_context(|| format!("failed to parse `{}`", config_path.display()))?
+            .build_cache
+            .unwrap_or_default(),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => BuildCacheConfig::default(),
+        Err(error) => {
+            return Err(error)
+                .with_context(|| format!("failed to read `{}`", config_path.display()));
+        }
+    };
+
+    let url = match url {
+        Some(url) => Some(
+            url.into_string()
+                .map_err(|_| anyhow::anyhow!("{REMOTE_BUILD_CACHE_ENV} must be valid UTF-8"))?,
+        ),
+        None => config.remote,
+    };
+    let Some(url) = url.filter(|url| url != "off") else {
+        return Ok(None);
+    };
+    let mode = match mode {
+        Some(mode) => mode
+            .to_str()
+            .and_then(RemoteCacheMode::parse)
+            .with_context(|| {
+                format!("{REMOTE_BUILD_CACHE_MODE_ENV} must be `read-write` or `read-only`")
+            })?,
+        None => config.mode.unwrap_or_default(),
+    };
+
+    let location = if let Some(path) = url.strip_prefix("file://") {
+        // `file:///C:/cache` names `C:/cache` on Windows.
+        #[cfg(windows)]
+        let path = path
+            .strip_prefix('/')
+            .filter(|path| path.as_bytes().get(1) == Some(&b':'))
+            .unwrap_or(path);
+        let path = PathBuf::from(path);
+        if !path.is_absolute() {
+            bail!("remote build cache `{url}` must name an absolute directory");
+        }
+        RemoteCacheLocation::Directory(path)
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        RemoteCacheLocation::Http(url.trim_end_matches('/').to_owned())
+    } else {
+        bail!("remote build cache `{url}` must be an http://, https://, or file:// URL");
+    };
+    Ok(Some(RemoteCacheConfig { location, mode }))
+}
+
 fn initialize_ownership_marker(root: &Path, kind: CacheKind) -> anyhow::Result<()> {
     if validate_existing_ownership_marker(root, kind)? {
         return Ok(());
@@ -318,6 +449,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn remote_build_cache_environment_overrides_config() {
+        let home = tempfile::TempDir::new().unwrap();
+        let config = home.path().join("config.json");
+        assert_eq!(
+            resolve_remote_build_cache_from(None, None, &config).unwrap(),
+            None
+        );
+
+        std::fs::write(
+            &config,
+            r#"{
+                // Registry settings may share this file.
+                "registry": "https://mooncakes.io",
+                "build-cache": { "remote": "https://cache.example/moon/", "mode": "read-only" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_remote_build_cache_from(None, None, &config).unwrap(),
+            Some(RemoteCacheConfig {
+                location: RemoteCacheLocation::Http("https://cache.example/moon".into()),
+                mode: RemoteCacheMode::ReadOnly,
+            })
+        );
+
+        let directory = home.path().join("shared");
+        assert_eq!(
+            resolve_remote_build_cache_from(
+                Some(format!("file://{}", directory.display()).into()),
+                Some("read-write".into()),
+                &config,
+            )
+            .unwrap(),
+            Some(RemoteCacheConfig {
+                location: RemoteCacheLocation::Directory(directory),
+                mode: RemoteCacheMode::ReadWrite,
+            })
+        );
+        assert_eq!(
+            resolve_remote_build_cache_from(Some("off".into()), None, &config).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_remote_build_cache_settings() {
+        let home = tempfile::TempDir::new().unwrap();
+        let config = home.path().join("config.json");
+
+        let error =
+            resolve_remote_build_cache_from(Some("ftp://cache".into()), None, &config).unwrap_err();
+        assert!(error.to_string().contains("http://, https://, or file://"));
+
+        let error = resolve_remote_build_cache_from(
+            Some("https://cache.example".into()),
+            Some("write-only".into()),
+            &config,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains(REMOTE_BUILD_CACHE_MODE_ENV));
+
+        let error = resolve_remote_build_cache_from(Some("file://relative".into()), None, &config)
+            .unwrap_err();
+        assert!(error.to_string().contains("absolute directory"));
+    }
+
     #[test]
     fn different_cache_kinds_cannot_claim_the_same_root() {
         let parent = tempfile::TempDir::new().unwrap();
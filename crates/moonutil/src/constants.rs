@@ -48,8 +48,20 @@ pub const MOON_COVERAGE_DELIMITER_END: &str = "----- END MOONBIT COVERAGE -----"
 
 pub const MOON_LOCK: &str = ".moon-lock";
 
+/// The dependency lock file recording a project's resolved module graph. Not
+/// to be confused with the [`MOON_LOCK`] directory mutex.
+pub const MOON_LOCK_FILE: &str = "moon.lock";
+
 pub const DEP_PATH: &str = ".mooncakes";
 
+/// The directory `moon vendor` copies dependency sources into, relative to the
+/// project root.
+pub const MOON_VENDOR_DIR: &str = "vendor";
+
+/// The index of vendored modules inside [`MOON_VENDOR_DIR`]. Builds use the
+/// vendored sources instead of the registry whenever this file exists.
+pub const MOON_VENDOR_MANIFEST: &str = "moon.vendor.json";
+
 pub const BUILD_DIR: &str = "_build";
 
 pub const IGNORE_DIRS: &[&str] = &[BUILD_DIR, ".git", "node_modules", DEP_PATH];
@@ -62,6 +74,8 @@ pub fn is_ignored_directory_name(name: &OsStr) -> bool {
 pub const WATCH_MODE_DIR: &str = "watch";
 
 pub const TEST_INFO_FILE: &str = "test_info.json";
+pub const LAST_TEST_RESULTS_JSON: &str = "last_test_results.json";
+pub const BENCH_BASELINES_DIR: &str = "bench_baselines";
 
 pub const WHITEBOX_TEST_PATCH: &str = "_wbtest.json";
 pub const BLACKBOX_TEST_PATCH: &str = "_test.json";
@@ -119,6 +119,25 @@ pub fn git_init_repo(path: &Path) -> Result<(), GitCommandError> {
     Ok(())
 }
 
+/// Run git in `path` and return its standard output.
+pub fn git_output(path: &Path, args: &[&str]) -> Result<String, GitCommandError> {
+    let args = [&["-C", path.to_str().unwrap()], args].concat();
+    let err = |source| GitCommandError {
+        cmd: format!("git {}", args.join(" ")),
+        source,
+    };
+    let output = git_command(&args, Stdios::npp())?
+        .wait_with_output()
+        .map_err(|e| err(GitCommandErrorKind::IO(e)))?;
+    if !output.status.success() {
+        return Err(err(match output.status.code() {
+            Some(code) => GitCommandErrorKind::ExitStatus(code),
+            None => GitCommandErrorKind::UnknownExitCode,
+        }));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 #[test]
 fn test_bad_git_command() {
     fn fake_git_command(
@@ -41,6 +41,7 @@ pub mod glob;
 pub mod graph;
 pub mod locks;
 pub mod manifest;
+pub mod message_format;
 mod module;
 mod moon_dir;
 pub mod moon_mod_patch;
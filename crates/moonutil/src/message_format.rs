@@ -0,0 +1,444 @@
+// moon: The build system and package manager for MoonBit.
+// Copyright (C) 2024 International Digital Economy Academy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//
+// For inquiries, you can contact us via e-mail at jichuruanjian@idea.edu.cn.
+
+//! Diagnostic output for CI tools, selected by `--message-format`.
+
+use std::{collections::BTreeMap, fmt::Write as _, path::Path};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{error_code_docs::get_error_code_doc, render::MooncDiagnostic};
+
+const ERROR_CODE_DOCS_URL: &str = "https://docs.moonbitlang.com/en/latest/language/error_codes";
+
+/// A machine-readable format to print diagnostics in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// A SARIF 2.1.0 log, for code scanning tools
+    Sarif,
+    /// GitHub Actions workflow commands (`::error file=...::`)
+    Github,
+    /// A GitLab Code Quality report
+    Gitlab,
+    /// One line per diagnostic: `path:line:col: level[code]: message`
+    Short,
+}
+
+impl MessageFormat {
+    /// Format `diagnostics` as one document. Paths under `base` are made
+    /// relative to it, since CI tools resolve them against the checkout.
+    ///
+    /// The output ends with a newline. Line-based formats return an empty
+    /// string when there is nothing to report, while document formats always
+    /// return a (possibly empty) document.
+    pub fn format(self, diagnostics: &[MooncDiagnostic], base: &Path) -> String {
+        match self {
+            MessageFormat::Sarif => sarif(diagnostics, base),
+            MessageFormat::Github => diagnostics
+                .iter()
+                .map(|diagnostic| github(diagnostic, base))
+                .collect(),
+            MessageFormat::Gitlab => gitlab(diagnostics, base),
+            MessageFormat::Short => diagnostics
+                .iter()
+                .map(|diagnostic| short(diagnostic, base))
+                .collect(),
+        }
+    }
+}
+
+fn relative_path(path: &str, base: &Path) -> String {
+    let relative = Path::new(path)
+        .strip_prefix(base)
+        .unwrap_or(Path::new(path));
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn rule_id(diagnostic: &MooncDiagnostic) -> String {
+    format!("E{}", diagnostic.formatted_error_code())
+}
+
+fn help_uri(diagnostic: &MooncDiagnostic) -> Option<String> {
+    get_error_code_doc(&diagnostic.formatted_error_code())
+        .map(|_| format!("{ERROR_CODE_DOCS_URL}/{}.html", rule_id(diagnostic)))
+}
+
+fn is_warning(diagnostic: &MooncDiagnostic) -> bool {
+    matches!(diagnostic.level.as_str(), "warn" | "warning")
+}
+
+fn short(diagnostic: &MooncDiagnostic, base: &Path) -> String {
+    let mut out = String::new();
+    let mut stack = vec![diagnostic];
+    while let Some(diagnostic) = stack.pop() {
+        if !diagnostic.path.is_empty() {
+            let start = &diagnostic.loc.start;
+            let path = relative_path(&diagnostic.path, base);
+            let _ = write!(out, "{path}:{}:{}: ", start.line, start.col);
+        }
+        let _ = writeln!(
+            out,
+            "{}[{}]: {}",
+            diagnostic.level,
+            rule_id(diagnostic),
+            diagnostic.message.replace('\n', " ")
+        );
+        stack.extend(diagnostic.children.iter().rev());
+    }
+    out
+}
+
+/// Escape the message of a workflow command.
+fn github_escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command.
+fn github_escape_property(text: &str) -> String {
+    github_escape_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+fn github(diagnostic: &MooncDiagnostic, base: &Path) -> String {
+    let command = match diagnostic.level.as_str() {
+        "error" => "error",
+        _ if is_warning(diagnostic) => "warning",
+        _ => "notice",
+    };
+    let mut properties = vec![];
+    if !diagnostic.path.is_empty() {
+        let loc = &diagnostic.loc;
+        properties.extend([
+            format!(
+                "file={}",
+                github_escape_property(&relative_path(&diagnostic.path, base))
+            ),
+            format!("line={}", loc.start.line),
+            format!("col={}", loc.start.col),
+            format!("endLine={}", loc.end.line),
+            format!("endColumn={}", loc.end.col),
+        ]);
+    }
+    properties.push(format!("title={}", rule_id(diagnostic)));
+
+    // Annotations have a single location, so related locations become part
+    // of the message.
+    let mut message = diagnostic.message.clone();
+    for child in &diagnostic.children {
+        let _ = write!(message, "\n{}", short(child, base).trim_end());
+    }
+    format!(
+        "::{command} {}::{}\n",
+        properties.join(","),
+        github_escape_data(&message)
+    )
+}
+
+#[derive(Serialize)]
+struct GitlabIssue {
+    description: String,
+    check_name: String,
+    fingerprint: String,
+    severity: &'static str,
+    location: GitlabLocation,
+}
+
+#[derive(Serialize)]
+struct GitlabLocation {
+    path: String,
+    lines: GitlabLines,
+}
+
+#[derive(Serialize)]
+struct GitlabLines {
+    begin: usize,
+}
+
+fn gitlab(diagnostics: &[MooncDiagnostic], base: &Path) -> String {
+    let issues: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let path = relative_path(&diagnostic.path, base);
+            let check_name = rule_id(diagnostic);
+            let fingerprint = fingerprint(&[
+                &path,
+                &diagnostic.loc.start.line.to_string(),
+                &diagnostic.loc.start.col.to_string(),
+                &check_name,
+                &diagnostic.message,
+            ]);
+            GitlabIssue {
+                description: diagnostic.message.clone(),
+                check_name,
+                fingerprint,
+                severity: match diagnostic.level.as_str() {
+                    "error" => "major",
+                    _ if is_warning(diagnostic) => "minor",
+                    _ => "info",
+                },
+                location: GitlabLocation {
+                    path,
+                    lines: GitlabLines {
+                        begin: diagnostic.loc.start.line.max(1),
+                    },
+                },
+            }
+        })
+        .collect();
+    serde_json_lenient::to_string_pretty(&issues).expect("code quality report should serialize")
+        + "\n"
+}
+
+/// A stable FNV-1a hash of `parts`, so that GitLab can track an issue across
+/// pipelines.
+fn fingerprint(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [SarifRun; 1],
+}
+
+#[derive(Serialize)]
+struct SarifRun {
+    tool: SarifTool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRule {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<SarifLocation>,
+}
+
+#[derive(Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<SarifMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    region: SarifRegion,
+}
+
+#[derive(Serialize)]
+struct SarifArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: usize,
+    start_column: usize,
+    end_line: usize,
+    end_column: usize,
+}
+
+fn sarif_location(
+    diagnostic: &MooncDiagnostic,
+    base: &Path,
+    message: Option<SarifMessage>,
+) -> Option<SarifLocation> {
+    if diagnostic.path.is_empty() {
+        return None;
+    }
+    let loc = &diagnostic.loc;
+    Some(SarifLocation {
+        physical_location: SarifPhysicalLocation {
+            artifact_location: SarifArtifactLocation {
+                uri: relative_path(&diagnostic.path, base),
+            },
+            region: SarifRegion {
+                start_line: loc.start.line.max(1),
+                start_column: loc.start.col.max(1),
+                end_line: loc.end.line.max(1),
+                end_column: loc.end.col.max(1),
+            },
+        },
+        message,
+    })
+}
+
+fn sarif(diagnostics: &[MooncDiagnostic], base: &Path) -> String {
+    let mut rule_indices = BTreeMap::new();
+    let mut rules = vec![];
+    let results = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let rule_id = rule_id(diagnostic);
+            let rule_index = *rule_indices.entry(rule_id.clone()).or_insert_with(|| {
+                rules.push(SarifRule {
+                    id: rule_id.clone(),
+                    help_uri: help_uri(diagnostic),
+                });
+                rules.len() - 1
+            });
+            SarifResult {
+                rule_id,
+                rule_index,
+                level: match diagnostic.level.as_str() {
+                    "error" => "error",
+                    _ if is_warning(diagnostic) => "warning",
+                    _ => "note",
+                },
+                message: SarifMessage {
+                    text: diagnostic.message.clone(),
+                },
+                locations: sarif_location(diagnostic, base, None).into_iter().collect(),
+                related_locations: diagnostic
+                    .children
+                    .iter()
+                    .filter_map(|child| {
+                        let message = SarifMessage {
+                            text: child.message.clone(),
+                        };
+                        sarif_location(child, base, Some(message))
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let log = SarifLog {
+        schema: "https://json.schemastore.org/sarif-2.1.0.json",
+        version: "2.1.0",
+        runs: [SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "moon",
+                    information_uri: "https://www.moonbitlang.com",
+                    rules,
+                },
+            },
+            results,
+        }],
+    };
+    serde_json_lenient::to_string_pretty(&log).expect("SARIF log should serialize") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<MooncDiagnostic> {
+        let json = r#"[
+            {"path":"/ws/src/lib/a.mbt","loc":"3:5-3:9","level":"warning","error_code":2,"message":"Unused variable 'x'"},
+            {"path":"/ws/src/lib/b.mbt","loc":"10:1-10:4","level":"error","error_code":4014,"message":"Expr type mismatch,\n has type Int","children":[
+                {"path":"/ws/src/lib/c.mbt","loc":"1:1-1:2","level":"info","error_code":4014,"message":"defined here"}
+            ]}
+        ]"#;
+        serde_json_lenient::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn short_and_github_formats() {
+        let base = Path::new("/ws");
+        expect_test::expect![[r#"
+            src/lib/a.mbt:3:5: warning[E0002]: Unused variable 'x'
+            src/lib/b.mbt:10:1: error[E4014]: Expr type mismatch,  has type Int
+            src/lib/c.mbt:1:1: info[E4014]: defined here
+        "#]]
+        .assert_eq(&MessageFormat::Short.format(&example(), base));
+        expect_test::expect![[r#"
+            ::warning file=src/lib/a.mbt,line=3,col=5,endLine=3,endColumn=9,title=E0002::Unused variable 'x'
+            ::error file=src/lib/b.mbt,line=10,col=1,endLine=10,endColumn=4,title=E4014::Expr type mismatch,%0A has type Int%0Asrc/lib/c.mbt:1:1: info[E4014]: defined here
+        "#]]
+        .assert_eq(&MessageFormat::Github.format(&example(), base));
+    }
+
+    #[test]
+    fn sarif_and_gitlab_formats() {
+        let base = Path::new("/ws");
+        let sarif: serde_json_lenient::Value =
+            serde_json_lenient::from_str(&MessageFormat::Sarif.format(&example(), base)).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(sarif["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"][1]["id"], "E4014");
+        assert_eq!(run["results"][1]["level"], "error");
+        assert_eq!(run["results"][1]["ruleIndex"], 1);
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/lib/b.mbt"
+        );
+        assert_eq!(
+            run["results"][1]["relatedLocations"][0]["message"]["text"],
+            "defined here"
+        );
+
+        let gitlab: serde_json_lenient::Value =
+            serde_json_lenient::from_str(&MessageFormat::Gitlab.format(&example(), base)).unwrap();
+        assert_eq!(gitlab[0]["severity"], "minor");
+        assert_eq!(gitlab[1]["severity"], "major");
+        assert_eq!(gitlab[1]["location"]["lines"]["begin"], 10);
+        assert_ne!(gitlab[0]["fingerprint"], gitlab[1]["fingerprint"]);
+    }
+}
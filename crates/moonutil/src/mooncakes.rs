@@ -582,6 +582,12 @@ pub mod sync {
         /// Do not sync dependencies, assuming local dependencies are up-to-date
         #[clap(long)]
         pub frozen: bool,
+
+        /// Require `moon.lock` to match the resolved dependencies, failing
+        /// instead of updating it
+        #[clap(long)]
+        #[serde(default)]
+        pub locked: bool,
     }
 
     impl AutoSyncFlags {
@@ -599,12 +605,37 @@ pub struct Credentials {
     pub username: Option<String>,
 }
 
+const DEFAULT_REGISTRY: &str = "https://mooncakes.io";
+const DEFAULT_REGISTRY_INDEX: &str = "https://mooncakes.io/git/index";
+
+/// The registry configuration in `$MOON_HOME/config.json`.
+///
+/// `registry` is either a hosted registry URL or a local registry directory
+/// (a `file://` URL or an absolute path) holding `index/` and `user/` trees.
 #[derive(Debug, Deserialize)]
 pub struct RegistryConfig {
+    #[serde(default = "default_registry")]
     pub registry: String,
+    #[serde(default = "default_registry_index")]
     pub index: String,
     #[serde(default)]
     pub symbols: Option<String>,
+    /// Additional locations serving package archives, tried in order before
+    /// `registry`. Each is a URL or a local registry directory.
+    ///
+    /// When the index cannot be synced from `index`, the mirrors are tried in
+    /// order for it too: a hosted mirror serves its Git index at `git/index`,
+    /// like the registry itself, and the `index/` of a local one is copied.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+}
+
+fn default_registry() -> String {
+    DEFAULT_REGISTRY.into()
+}
+
+fn default_registry_index() -> String {
+    DEFAULT_REGISTRY_INDEX.into()
 }
 
 impl RegistryConfig {
@@ -614,12 +645,14 @@ impl RegistryConfig {
                 index: format!("{v}/git/index"),
                 symbols: Some(format!("{v}/symbols.zip")),
                 registry: v,
+                mirrors: Vec::new(),
             }
         } else {
             RegistryConfig {
-                registry: "https://mooncakes.io".into(),
-                index: "https://mooncakes.io/git/index".into(),
+                registry: default_registry(),
+                index: default_registry_index(),
                 symbols: None,
+                mirrors: Vec::new(),
             }
         }
     }
@@ -312,6 +312,13 @@ pub struct MoonPkgJSON {
     #[schemars(rename = "max-concurrent-tests")]
     pub max_concurrent_tests: Option<u32>,
 
+    /// Seconds a test executable of this package may run before it is killed.
+    /// Overridden by `moon test --timeout`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "test-timeout")]
+    #[schemars(rename = "test-timeout", range(min = 1))]
+    pub test_timeout: Option<u64>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(alias = "regex-backend")]
     #[schemars(rename = "regex-backend")]
@@ -733,6 +740,7 @@ pub struct MoonPkg {
     pub overrides: Option<Vec<String>>,
 
     pub max_concurrent_tests: Option<u32>,
+    pub test_timeout: Option<u64>,
 
     pub regex_backend: Option<RegexBackend>,
 
@@ -1025,6 +1033,12 @@ pub fn convert_pkg_json_to_package_with_supported_targets_decl(
         }
     }
 
+    // Like `moon test --timeout`, a timeout of 0 would kill every test
+    // executable right away.
+    if j.test_timeout == Some(0) {
+        bail!("`test-timeout` in moon.pkg must be at least 1 second.");
+    }
+
     let result = MoonPkg {
         name: None,
         is_main,
@@ -1051,6 +1065,7 @@ pub fn convert_pkg_json_to_package_with_supported_targets_decl(
         implement: j.implement,
         overrides: j.overrides,
         max_concurrent_tests: j.max_concurrent_tests,
+        test_timeout: j.test_timeout,
         regex_backend: j.regex_backend,
         local_rules: j.rule,
     };
@@ -1301,6 +1316,20 @@ options(
     );
 }
 
+#[test]
+fn convert_pkg_json_rejects_zero_test_timeout() {
+    let json: MoonPkgJSON = serde_json_lenient::from_str(r#"{ "test-timeout": 0 }"#).unwrap();
+    let err = convert_test_pkg_json(json, true).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("`test-timeout` in moon.pkg must be at least 1 second.")
+    );
+
+    let json: MoonPkgJSON = serde_json_lenient::from_str(r#"{ "test-timeout": 1 }"#).unwrap();
+    let (pkg, _) = convert_test_pkg_json(json, true).unwrap();
+    assert_eq!(pkg.test_timeout, Some(1));
+}
+
 #[test]
 fn convert_pkg_json_supports_proof_enabled_hyphenated() {
     let json: MoonPkgJSON = serde_json_lenient::from_str(
@@ -104,6 +104,17 @@ impl MbtTestInfo {
     pub fn has_skip(&self) -> bool {
         self.attrs.iter().any(|attr| attr.starts_with("#skip"))
     }
+
+    /// The tags given by `#tag("slow", "network")` attributes, which
+    /// `moon test --tag` selects tests by.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.attrs
+            .iter()
+            .filter_map(|attr| attr.trim().strip_prefix("#tag(")?.strip_suffix(')'))
+            .flat_map(|args| args.split(','))
+            .map(|tag| tag.trim().trim_matches('"'))
+            .filter(|tag| !tag.is_empty())
+    }
 }
 
 impl MooncGenTestInfo {
@@ -185,4 +196,25 @@ mod tests {
                 vec![],
             )])));
     }
+
+    #[test]
+    fn tags_come_from_tag_attributes() {
+        let test = MbtTestInfo {
+            index: 0,
+            func: "__test_0".into(),
+            name: None,
+            line_number: None,
+            attrs: vec![
+                "#skip(\"flaky\")".into(),
+                "#tag(\"slow\", \"network\")".into(),
+                " #tag(\"integration\") ".into(),
+                "#tag()".into(),
+            ],
+        };
+
+        assert_eq!(
+            test.tags().collect::<Vec<_>>(),
+            ["slow", "network", "integration"]
+        );
+    }
 }
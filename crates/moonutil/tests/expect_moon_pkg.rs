@@ -110,6 +110,7 @@ fn expect_import() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -152,6 +153,7 @@ fn expect_is_main() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -198,6 +200,7 @@ fn expect_formatter() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -247,6 +250,7 @@ fn expect_supported_targets() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -287,6 +291,7 @@ fn expect_supported_targets_expr() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -327,6 +332,7 @@ fn expect_supported_targets_expr_with_wasm_gc() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -362,6 +368,7 @@ fn expect_supported_targets_expr_with_wasm_gc() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -438,6 +445,7 @@ fn expect_build() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -485,6 +493,7 @@ fn expect_warnings() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -526,6 +535,7 @@ fn expect_supported_targets_prefers_new_config() {
             implement: None,
             overrides: None,
             max_concurrent_tests: None,
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -753,6 +763,7 @@ fn expect_options() {
             max_concurrent_tests: Some(
                 4,
             ),
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]
@@ -799,6 +810,7 @@ fn expect_max_concurrent_tests() {
             max_concurrent_tests: Some(
                 1,
             ),
+            test_timeout: None,
             regex_backend: None,
             local_rules: None,
         }"#]]